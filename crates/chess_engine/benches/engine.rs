@@ -0,0 +1,90 @@
+use std::hint::black_box;
+
+use chess_engine::{MoveList, Position};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// The opening, a busy middlegame with every kind of move available, and a sparse endgame.
+const POSITIONS: [(&str, &str); 3] = [
+    (
+        "start",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+fn positions() -> impl Iterator<Item = (&'static str, Position)> {
+    POSITIONS.into_iter().map(|(name, fen)| {
+        let mut position = Position::default();
+        position.set_fen(fen).unwrap();
+        (name, position)
+    })
+}
+
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(20);
+
+    for (name, position) in positions() {
+        group.bench_function(name, |b| b.iter(|| black_box(&position).perft(3)));
+    }
+
+    group.finish();
+}
+
+fn move_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("move generation");
+
+    for (name, position) in positions() {
+        group.bench_function(format!("{} pseudo-legal", name), |b| {
+            b.iter(|| {
+                let mut moves = MoveList::new();
+                black_box(&position).generate_moves(position.side_to_move(), &mut moves);
+                moves
+            })
+        });
+
+        group.bench_function(format!("{} legal", name), |b| {
+            b.iter(|| black_box(&position).legal_moves())
+        });
+    }
+
+    group.finish();
+}
+
+// Positions are copied before each move rather than unmade afterwards, so this measures both.
+fn make_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make move");
+
+    for (name, position) in positions() {
+        let moves = position.legal_moves();
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for &piece_move in &moves {
+                    let mut child = black_box(&position).clone();
+                    child.make_move(piece_move);
+                    black_box(&child);
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluation");
+
+    for (name, position) in positions() {
+        group.bench_function(name, |b| b.iter(|| black_box(&position).evaluate()));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, perft, move_generation, make_move, evaluation);
+criterion_main!(benches);
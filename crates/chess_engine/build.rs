@@ -0,0 +1,243 @@
+// Generates the magic bitboard tables used to look up rook and bishop attacks, so that they are
+// built once at compile time rather than each time the engine starts. The magic numbers are found
+// by trying random candidates from a fixed seed, so the output is the same on every build. A second
+// table, indexed by the PEXT instruction instead of magic multiplication, is generated alongside.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+// A xorshift generator, used only to propose magic numbers.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // Magic numbers with few bits set are far more likely to work.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+// The squares attacked from `square` by sliding in each direction until the edge of the board or
+// the first occupied square. With `relevant_only`, the last square of each ray is left out, as a
+// piece there can't block anything further; this gives the mask of occupancy bits which matter.
+fn sliding_attacks(
+    square: usize,
+    occupied: u64,
+    directions: &[(i32, i32)],
+    relevant_only: bool,
+) -> u64 {
+    let mut attacks = 0;
+
+    for &(file_step, rank_step) in directions {
+        let mut file = (square % 8) as i32 + file_step;
+        let mut rank = (square / 8) as i32 + rank_step;
+
+        while (0..8).contains(&file) && (0..8).contains(&rank) {
+            let next_on_board =
+                (0..8).contains(&(file + file_step)) && (0..8).contains(&(rank + rank_step));
+
+            if relevant_only && !next_on_board {
+                break;
+            }
+
+            let bit = 1 << (rank * 8 + file);
+            attacks |= bit;
+
+            if occupied & bit != 0 {
+                break;
+            }
+
+            file += file_step;
+            rank += rank_step;
+        }
+    }
+
+    attacks
+}
+
+// Extracts the bits of `value` selected by `mask` into the low bits of the result, as the BMI2
+// PEXT instruction does.
+fn pext(value: u64, mask: u64) -> u64 {
+    let mut result = 0;
+    let mut remaining = mask;
+    let mut bit = 0;
+
+    while remaining != 0 {
+        let lowest = remaining & remaining.wrapping_neg();
+
+        if value & lowest != 0 {
+            result |= 1 << bit;
+        }
+
+        remaining &= remaining - 1;
+        bit += 1;
+    }
+
+    result
+}
+
+// Finds a magic number for one square, appending its attack tables to `attacks` and
+// `pext_attacks`. Both tables have the same size for each square, so share the offset.
+fn find_magic(
+    square: usize,
+    directions: &[(i32, i32)],
+    rng: &mut Rng,
+    attacks: &mut Vec<u64>,
+    pext_attacks: &mut Vec<u64>,
+) -> Magic {
+    let mask = sliding_attacks(square, 0, directions, true);
+    let bits = mask.count_ones();
+    let shift = 64 - bits;
+
+    // Every subset of the mask, enumerated with the carry-rippler trick, and its attacks.
+    let mut occupancies = vec![];
+    let mut subset: u64 = 0;
+
+    loop {
+        occupancies.push((subset, sliding_attacks(square, subset, directions, false)));
+        subset = subset.wrapping_sub(mask) & mask;
+
+        if subset == 0 {
+            break;
+        }
+    }
+
+    let mut table = vec![0; 1 << bits];
+
+    loop {
+        let magic = rng.sparse();
+
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        let mut used = vec![false; 1 << bits];
+
+        let found = occupancies.iter().all(|&(occupied, square_attacks)| {
+            let index = (occupied.wrapping_mul(magic) >> shift) as usize;
+
+            if !used[index] {
+                used[index] = true;
+                table[index] = square_attacks;
+                true
+            } else {
+                table[index] == square_attacks
+            }
+        });
+
+        if found {
+            let offset = attacks.len();
+            attacks.extend_from_slice(&table);
+
+            pext_attacks.resize(offset + table.len(), 0);
+
+            for &(occupied, square_attacks) in &occupancies {
+                pext_attacks[offset + pext(occupied, mask) as usize] = square_attacks;
+            }
+
+            return Magic {
+                mask,
+                magic,
+                shift,
+                offset,
+            };
+        }
+    }
+}
+
+fn write_table(out: &mut String, name: &str, table: &[u64]) {
+    writeln!(
+        out,
+        "pub(crate) static {}: [Bitboard; {}] = [",
+        name,
+        table.len()
+    )
+    .unwrap();
+
+    for entry in table {
+        writeln!(out, "    {:#018x},", entry).unwrap();
+    }
+
+    writeln!(out, "];").unwrap();
+}
+
+fn write_magics(out: &mut String, name: &str, magics: &[Magic]) {
+    writeln!(out, "pub(crate) static {}: [Magic; 64] = [", name).unwrap();
+
+    for magic in magics {
+        writeln!(
+            out,
+            "    Magic {{ mask: {:#018x}, magic: {:#018x}, shift: {}, offset: {} }},",
+            magic.mask, magic.magic, magic.shift, magic.offset
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "];").unwrap();
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+    let mut attacks = vec![];
+    let mut pext_attacks = vec![];
+
+    let rook_magics: Vec<Magic> = (0..64)
+        .map(|square| {
+            find_magic(
+                square,
+                &ROOK_DIRECTIONS,
+                &mut rng,
+                &mut attacks,
+                &mut pext_attacks,
+            )
+        })
+        .collect();
+    let bishop_magics: Vec<Magic> = (0..64)
+        .map(|square| {
+            find_magic(
+                square,
+                &BISHOP_DIRECTIONS,
+                &mut rng,
+                &mut attacks,
+                &mut pext_attacks,
+            )
+        })
+        .collect();
+
+    let mut out = String::new();
+    write_magics(&mut out, "ROOK_MAGICS", &rook_magics);
+    write_magics(&mut out, "BISHOP_MAGICS", &bishop_magics);
+
+    write_table(&mut out, "SLIDING_ATTACKS", &attacks);
+
+    // PEXT is only available on x86-64, and only used where std can check the CPU supports it.
+    writeln!(
+        out,
+        "#[cfg(all(feature = \"std\", target_arch = \"x86_64\"))]"
+    )
+    .unwrap();
+    write_table(&mut out, "PEXT_ATTACKS", &pext_attacks);
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("magics.rs");
+    fs::write(path, out).unwrap();
+}
@@ -0,0 +1,23 @@
+// Reads arbitrary text as an EPD record. A record accepted must leave a position the engine can
+// work with, and writing it back with its operations must read again.
+
+#![no_main]
+
+use chess_engine::Engine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|epd: &str| {
+    let mut engine = Engine::default();
+
+    let Ok(operations) = engine.set_position_from_epd(epd) else {
+        return;
+    };
+
+    let _ = engine.legal_moves();
+
+    let written = engine.to_epd(&operations);
+    let mut parsed = Engine::default();
+    parsed
+        .set_position_from_epd(&written)
+        .expect("the engine's own EPD reads back");
+});
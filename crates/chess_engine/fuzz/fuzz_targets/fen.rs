@@ -0,0 +1,39 @@
+// Reads arbitrary text as FEN. Whatever is accepted must be a position the engine can work with:
+// the FEN it writes back must read as the same position, and each legal move must leave a
+// consistent position whose FEN reads back too. Positions are copied rather than unmade, so each
+// move is played on a copy.
+
+#![no_main]
+
+use chess_engine::{check_invariants, Position};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|fen: &str| {
+    let mut position = Position::default();
+
+    if position.set_fen(fen).is_err() {
+        return;
+    }
+
+    let _ = position.evaluate();
+
+    if let Err(error) = check_invariants(&position) {
+        panic!("{}: {}", position.fen(), error);
+    }
+
+    for piece_move in position.legal_moves() {
+        let mut child = position.clone();
+        child.make_move(piece_move);
+
+        let mut parsed = child.clone();
+        parsed
+            .set_fen(&child.fen())
+            .expect("the engine's own FEN reads back");
+        assert!(
+            parsed == child,
+            "{} after {} doesn't round-trip",
+            child.fen(),
+            piece_move
+        );
+    }
+});
@@ -0,0 +1,33 @@
+// Reads arbitrary text as PGN. Each game read must have a main line of legal moves, and writing it
+// back as PGN must read again as a game with the same moves.
+
+#![no_main]
+
+use chess_engine::parse_pgn;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|pgn: &str| {
+    let Ok(games) = parse_pgn(pgn) else {
+        return;
+    };
+
+    for game in games {
+        let mut engine = game.start().clone();
+
+        for piece_move in game.moves() {
+            assert!(
+                engine.legal_moves().contains(&piece_move),
+                "{} isn't legal in {}",
+                piece_move,
+                engine.fen()
+            );
+            engine.make_move(piece_move);
+        }
+
+        let written = game.to_pgn();
+        let reparsed = parse_pgn(&written).expect("the engine's own PGN reads back");
+
+        assert_eq!(reparsed.len(), 1, "{} doesn't read as one game", written);
+        assert_eq!(reparsed[0].moves(), game.moves(), "{} doesn't round-trip", written);
+    }
+});
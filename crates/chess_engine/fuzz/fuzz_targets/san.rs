@@ -0,0 +1,33 @@
+// Reads arbitrary text as SAN in a few positions with castling, en passant and promotions to
+// misread. A move read must be legal, and writing it back as SAN must read as the same move.
+
+#![no_main]
+
+use chess_engine::Position;
+use libfuzzer_sys::fuzz_target;
+
+const FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+];
+
+fuzz_target!(|san: &str| {
+    for fen in FENS {
+        let mut position = Position::default();
+        position.set_fen(fen).unwrap();
+
+        let Some(piece_move) = position.move_from_san(san) else {
+            continue;
+        };
+
+        assert!(position.legal_moves().contains(&piece_move));
+        assert_eq!(
+            position.move_from_san(&position.move_to_san(piece_move)),
+            Some(piece_move)
+        );
+
+        position.make_move(piece_move);
+    }
+});
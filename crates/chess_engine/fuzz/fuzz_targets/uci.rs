@@ -0,0 +1,26 @@
+// Feeds arbitrary text to the UCI handler a line at a time, as a GUI would. No command may panic,
+// whatever state earlier commands left the handler in.
+
+#![no_main]
+
+use chess_engine::uci::UciHandler;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let mut uci = UciHandler::new(Vec::new());
+    uci.handle_line("uci");
+
+    for line in input.lines() {
+        // The benchmark runs in the handler's own thread and takes far too long to fuzz.
+        if line.trim() == "bench" {
+            continue;
+        }
+
+        if !uci.handle_line(line) {
+            return;
+        }
+    }
+
+    // Stops any search still running, such as after `go infinite`.
+    uci.handle_line("quit");
+});
@@ -0,0 +1,163 @@
+use crate::pawns::{adjacent_files, ranks_ahead};
+use crate::{
+    bishop_attacks, pawn_east_attacks, pawn_west_attacks, rook_attacks, Bitboard, BitboardOps,
+    Board, PieceType, Side, FILES,
+};
+
+// Mobility weights per safe square as (middlegame, endgame) pairs, in centipawns, and the number
+// of safe squares a piece typically has. Pieces with more squares than usual get a bonus, and
+// those with fewer a penalty.
+const KNIGHT_MOBILITY: (i32, i32) = (4, 4);
+const BISHOP_MOBILITY: (i32, i32) = (5, 5);
+const ROOK_MOBILITY: (i32, i32) = (2, 4);
+const QUEEN_MOBILITY: (i32, i32) = (1, 2);
+const TYPICAL_MOBILITY: [i32; 6] = [0, 4, 6, 7, 13, 0];
+
+// Piece activity terms as (middlegame, endgame) pairs, in centipawns.
+const ROOK_OPEN_FILE: (i32, i32) = (25, 10);
+const ROOK_SEMI_OPEN_FILE: (i32, i32) = (12, 6);
+const BISHOP_LONG_DIAGONAL: (i32, i32) = (12, 4);
+const KNIGHT_OUTPOST: (i32, i32) = (20, 10);
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+
+// The a1-h8 and h1-a8 diagonals.
+const LONG_DIAGONALS: Bitboard = 0x8040201008040201 | 0x0102040810204080;
+
+// Returns the squares attacked by the given side's pawns.
+fn pawn_attacks(board: &Board, side: Side) -> Bitboard {
+    let pawns = board.bitboard_by_piece[PieceType::Pawn.val()] & board.bitboard_by_side[side.val()];
+
+    pawn_east_attacks(pawns, !0, side) | pawn_west_attacks(pawns, !0, side)
+}
+
+/// Evaluates the mobility of a side's knights, bishops, rooks and queens, counting the squares
+/// each attacks which are neither occupied by a friendly piece nor attacked by an enemy pawn.
+/// Returns the middlegame and endgame scores, in centipawns from that side's perspective.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{evaluate_mobility, Engine, Side};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// // Both sides are equally cramped in the initial position.
+/// assert_eq!(
+///     evaluate_mobility(engine.board(), Side::White),
+///     evaluate_mobility(engine.board(), Side::Black)
+/// );
+/// ```
+pub fn evaluate_mobility(board: &Board, side: Side) -> (i32, i32) {
+    let occupied = board.occupied();
+    let safe_squares = !board.bitboard_by_side[side.val()] & !pawn_attacks(board, side.flip());
+
+    let mut middlegame = 0;
+    let mut endgame = 0;
+
+    for (piece_type, weight) in [
+        (PieceType::Knight, KNIGHT_MOBILITY),
+        (PieceType::Bishop, BISHOP_MOBILITY),
+        (PieceType::Rook, ROOK_MOBILITY),
+        (PieceType::Queen, QUEEN_MOBILITY),
+    ] {
+        let pieces = board.bitboard_by_piece[piece_type.val()] & board.bitboard_by_side[side.val()];
+
+        for piece in pieces.iter() {
+            let square = piece.trailing_zeros() as usize;
+
+            let attacks = match piece_type {
+                PieceType::Knight => board.knight_attacks(square),
+                PieceType::Bishop => bishop_attacks(square, occupied),
+                PieceType::Rook => rook_attacks(square, occupied),
+                _ => bishop_attacks(square, occupied) | rook_attacks(square, occupied),
+            };
+
+            let mobility =
+                (attacks & safe_squares).count_ones() as i32 - TYPICAL_MOBILITY[piece_type.val()];
+
+            middlegame += weight.0 * mobility;
+            endgame += weight.1 * mobility;
+        }
+    }
+
+    (middlegame, endgame)
+}
+
+/// Evaluates how well a side's pieces are placed: rooks on open and semi-open files, bishops on
+/// the long diagonals, knights on outposts, and the bishop pair. Returns the middlegame and
+/// endgame scores, in centipawns from that side's perspective.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{evaluate_piece_activity, Engine, Side};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// assert_eq!(
+///     evaluate_piece_activity(engine.board(), Side::White),
+///     evaluate_piece_activity(engine.board(), Side::Black)
+/// );
+/// ```
+pub fn evaluate_piece_activity(board: &Board, side: Side) -> (i32, i32) {
+    let all_pawns = board.bitboard_by_piece[PieceType::Pawn.val()];
+
+    let mut middlegame = 0;
+    let mut endgame = 0;
+
+    let mut add = |(term_middlegame, term_endgame): (i32, i32)| {
+        middlegame += term_middlegame;
+        endgame += term_endgame;
+    };
+
+    let our_pieces = board.bitboard_by_side[side.val()];
+    let our_pawns = all_pawns & our_pieces;
+    let enemy_pawns = all_pawns & !our_pieces;
+
+    let rooks = board.bitboard_by_piece[PieceType::Rook.val()] & our_pieces;
+
+    for rook in rooks.iter() {
+        let file = FILES[rook.trailing_zeros() as usize % 8];
+
+        if all_pawns & file == 0 {
+            add(ROOK_OPEN_FILE);
+        } else if our_pawns & file == 0 {
+            add(ROOK_SEMI_OPEN_FILE);
+        }
+    }
+
+    let bishops = board.bitboard_by_piece[PieceType::Bishop.val()] & our_pieces;
+
+    for _ in (bishops & LONG_DIAGONALS).iter() {
+        add(BISHOP_LONG_DIAGONAL);
+    }
+
+    if bishops.count_ones() >= 2 {
+        add(BISHOP_PAIR);
+    }
+
+    // An outpost is a square in the enemy half, defended by a pawn, which no enemy pawn can ever
+    // attack.
+    let our_pawn_attacks = pawn_attacks(board, side);
+    let knights = board.bitboard_by_piece[PieceType::Knight.val()] & our_pieces;
+
+    for knight in (knights & our_pawn_attacks).iter() {
+        let square = knight.trailing_zeros() as usize;
+        let (file, rank) = (square % 8, square / 8);
+
+        let advanced = match side {
+            Side::White => rank,
+            _ => 7 - rank,
+        };
+
+        if (3..=5).contains(&advanced)
+            && enemy_pawns & adjacent_files(file) & ranks_ahead(rank, side) == 0
+        {
+            add(KNIGHT_OUTPOST);
+        }
+    }
+
+    (middlegame, endgame)
+}
@@ -0,0 +1,71 @@
+use std::cmp::Reverse;
+
+use crate::{Engine, Move, Score, Search, SearchLimits};
+
+impl Engine {
+    /// Searches every legal move in the current position separately, each within the given
+    /// limits, and returns each move with its score and principal variation, best first. Moves
+    /// which score the same keep the order they were generated in. If the limits give search
+    /// moves, only those are analysed.
+    ///
+    /// As with `Search::best_move_in_game`, returning to a position from earlier in the game is
+    /// scored as a draw.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Score, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    ///
+    /// let limits = SearchLimits {
+    ///     depth: Some(3),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let analysis = engine.analyze_root(&limits);
+    /// assert_eq!(analysis.len(), engine.legal_moves().len());
+    ///
+    /// // Only the back rank mate wins outright.
+    /// let (best_move, score, pv) = &analysis[0];
+    /// assert_eq!(best_move.to_string(), "a1a8");
+    /// assert_eq!(*score, Score::Mate(1));
+    /// assert_eq!(pv[0], *best_move);
+    ///
+    /// assert!(analysis[1..].iter().all(|(_, score, _)| !matches!(score, Score::Mate(_))));
+    /// ```
+    pub fn analyze_root(&self, limits: &SearchLimits) -> Vec<(Move, Score, Vec<Move>)> {
+        let mut search = Search::new();
+        let mut analysis = vec![];
+
+        for piece_move in self.legal_moves() {
+            // Search moves are compared as the search compares them, by their squares and
+            // promotion only.
+            let is_search_move = limits.search_moves.iter().any(|search_move| {
+                search_move.from == piece_move.from
+                    && search_move.to == piece_move.to
+                    && search_move.promote == piece_move.promote
+            });
+
+            if !limits.search_moves.is_empty() && !is_search_move {
+                continue;
+            }
+
+            let move_limits = SearchLimits {
+                search_moves: vec![piece_move],
+                ..limits.clone()
+            };
+
+            let result = search.best_move_in_game(self, &move_limits);
+            analysis.push((piece_move, result.score, result.pv));
+        }
+
+        analysis.sort_by_key(|(_, score, _)| Reverse(*score));
+
+        analysis
+            .into_iter()
+            .map(|(piece_move, score, pv)| (piece_move, Score::from_search_score(score), pv))
+            .collect()
+    }
+}
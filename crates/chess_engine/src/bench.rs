@@ -0,0 +1,145 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::time::Instant;
+use crate::{Position, Search, SearchLimits};
+
+/// The depth the benchmark positions are searched to, unless another is given.
+pub const DEFAULT_BENCH_DEPTH: u32 = 8;
+
+// A spread of positions from the opening to the endgame, including the standard perft test
+// positions, which exercise castling, en passant and promotions.
+const BENCH_POSITIONS: [&str; 10] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "2r3k1/pp3ppp/4p3/3pP3/3P4/P4N2/1P3PPP/2R3K1 b - - 0 24",
+    "8/5pk1/6p1/3R4/7P/6P1/r4PK1/8 w - - 0 40",
+    "8/8/4k3/8/2p5/2P5/4K3/8 w - - 0 60",
+];
+
+/// A position which is evaluated differently from its mirror image, as found by
+/// `check_eval_symmetry`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvalAsymmetry {
+    pub fen: String,
+    pub score: i32,
+    pub mirrored_score: i32,
+}
+
+/// The total work done by a benchmark run.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+impl BenchResult {
+    /// The nodes searched per second.
+    pub fn nps(&self) -> u64 {
+        (self.nodes as u128 * 1000 / self.time.as_millis().max(1)) as u64
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} nodes {} nps", self.nodes, self.nps())
+    }
+}
+
+/// Searches a fixed set of positions to the given depth, returning the total nodes searched and
+/// the time taken. The search is reset before each position, so the node count depends only on
+/// the search itself, and changes only when its behaviour does; this makes it a quick check of
+/// whether a change was meant to be purely a speed up.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::run_bench;
+///
+/// let result = run_bench(3);
+/// assert!(result.nodes > 0);
+/// assert_eq!(run_bench(3).nodes, result.nodes);
+/// ```
+pub fn run_bench(depth: u32) -> BenchResult {
+    let mut search = Search::new();
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..Default::default()
+    };
+
+    let mut result = BenchResult {
+        nodes: 0,
+        time: Duration::ZERO,
+    };
+
+    for fen in BENCH_POSITIONS {
+        let mut position = Position::default();
+        position
+            .set_fen(fen)
+            .expect("bench positions should be valid");
+
+        search.new_game();
+
+        let start = Instant::now();
+        result.nodes += search.best_move(&position, &limits).nodes;
+        result.time += start.elapsed();
+    }
+
+    result
+}
+
+/// Evaluates every position within the given number of plies of the benchmark positions, along
+/// with its mirror image, and returns those which score differently. The evaluation shouldn't
+/// depend on which colour the side to move has, so any asymmetry found is a bug.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::check_eval_symmetry;
+///
+/// assert_eq!(check_eval_symmetry(1), []);
+/// ```
+pub fn check_eval_symmetry(depth: u32) -> Vec<EvalAsymmetry> {
+    let mut asymmetries = vec![];
+
+    for fen in BENCH_POSITIONS {
+        let mut position = Position::default();
+        position
+            .set_fen(fen)
+            .expect("bench positions should be valid");
+
+        find_asymmetries(&position, depth, &mut asymmetries);
+    }
+
+    asymmetries
+}
+
+// Checks the position and everything within `depth` plies of it, adding any asymmetries found.
+fn find_asymmetries(position: &Position, depth: u32, asymmetries: &mut Vec<EvalAsymmetry>) {
+    let score = position.evaluate();
+    let mirrored_score = position.mirrored().evaluate();
+
+    if score != mirrored_score {
+        asymmetries.push(EvalAsymmetry {
+            fen: position.fen(),
+            score,
+            mirrored_score,
+        });
+    }
+
+    if depth == 0 {
+        return;
+    }
+
+    for piece_move in position.legal_moves() {
+        let mut child = position.clone();
+        child.make_move(piece_move);
+
+        find_asymmetries(&child, depth - 1, asymmetries);
+    }
+}
@@ -1,11 +1,54 @@
-use crate::{Move, PieceType, Side};
+use alloc::string::{String, ToString};
+
+use crate::magic::{BISHOP_MAGICS, ROOK_MAGICS};
+use crate::{
+    Move, MoveKind, MoveList, ParseSquareError, PieceType, Side, Square, MIDDLEGAME_VALUES,
+};
 
 pub use u64 as Bitboard;
 
+/// Builds a bitboard from square names separated by whitespace, such as `bb!("e4 e5 d4")`, which
+/// is easier to write and check in tests than a hex constant. Panics if a name isn't a square.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{bb, RANK_1};
+///
+/// assert_eq!(bb!("a1 b1 c1 d1 e1 f1 g1 h1"), RANK_1);
+/// assert_eq!(bb!("e4 d5"), 0x0000000810000000);
+/// assert_eq!(bb!(""), 0);
+/// ```
+#[macro_export]
+macro_rules! bb {
+    ($squares:expr) => {
+        $crate::bitboard_from_squares($squares).expect("bb! expects square names like e4")
+    };
+}
+
+/// Returns the bitboard of the squares named in the string, separated by whitespace, or an error
+/// naming the first word which isn't a square.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::bitboard_from_squares;
+///
+/// assert_eq!(bitboard_from_squares("a1 h8"), Ok(0x8000000000000001));
+/// assert!(bitboard_from_squares("a1 i9").is_err());
+/// ```
+pub fn bitboard_from_squares(squares: &str) -> Result<Bitboard, ParseSquareError> {
+    squares.split_whitespace().try_fold(0, |bitboard, name| {
+        Ok(bitboard | name.parse::<Square>()?.bitboard())
+    })
+}
+
 pub trait BitboardOps {
     fn get_ls1b(&self) -> Self;
     fn remove_ls1b(&self) -> Self;
     fn iter(&self) -> BitboardIterator;
+    fn to_board_string(&self) -> String;
+    fn shift(&self, direction: Direction) -> Self;
 }
 
 impl BitboardOps for Bitboard {
@@ -40,6 +83,56 @@ impl BitboardOps for Bitboard {
     fn iter(&self) -> BitboardIterator {
         BitboardIterator { current: *self }
     }
+
+    /// Draws the bitboard as a grid seen from White's side, with `x` for set squares and `.` for
+    /// the rest, for printing while debugging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{bb, BitboardOps};
+    ///
+    /// let diagram = bb!("a8 e4 h1").to_board_string();
+    ///
+    /// assert!(diagram.starts_with("8 x . . . . . . .\n"));
+    /// assert!(diagram.contains("4 . . . . x . . .\n"));
+    /// assert!(diagram.ends_with("1 . . . . . . . x\n  a b c d e f g h"));
+    /// ```
+    fn to_board_string(&self) -> String {
+        let mut out = String::new();
+
+        for rank in (0..8).rev() {
+            out += &(rank + 1).to_string();
+
+            for file in 0..8 {
+                out += if self & (1 << (rank * 8 + file)) != 0 {
+                    " x"
+                } else {
+                    " ."
+                };
+            }
+
+            out += "\n";
+        }
+
+        out += "  a b c d e f g h";
+
+        out
+    }
+
+    /// Moves every set square one step in the given direction, as `bb_shift` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{bb, BitboardOps, Direction};
+    ///
+    /// assert_eq!(bb!("e4 a1").shift(Direction::N), bb!("e5 a2"));
+    /// assert_eq!(bb!("e4 a1").shift(Direction::W), bb!("d4"));
+    /// ```
+    fn shift(&self, direction: Direction) -> Self {
+        bb_shift(*self, direction)
+    }
 }
 
 pub struct BitboardIterator {
@@ -89,6 +182,13 @@ pub const FILES: [Bitboard; 8] = [
     FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H,
 ];
 
+// Squares by colour, where a1 is a dark square.
+pub const LIGHT_SQUARES: Bitboard = 0x55AA55AA55AA55AA;
+pub const DARK_SQUARES: Bitboard = !LIGHT_SQUARES;
+
+/// A step from one square to another: the eight king steps, named by compass point, and the eight
+/// knight jumps, named by the two or three points they're made of.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Direction {
     N,
     E,
@@ -108,6 +208,108 @@ pub enum Direction {
     NNW,
 }
 
+impl Direction {
+    /// Every direction, king steps first and then knight jumps.
+    pub const ALL: [Direction; 16] = [
+        Direction::N,
+        Direction::E,
+        Direction::S,
+        Direction::W,
+        Direction::NE,
+        Direction::SE,
+        Direction::SW,
+        Direction::NW,
+        Direction::NNE,
+        Direction::NEE,
+        Direction::SEE,
+        Direction::SSE,
+        Direction::SSW,
+        Direction::SWW,
+        Direction::NWW,
+        Direction::NNW,
+    ];
+
+    /// Returns how many files east and ranks north a step in this direction goes, negative for
+    /// west and south.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{bb_shift, Direction, Square};
+    ///
+    /// assert_eq!(Direction::NWW.offset(), (-2, 1));
+    ///
+    /// // Shifting agrees with stepping by file and rank from every square in every direction,
+    /// // and never wraps around the edge of the board.
+    /// for direction in Direction::ALL {
+    ///     let (file_step, rank_step) = direction.offset();
+    ///
+    ///     for index in 0..64 {
+    ///         let square = Square::new(index);
+    ///         let file = square.file().index() as i32 + file_step;
+    ///         let rank = square.rank().index() as i32 + rank_step;
+    ///
+    ///         let expected = if (0..8).contains(&file) && (0..8).contains(&rank) {
+    ///             Square::new((rank * 8 + file) as usize).bitboard()
+    ///         } else {
+    ///             0
+    ///         };
+    ///
+    ///         assert_eq!(bb_shift(square.bitboard(), direction), expected, "{:?} from {}", direction, square);
+    ///     }
+    /// }
+    /// ```
+    pub const fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::N => (0, 1),
+            Direction::E => (1, 0),
+            Direction::S => (0, -1),
+            Direction::W => (-1, 0),
+            Direction::NE => (1, 1),
+            Direction::SE => (1, -1),
+            Direction::SW => (-1, -1),
+            Direction::NW => (-1, 1),
+            Direction::NNE => (1, 2),
+            Direction::NEE => (2, 1),
+            Direction::SEE => (2, -1),
+            Direction::SSE => (1, -2),
+            Direction::SSW => (-1, -2),
+            Direction::SWW => (-2, -1),
+            Direction::NWW => (-2, 1),
+            Direction::NNW => (-1, 2),
+        }
+    }
+}
+
+// The piece types other than pawns, whose moves are all generated in the same way.
+const NON_PAWN_PIECES: [PieceType; 5] = [
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+// All directions a king can attack. Used for iteration.
+pub const KING_ATTACKS_DIRECTIONS: [Direction; 8] = [
+    Direction::N,
+    Direction::E,
+    Direction::S,
+    Direction::W,
+    Direction::NE,
+    Direction::SE,
+    Direction::SW,
+    Direction::NW,
+];
+
+// All directions a rook can slide.
+pub const ROOK_DIRECTIONS: [Direction; 4] =
+    [Direction::N, Direction::E, Direction::S, Direction::W];
+
+// All directions a bishop can slide.
+pub const BISHOP_DIRECTIONS: [Direction; 4] =
+    [Direction::NE, Direction::SE, Direction::SW, Direction::NW];
+
 // All directions a knight can attack. Used for iteration.
 pub const KNIGHT_ATTACKS_DIRECTIONS: [Direction; 8] = [
     Direction::NNE,
@@ -120,7 +322,48 @@ pub const KNIGHT_ATTACKS_DIRECTIONS: [Direction; 8] = [
     Direction::NNW,
 ];
 
-const fn bb_shift(bitboard: Bitboard, direction: Direction) -> Bitboard {
+/// Returns the bitboard with every set square moved one step in the given direction, where north
+/// is towards rank 8 and east towards the h-file. Squares stepping off the board are dropped
+/// rather than wrapping around onto the other side.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{bb, bb_shift, Direction};
+///
+/// let d4 = bb!("d4");
+///
+/// // King steps.
+/// assert_eq!(bb_shift(d4, Direction::N), bb!("d5"));
+/// assert_eq!(bb_shift(d4, Direction::E), bb!("e4"));
+/// assert_eq!(bb_shift(d4, Direction::S), bb!("d3"));
+/// assert_eq!(bb_shift(d4, Direction::W), bb!("c4"));
+/// assert_eq!(bb_shift(d4, Direction::NE), bb!("e5"));
+/// assert_eq!(bb_shift(d4, Direction::SE), bb!("e3"));
+/// assert_eq!(bb_shift(d4, Direction::SW), bb!("c3"));
+/// assert_eq!(bb_shift(d4, Direction::NW), bb!("c5"));
+///
+/// // Knight jumps.
+/// assert_eq!(bb_shift(d4, Direction::NNE), bb!("e6"));
+/// assert_eq!(bb_shift(d4, Direction::NEE), bb!("f5"));
+/// assert_eq!(bb_shift(d4, Direction::SEE), bb!("f3"));
+/// assert_eq!(bb_shift(d4, Direction::SSE), bb!("e2"));
+/// assert_eq!(bb_shift(d4, Direction::SSW), bb!("c2"));
+/// assert_eq!(bb_shift(d4, Direction::SWW), bb!("b3"));
+/// assert_eq!(bb_shift(d4, Direction::NWW), bb!("b5"));
+/// assert_eq!(bb_shift(d4, Direction::NNW), bb!("c6"));
+///
+/// // Steps off the edge of the board are dropped, including jumps two files sideways from the
+/// // second file in from the edge.
+/// assert_eq!(bb_shift(bb!("h4 e8"), Direction::NE), 0);
+/// assert_eq!(bb_shift(bb!("a4"), Direction::W), 0);
+/// assert_eq!(bb_shift(bb!("g4 h4"), Direction::NEE), 0);
+/// assert_eq!(bb_shift(bb!("g4 h4"), Direction::SEE), 0);
+/// assert_eq!(bb_shift(bb!("a4 b4"), Direction::SWW), 0);
+/// assert_eq!(bb_shift(bb!("a4 b4"), Direction::NWW), 0);
+/// assert_eq!(bb_shift(bb!("b4 g4"), Direction::NWW), bb!("e5"));
+/// ```
+pub const fn bb_shift(bitboard: Bitboard, direction: Direction) -> Bitboard {
     match direction {
         // Cardinal moves.
         Direction::N => bitboard << 8,
@@ -134,14 +377,14 @@ const fn bb_shift(bitboard: Bitboard, direction: Direction) -> Bitboard {
         Direction::SW => (bitboard >> 9) & !FILE_H,
         Direction::NW => (bitboard << 7) & !FILE_H,
 
-        // Knight moves.
+        // Knight moves. Those moving two files sideways must mask out two files to avoid wrapping.
         Direction::NNE => (bitboard << 17) & !FILE_A,
-        Direction::NEE => (bitboard << 10) & !FILE_A,
-        Direction::SEE => (bitboard >> 6) & !FILE_A,
+        Direction::NEE => (bitboard << 10) & !(FILE_A | FILE_B),
+        Direction::SEE => (bitboard >> 6) & !(FILE_A | FILE_B),
         Direction::SSE => (bitboard >> 15) & !FILE_A,
         Direction::SSW => (bitboard >> 17) & !FILE_H,
-        Direction::SWW => (bitboard >> 10) & !FILE_H,
-        Direction::NWW => (bitboard << 6) & !FILE_H,
+        Direction::SWW => (bitboard >> 10) & !(FILE_G | FILE_H),
+        Direction::NWW => (bitboard << 6) & !(FILE_G | FILE_H),
         Direction::NNW => (bitboard << 15) & !FILE_H,
     }
 }
@@ -210,53 +453,554 @@ pub const fn pawn_west_attacks(pawns: Bitboard, enemy_pieces: Bitboard, side: Si
     }
 }
 
+/// Returns a bitboard of all squares attacked by the kings in the given bitboard.
+///
+/// # Arguments
+///
+/// * `kings` - The bitboard of kings to generate attacks for.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{bb, king_attacks};
+///
+/// // A king on a1 attacks a2, b1, and b2.
+/// assert_eq!(king_attacks(bb!("a1")), bb!("a2 b1 b2"));
+/// ```
+pub const fn king_attacks(kings: Bitboard) -> Bitboard {
+    let mut attacks = 0;
+    let mut i = 0;
+
+    while i < KING_ATTACKS_DIRECTIONS.len() {
+        attacks |= bb_shift(kings, KING_ATTACKS_DIRECTIONS[i]);
+        i += 1;
+    }
+
+    attacks
+}
+
+/// Returns a bitboard of all squares attacked by a rook on the given square.
+///
+/// # Arguments
+///
+/// * `square` - The index of the square the rook is on.
+/// * `occupied` - The bitboard of all occupied squares on the board.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{bb, rook_attacks};
+///
+/// // A rook on a1 blocked by pieces on a3 and c1.
+/// assert_eq!(rook_attacks(0, bb!("a3 c1")), bb!("a2 a3 b1 c1"));
+/// ```
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    ROOK_MAGICS[square].attacks(occupied)
+}
+
+/// Returns a bitboard of all squares attacked by a bishop on the given square.
+///
+/// # Arguments
+///
+/// * `square` - The index of the square the bishop is on.
+/// * `occupied` - The bitboard of all occupied squares on the board.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::bishop_attacks;
+///
+/// // A bishop on a1 blocked by a piece on c3.
+/// assert_eq!(bishop_attacks(0, 0x0000000000040000), 0x0000000000040200);
+/// ```
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    BISHOP_MAGICS[square].attacks(occupied)
+}
+
+/// Returns a bitboard of the squares a rook on the given square would attack through the first
+/// of the `blockers` in each direction, but doesn't attack directly.
+///
+/// # Arguments
+///
+/// * `square` - The index of the square the rook is on.
+/// * `occupied` - The bitboard of all occupied squares on the board.
+/// * `blockers` - The pieces to see through, such as those of one side.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::xray_rook_attacks;
+///
+/// // A rook on a1 sees through the piece on a3 to a5, but not through c1, which isn't a blocker.
+/// let occupied = 0x0000000100010004;
+/// assert_eq!(xray_rook_attacks(0, occupied, 0x0000000000010000), 0x0000000101000000);
+/// ```
+pub fn xray_rook_attacks(square: usize, occupied: Bitboard, blockers: Bitboard) -> Bitboard {
+    let attacks = rook_attacks(square, occupied);
+    let blockers = blockers & attacks;
+
+    attacks ^ rook_attacks(square, occupied ^ blockers)
+}
+
+/// Returns a bitboard of the squares a bishop on the given square would attack through the first
+/// of the `blockers` in each direction, but doesn't attack directly.
+///
+/// # Arguments
+///
+/// * `square` - The index of the square the bishop is on.
+/// * `occupied` - The bitboard of all occupied squares on the board.
+/// * `blockers` - The pieces to see through, such as those of one side.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::xray_bishop_attacks;
+///
+/// // A bishop on a1 sees through the piece on c3 as far as f6.
+/// let occupied = 0x0000200000040000;
+/// assert_eq!(xray_bishop_attacks(0, occupied, occupied), 0x0000201008000000);
+/// ```
+pub fn xray_bishop_attacks(square: usize, occupied: Bitboard, blockers: Bitboard) -> Bitboard {
+    let attacks = bishop_attacks(square, occupied);
+    let blockers = blockers & attacks;
+
+    attacks ^ bishop_attacks(square, occupied ^ blockers)
+}
+
+// Attacks from each square for the pieces which don't slide, computed at compile time.
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_ATTACKS_DIRECTIONS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_ATTACKS_DIRECTIONS);
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[Direction::NE, Direction::NW]),
+    leaper_attacks(&[Direction::SE, Direction::SW]),
+];
+
+// The squares reached by a single step in any of the given directions, from every square.
+const fn leaper_attacks(directions: &[Direction]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+
+    while square < 64 {
+        let mut i = 0;
+
+        while i < directions.len() {
+            attacks[square] |= bb_shift(1 << square, directions[i]);
+            i += 1;
+        }
+
+        square += 1;
+    }
+
+    attacks
+}
+
+// Castling rights, stored as flags in a single byte.
+pub const CASTLE_WHITE_KINGSIDE: u8 = 0b0001;
+pub const CASTLE_WHITE_QUEENSIDE: u8 = 0b0010;
+pub const CASTLE_BLACK_KINGSIDE: u8 = 0b0100;
+pub const CASTLE_BLACK_QUEENSIDE: u8 = 0b1000;
+pub const CASTLE_ALL: u8 = 0b1111;
+
+/// The castling rights of a position, and the rook each right castles with. In standard chess the
+/// rooks are in the corners, but in Chess960 they can start anywhere on the back rank. Rights are
+/// compared by the rooks of the rights still held, so a rook left over from a lost right doesn't
+/// tell positions apart.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Move, Position};
+///
+/// // White can castle with the rook on g1, until the king moves.
+/// let mut position = Position::default();
+/// position
+///     .set_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKNBRQ w KQkq - 0 1")
+///     .unwrap();
+/// position.make_move(Move::from_uci_str("d2d4").unwrap());
+/// position.make_move(Move::from_uci_str("d7d5").unwrap());
+/// position.make_move(Move::from_uci_str("d1d2").unwrap());
+///
+/// let mut parsed = Position::default();
+/// parsed.set_fen(&position.fen()).unwrap();
+/// assert!(parsed == position);
+/// ```
+#[derive(Copy, Clone, Eq, Debug)]
+pub struct Castling {
+    // The `CASTLE_*` flags of the rights still held.
+    pub rights: u8,
+    // The square of the rook for each right, in the same order as the flags.
+    pub rooks: [Square; 4],
+    // Whether castling moves are written as the king capturing its own rook, as UCI does for
+    // Chess960, rather than as the king moving two squares.
+    pub chess960: bool,
+}
+
+impl Castling {
+    /// No rights, with the rooks where they start in standard chess.
+    pub const fn new() -> Self {
+        Self {
+            rights: 0,
+            rooks: [
+                Square::new(7),
+                Square::new(0),
+                Square::new(63),
+                Square::new(56),
+            ],
+            chess960: false,
+        }
+    }
+}
+
+impl Default for Castling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Castling {
+    fn eq(&self, other: &Self) -> bool {
+        self.rights == other.rights
+            && self.chess960 == other.chess960
+            && (0..4)
+                .filter(|bit| self.rights & (1 << bit) != 0)
+                .all(|bit| self.rooks[bit] == other.rooks[bit])
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
 pub struct Board {
-    pub attacks_by_piece: [[Bitboard; 64]; 6],
     pub bitboard_by_side: [Bitboard; 2],
     pub bitboard_by_piece: [Bitboard; 6],
+    // Every piece of either side, and the piece on each square, kept in step with the bitboards
+    // by `set_square` so that neither has to be worked out from them.
+    occupied: Bitboard,
+    squares: [Option<(PieceType, Side)>; 64],
 }
 
 impl Board {
-    pub fn new() -> Self {
-        let mut attacks_by_piece = [[0; 64]; 6];
-
-        // Compute all knight attacks for every square.
-        for square in 0..64 {
-            for direction in KNIGHT_ATTACKS_DIRECTIONS {
-                attacks_by_piece[PieceType::Knight.val()][square] |=
-                    bb_shift(1 << square, direction);
-            }
-        }
-
+    pub const fn new() -> Self {
         Self {
-            attacks_by_piece,
             bitboard_by_side: [0; 2],
             bitboard_by_piece: [0; 6],
+            occupied: 0,
+            squares: [None; 64],
         }
     }
 
-    pub fn generate_pawn_moves(&self, side: Side) -> Vec<Move> {
+    /// Returns a bitboard of the squares with a piece of either side on them.
+    pub fn occupied(&self) -> Bitboard {
+        self.occupied
+    }
+
+    /// Returns a bitboard of the squares with no piece on them.
+    pub fn empty(&self) -> Bitboard {
+        !self.occupied
+    }
+
+    /// Places a piece on the given square, or clears it if `piece` is `None`, returning whatever
+    /// was there before. The bitboards, occupancy and piece on each square are all updated, so
+    /// pieces should only be placed through this, rather than by changing the bitboards directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Board, PieceType, Side};
+    ///
+    /// let mut board = Board::new();
+    /// board.set_square(28, Some((PieceType::Pawn, Side::White)));
+    /// assert_eq!(board.occupied(), 1 << 28);
+    ///
+    /// let old = board.set_square(28, Some((PieceType::Knight, Side::Black)));
+    /// assert_eq!(old, Some((PieceType::Pawn, Side::White)));
+    /// assert_eq!(board.piece_at(28), Some((PieceType::Knight, Side::Black)));
+    /// assert_eq!(board.bitboard_by_side[Side::White.val()], 0);
+    ///
+    /// board.set_square(28, None);
+    /// assert_eq!(board.empty(), !0);
+    /// ```
+    pub fn set_square(
+        &mut self,
+        square: usize,
+        piece: Option<(PieceType, Side)>,
+    ) -> Option<(PieceType, Side)> {
+        let square_bitboard: Bitboard = 1 << square;
+        let old_piece = core::mem::replace(&mut self.squares[square], piece);
+
+        if let Some((piece_type, side)) = old_piece {
+            self.bitboard_by_side[side.val()] &= !square_bitboard;
+            self.bitboard_by_piece[piece_type.val()] &= !square_bitboard;
+            self.occupied &= !square_bitboard;
+        }
+
+        if let Some((piece_type, side)) = piece {
+            self.bitboard_by_side[side.val()] |= square_bitboard;
+            self.bitboard_by_piece[piece_type.val()] |= square_bitboard;
+            self.occupied |= square_bitboard;
+        }
+
+        old_piece
+    }
+
+    /// Returns the type and side of the piece on the given square, if any.
+    pub fn piece_at(&self, square: usize) -> Option<(PieceType, Side)> {
+        self.squares[square]
+    }
+
+    /// Returns the number of pieces of the given type belonging to `side`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, PieceType, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let board = engine.position().board();
+    /// assert_eq!(board.count(PieceType::Pawn, Side::White), 8);
+    /// assert_eq!(board.count(PieceType::Queen, Side::Black), 1);
+    /// ```
+    pub fn count(&self, piece_type: PieceType, side: Side) -> u32 {
+        (self.bitboard_by_piece[piece_type.val()] & self.bitboard_by_side[side.val()]).count_ones()
+    }
+
+    /// Returns the total middlegame value of the pieces belonging to `side`, in centipawns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, PieceType, Side, MIDDLEGAME_VALUES};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let board = engine.position().board();
+    /// assert_eq!(board.material(Side::White), board.material(Side::Black));
+    /// assert_eq!(
+    ///     board.material(Side::White) - board.non_pawn_material(Side::White),
+    ///     8 * MIDDLEGAME_VALUES[PieceType::Pawn.val()]
+    /// );
+    /// ```
+    pub fn material(&self, side: Side) -> i32 {
+        self.non_pawn_material(side)
+            + self.count(PieceType::Pawn, side) as i32 * MIDDLEGAME_VALUES[PieceType::Pawn.val()]
+    }
+
+    /// Returns the total middlegame value of the pieces other than pawns belonging to `side`, in
+    /// centipawns. The king has no value, so this is 0 when `side` has only pawns left.
+    pub fn non_pawn_material(&self, side: Side) -> i32 {
+        NON_PAWN_PIECES
+            .into_iter()
+            .map(|piece_type| {
+                self.count(piece_type, side) as i32 * MIDDLEGAME_VALUES[piece_type.val()]
+            })
+            .sum()
+    }
+
+    /// Returns a bitboard of the squares attacked by a knight on the given square.
+    pub fn knight_attacks(&self, square: usize) -> Bitboard {
+        KNIGHT_ATTACKS[square]
+    }
+
+    /// Returns a bitboard of the squares attacked by a king on the given square.
+    pub fn king_attacks(&self, square: usize) -> Bitboard {
+        KING_ATTACKS[square]
+    }
+
+    /// Returns a bitboard of the squares attacked by a pawn of the given side on the given square.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Board, Side};
+    ///
+    /// let board = Board::new();
+    ///
+    /// // A white pawn on e4 attacks d5 and f5, and a black one on a5 attacks only b4.
+    /// assert_eq!(board.pawn_attacks(28, Side::White), (1 << 35) | (1 << 37));
+    /// assert_eq!(board.pawn_attacks(32, Side::Black), 1 << 25);
+    /// ```
+    pub fn pawn_attacks(&self, square: usize, side: Side) -> Bitboard {
+        PAWN_ATTACKS[side.val()][square]
+    }
+
+    /// Returns the type of the piece on the given square, if any.
+    pub fn piece_type_at(&self, square: usize) -> Option<PieceType> {
+        self.squares[square].map(|(piece_type, _)| piece_type)
+    }
+
+    /// Returns a bitboard of the pieces belonging to `by_side` that attack the given square.
+    ///
+    /// # Arguments
+    ///
+    /// * `square` - The index of the square to find attackers of.
+    /// * `by_side` - The side whose pieces should be considered as attackers.
+    pub fn attackers_to(&self, square: usize, by_side: Side) -> Bitboard {
+        self.attackers_with_occupancy(
+            square,
+            by_side,
+            self.bitboard_by_side[by_side.val()],
+            self.occupied,
+        )
+    }
+
+    /// Returns true if the given square is attacked by any piece belonging to `by_side`.
+    ///
+    /// # Arguments
+    ///
+    /// * `square` - The index of the square to check.
+    /// * `by_side` - The side whose pieces should be considered as attackers.
+    pub fn is_square_attacked(&self, square: usize, by_side: Side) -> bool {
+        self.attackers_to(square, by_side) != 0
+    }
+
+    // Attackers of a square, restricted to the pieces in `attacking_pieces`, with sliding attacks
+    // blocked by `occupied`. Used to test hypothetical positions without modifying the board.
+    fn attackers_with_occupancy(
+        &self,
+        square: usize,
+        by_side: Side,
+        attacking_pieces: Bitboard,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let pawns = self.bitboard_by_piece[PieceType::Pawn.val()] & attacking_pieces;
+        let knights = self.bitboard_by_piece[PieceType::Knight.val()] & attacking_pieces;
+        let kings = self.bitboard_by_piece[PieceType::King.val()] & attacking_pieces;
+        let queens = self.bitboard_by_piece[PieceType::Queen.val()];
+        let diagonal_sliders =
+            (self.bitboard_by_piece[PieceType::Bishop.val()] | queens) & attacking_pieces;
+        let orthogonal_sliders =
+            (self.bitboard_by_piece[PieceType::Rook.val()] | queens) & attacking_pieces;
+
+        // A pawn attacks the square if a pawn of the other side on the square would attack it.
+        (self.pawn_attacks(square, by_side.flip()) & pawns)
+            | (self.knight_attacks(square) & knights)
+            | (self.king_attacks(square) & kings)
+            | (bishop_attacks(square, occupied) & diagonal_sliders)
+            | (rook_attacks(square, occupied) & orthogonal_sliders)
+    }
+
+    /// Returns a bitboard of the pieces of `side` which are pinned to its king: those which alone
+    /// stand between the king and an enemy slider, and so may only move along the line of the
+    /// attack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/4r3/8/8/4N3/8/2B5/4K3 w - - 0 1").unwrap();
+    ///
+    /// // The knight on e4 is pinned by the rook. The bishop on c2 isn't on a line to the king.
+    /// let board = engine.position().board();
+    /// assert_eq!(board.pinned_pieces(Side::White), 1 << 28);
+    /// ```
+    pub fn pinned_pieces(&self, side: Side) -> Bitboard {
+        let our_king =
+            self.bitboard_by_piece[PieceType::King.val()] & self.bitboard_by_side[side.val()];
+
+        match Square::from_bitboard(our_king) {
+            Some(king) => {
+                self.blockers(king.index(), side.flip()) & self.bitboard_by_side[side.val()]
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns a bitboard of the pieces of `side` which would give discovered check by moving:
+    /// those which alone stand between one of its sliders and the enemy king.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/8/2N5/8/4B3/4RK2 w - - 0 1").unwrap();
+    ///
+    /// // The bishop on e2 blocks the rook's attack on the king.
+    /// let board = engine.position().board();
+    /// assert_eq!(board.discovered_check_candidates(Side::White), 1 << 12);
+    /// assert_eq!(board.discovered_check_candidates(Side::Black), 0);
+    /// ```
+    pub fn discovered_check_candidates(&self, side: Side) -> Bitboard {
+        let their_king = self.bitboard_by_piece[PieceType::King.val()]
+            & self.bitboard_by_side[side.flip().val()];
+
+        match Square::from_bitboard(their_king) {
+            Some(king) => self.blockers(king.index(), side) & self.bitboard_by_side[side.val()],
+            None => 0,
+        }
+    }
+
+    /// Returns true if making the given (pseudo-legal) move would leave the moving side's king in
+    /// check, meaning the move is illegal.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_move` - The move to test.
+    /// * `side` - The side making the move.
+    pub fn leaves_king_in_check(&self, piece_move: &Move, side: Side) -> bool {
+        // Castling moves are only generated when they are legal.
+        if piece_move.is_castle() {
+            return false;
+        }
+
+        let from_bitboard = piece_move.from.bitboard();
+        let to_bitboard = piece_move.to.bitboard();
+
+        let our_bitboard = self.bitboard_by_side[side.val()];
         let opp_bitboard = self.bitboard_by_side[side.flip().val()];
 
-        // Single / double pushes.
-        let our_pawns =
-            self.bitboard_by_piece[PieceType::Pawn.val()] & self.bitboard_by_side[side.val()];
+        let mut captured = to_bitboard & opp_bitboard;
+
+        // A pawn moving diagonally onto an empty square is capturing en passant.
+        let is_pawn = self.bitboard_by_piece[PieceType::Pawn.val()] & from_bitboard != 0;
+        if is_pawn && captured == 0 && piece_move.from.file() != piece_move.to.file() {
+            captured = match side {
+                Side::White => to_bitboard >> 8,
+                Side::Black => to_bitboard << 8,
+                _ => 0,
+            };
+        }
 
-        let empty = !self.bitboard_by_side[side.val()] & !opp_bitboard;
+        let occupied = (self.occupied & !from_bitboard & !captured) | to_bitboard;
 
-        let promotion_rank = match side {
-            Side::White => RANK_8,
-            Side::Black => RANK_1,
-            _ => !0,
+        let our_king = self.bitboard_by_piece[PieceType::King.val()] & our_bitboard;
+        let king_bitboard = if our_king & from_bitboard != 0 {
+            to_bitboard
+        } else {
+            our_king
         };
 
-        let mut moves: Vec<Move> = vec![];
+        if king_bitboard == 0 {
+            return false;
+        }
+
+        self.attackers_with_occupancy(
+            king_bitboard.trailing_zeros() as usize,
+            side.flip(),
+            opp_bitboard & !captured,
+            occupied,
+        ) != 0
+    }
 
-        //
-        // Pushes
-        //
+    pub fn generate_pawn_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_pawn_pushes(side, moves);
+        self.generate_pawn_captures(side, moves);
+    }
 
-        single_pawn_push(our_pawns, empty, side)
+    /// Generates pawn pushes for the given side, other than those which promote.
+    pub fn generate_pawn_pushes(&self, side: Side, moves: &mut MoveList) {
+        self.generate_pawn_pushes_to(side, !0, moves);
+    }
+
+    // Pawn pushes other than promotions, landing on one of the target squares.
+    fn generate_pawn_pushes_to(&self, side: Side, targets: Bitboard, moves: &mut MoveList) {
+        let our_pawns =
+            self.bitboard_by_piece[PieceType::Pawn.val()] & self.bitboard_by_side[side.val()];
+        (single_pawn_push(our_pawns, self.empty(), side) & !promotion_rank(side) & targets)
             .iter()
             .for_each(|to_square| {
                 let from_square = match side {
@@ -265,20 +1009,16 @@ impl Board {
                     _ => 0,
                 };
 
-                let promotion_piece = if to_square & promotion_rank != 0 {
-                    Some(PieceType::Queen)
-                } else {
-                    None
-                };
-
                 moves.push(Move {
-                    from: from_square.trailing_zeros(),
-                    to: to_square.trailing_zeros(),
-                    promote: promotion_piece,
+                    from: lowest_square(from_square),
+                    to: lowest_square(to_square),
+                    promote: None,
+                    captured: None,
+                    kind: MoveKind::Normal,
                 });
             });
 
-        double_pawn_push(our_pawns, empty, side)
+        (double_pawn_push(our_pawns, self.empty(), side) & targets)
             .iter()
             .for_each(|to_square| {
                 let from_square = match side {
@@ -288,15 +1028,40 @@ impl Board {
                 };
 
                 moves.push(Move {
-                    from: from_square.trailing_zeros(),
-                    to: to_square.trailing_zeros(),
+                    from: lowest_square(from_square),
+                    to: lowest_square(to_square),
                     promote: None,
+                    captured: None,
+                    kind: MoveKind::DoublePush,
                 });
             });
+    }
 
-        //
-        // Captures
-        //
+    /// Generates pawn captures for the given side, other than en passant, along with pushes
+    /// which promote.
+    pub fn generate_pawn_captures(&self, side: Side, moves: &mut MoveList) {
+        self.generate_pawn_captures_to(side, !0, moves);
+    }
+
+    // Pawn captures other than en passant, and promoting pushes, landing on one of the target
+    // squares.
+    fn generate_pawn_captures_to(&self, side: Side, targets: Bitboard, moves: &mut MoveList) {
+        let opp_bitboard = self.bitboard_by_side[side.flip().val()] & targets;
+        let our_pawns =
+            self.bitboard_by_piece[PieceType::Pawn.val()] & self.bitboard_by_side[side.val()];
+        let promotion_rank = promotion_rank(side);
+
+        (single_pawn_push(our_pawns, self.empty(), side) & promotion_rank & targets)
+            .iter()
+            .for_each(|to_square| {
+                let from_square = match side {
+                    Side::White => to_square >> 8,
+                    Side::Black => to_square << 8,
+                    _ => 0,
+                };
+
+                push_promotions(moves, from_square, to_square, None);
+            });
 
         pawn_east_attacks(our_pawns, opp_bitboard, side)
             .iter()
@@ -304,17 +1069,19 @@ impl Board {
                 pawn_west_attacks(target_piece, our_pawns, side.flip())
                     .iter()
                     .for_each(|source_piece| {
-                        let promotion_piece = if target_piece & promotion_rank != 0 {
-                            Some(PieceType::Queen)
+                        let captured = self.piece_type_at(target_piece.trailing_zeros() as usize);
+
+                        if target_piece & promotion_rank != 0 {
+                            push_promotions(moves, source_piece, target_piece, captured);
                         } else {
-                            None
-                        };
-
-                        moves.push(Move {
-                            from: source_piece.trailing_zeros(),
-                            to: target_piece.trailing_zeros(),
-                            promote: promotion_piece,
-                        });
+                            moves.push(Move {
+                                from: lowest_square(source_piece),
+                                to: lowest_square(target_piece),
+                                promote: None,
+                                captured,
+                                kind: MoveKind::Normal,
+                            });
+                        }
                     });
             });
 
@@ -324,55 +1091,499 @@ impl Board {
                 pawn_east_attacks(target_piece, our_pawns, side.flip())
                     .iter()
                     .for_each(|source_piece| {
-                        let promotion_piece = if target_piece & promotion_rank != 0 {
-                            Some(PieceType::Queen)
+                        let captured = self.piece_type_at(target_piece.trailing_zeros() as usize);
+
+                        if target_piece & promotion_rank != 0 {
+                            push_promotions(moves, source_piece, target_piece, captured);
                         } else {
-                            None
-                        };
-
-                        moves.push(Move {
-                            from: source_piece.trailing_zeros(),
-                            to: target_piece.trailing_zeros(),
-                            promote: promotion_piece,
-                        });
+                            moves.push(Move {
+                                from: lowest_square(source_piece),
+                                to: lowest_square(target_piece),
+                                promote: None,
+                                captured,
+                                kind: MoveKind::Normal,
+                            });
+                        }
                     });
             });
+    }
+
+    /// Generates pseudo-legal en passant captures for the given side.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate captures for.
+    /// * `en_passant` - The square behind a pawn which has just made a double push, if any.
+    /// * `moves` - The list to add the captures to.
+    pub fn generate_en_passant_moves(
+        &self,
+        side: Side,
+        en_passant: Option<Square>,
+        moves: &mut MoveList,
+    ) {
+        let Some(target_square) = en_passant else {
+            return;
+        };
 
-        moves
+        let our_pawns =
+            self.bitboard_by_piece[PieceType::Pawn.val()] & self.bitboard_by_side[side.val()];
+
+        // Pawns able to capture onto the target square are those an enemy pawn on the target
+        // square would attack.
+        let target = target_square.bitboard();
+        let source_pawns = pawn_east_attacks(target, our_pawns, side.flip())
+            | pawn_west_attacks(target, our_pawns, side.flip());
+
+        source_pawns.iter().for_each(|source_piece| {
+            moves.push(Move {
+                from: lowest_square(source_piece),
+                to: target_square,
+                promote: None,
+                captured: Some(PieceType::Pawn),
+                kind: MoveKind::EnPassant,
+            });
+        });
     }
 
-    pub fn generate_knight_moves(&self, side: Side) -> Vec<Move> {
-        let opp_bitboard = self.bitboard_by_side[side.flip().val()];
+    pub fn generate_knight_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_piece_moves(side, PieceType::Knight, !0, moves);
+    }
+
+    pub fn generate_bishop_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_piece_moves(side, PieceType::Bishop, !0, moves);
+    }
+
+    pub fn generate_rook_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_piece_moves(side, PieceType::Rook, !0, moves);
+    }
+
+    pub fn generate_queen_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_piece_moves(side, PieceType::Queen, !0, moves);
+    }
+
+    pub fn generate_king_moves(&self, side: Side, moves: &mut MoveList) {
+        self.generate_piece_moves(side, PieceType::King, !0, moves);
+    }
+
+    /// Generates pseudo-legal moves for the given side's pieces of one type, other than pawn
+    /// moves and castling, which land on one of the target squares.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate moves for.
+    /// * `piece_type` - The type of piece to move.
+    /// * `targets` - The squares the pieces may move to, such as the enemy pieces for captures.
+    /// * `moves` - The list to add the moves to.
+    pub fn generate_piece_moves(
+        &self,
+        side: Side,
+        piece_type: PieceType,
+        targets: Bitboard,
+        moves: &mut MoveList,
+    ) {
         let our_bitboard = self.bitboard_by_side[side.val()];
+        let our_pieces = self.bitboard_by_piece[piece_type.val()] & our_bitboard;
 
-        let empty_bitboard = !our_bitboard & !opp_bitboard;
+        our_pieces.iter().for_each(|source| {
+            let square = source.trailing_zeros() as usize;
 
-        let our_knights =
-            self.bitboard_by_piece[PieceType::Knight.val()] & self.bitboard_by_side[side.val()];
+            let attacks = match piece_type {
+                PieceType::Knight => self.knight_attacks(square),
+                PieceType::Bishop => bishop_attacks(square, self.occupied),
+                PieceType::Rook => rook_attacks(square, self.occupied),
+                PieceType::Queen => {
+                    bishop_attacks(square, self.occupied) | rook_attacks(square, self.occupied)
+                }
+                PieceType::King => self.king_attacks(square),
+                _ => 0,
+            };
 
-        let mut moves: Vec<Move> = vec![];
+            self.push_moves(
+                moves,
+                lowest_square(source),
+                attacks & targets & !our_bitboard,
+            );
+        });
+    }
 
-        our_knights.iter().for_each(|knight_source| {
-            let source_index = knight_source.trailing_zeros();
+    /// Generates pseudo-legal captures for the given side, along with pawn pushes which promote.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate captures for.
+    /// * `en_passant` - The square behind a pawn which has just made a double push, if any.
+    /// * `moves` - The list to add the captures to.
+    pub fn generate_captures(&self, side: Side, en_passant: Option<Square>, moves: &mut MoveList) {
+        let enemy = self.bitboard_by_side[side.flip().val()];
 
-            // Empty squares to move the knight to.
-            let knight_moves = self.attacks_by_piece[PieceType::Knight.val()]
-                [source_index as usize]
-                & empty_bitboard;
+        self.generate_pawn_captures(side, moves);
+        self.generate_en_passant_moves(side, en_passant, moves);
 
-            knight_moves.iter().for_each(|knight_move| {
-                moves.push(Move {
-                    from: source_index,
-                    to: knight_move.trailing_zeros(),
-                    promote: None,
-                });
+        for piece_type in NON_PAWN_PIECES {
+            self.generate_piece_moves(side, piece_type, enemy, moves);
+        }
+    }
+
+    /// Generates pseudo-legal moves for the given side which may get its king out of check:
+    /// king moves, captures of the checking piece, and moves which block its line of attack. In
+    /// double check only king moves are generated, as nothing else can answer both checks. The
+    /// side must be in check; together with `generate_captures` and `generate_quiets` this
+    /// replaces, rather than adds to, the other generators.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side in check.
+    /// * `en_passant` - The square behind a pawn which has just made a double push, if any.
+    /// * `moves` - The list to add the moves to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, MoveList, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/8/Rb6/8/8/1N2K3 w - - 0 1").unwrap();
+    ///
+    /// // The bishop on b4 checks the king, which can step aside, or the rook can capture it, or
+    /// // the knight can block.
+    /// let mut moves = MoveList::new();
+    /// engine.position().board().generate_evasions(Side::White, None, &mut moves);
+    ///
+    /// let mut moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+    /// moves.sort();
+    /// assert_eq!(
+    ///     moves,
+    ///     ["a4b4", "b1c3", "b1d2", "e1d1", "e1d2", "e1e2", "e1f1", "e1f2"]
+    /// );
+    /// ```
+    pub fn generate_evasions(&self, side: Side, en_passant: Option<Square>, moves: &mut MoveList) {
+        let our_king =
+            self.bitboard_by_piece[PieceType::King.val()] & self.bitboard_by_side[side.val()];
+        let Some(king) = Square::from_bitboard(our_king) else {
+            return;
+        };
+
+        // The king may move even into check here, as with the other generators; moves which
+        // leave it attacked are filtered out by `leaves_king_in_check`.
+        self.generate_piece_moves(side, PieceType::King, !0, moves);
+
+        let checkers = self.attackers_to(king.index(), side.flip());
+        if checkers.count_ones() != 1 {
+            return;
+        }
+
+        let targets = checkers | squares_between(king.index(), checkers.trailing_zeros() as usize);
+
+        self.generate_pawn_pushes_to(side, targets, moves);
+        self.generate_pawn_captures_to(side, targets, moves);
+
+        // Capturing en passant can only help if the pawn taken is the one giving check.
+        if let Some(target_square) = en_passant {
+            let captured_pawn = match side {
+                Side::White => target_square.bitboard() >> 8,
+                _ => target_square.bitboard() << 8,
+            };
+
+            if captured_pawn & checkers != 0 {
+                self.generate_en_passant_moves(side, en_passant, moves);
+            }
+        }
+
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            self.generate_piece_moves(side, piece_type, targets, moves);
+        }
+    }
+
+    /// Generates the pseudo-legal moves for the given side which neither capture nor promote,
+    /// including castling.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate moves for.
+    /// * `castling` - The castling rights of the position.
+    /// * `moves` - The list to add the moves to.
+    pub fn generate_quiets(&self, side: Side, castling: &Castling, moves: &mut MoveList) {
+        self.generate_pawn_pushes(side, moves);
+
+        for piece_type in NON_PAWN_PIECES {
+            self.generate_piece_moves(side, piece_type, self.empty(), moves);
+        }
+
+        self.generate_castling_moves(side, castling, moves);
+    }
+
+    /// Generates the pseudo-legal moves for the given side which neither capture nor promote,
+    /// but give check, either directly or by moving out of the way of a slider aimed at the
+    /// enemy king. Castling is left out, even when the rook would give check.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate checks for.
+    /// * `moves` - The list to add the moves to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, MoveList, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/8/2N5/8/4B3/4RK2 w - - 0 1").unwrap();
+    ///
+    /// // The knight checks from d6, and any bishop move uncovers the rook's attack on e8.
+    /// let mut moves = MoveList::new();
+    /// engine.position().board().generate_quiet_checks(Side::White, &mut moves);
+    ///
+    /// let mut moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+    /// moves.sort();
+    /// assert_eq!(moves, ["c4d6", "e2d1", "e2d3", "e2f3", "e2g4", "e2h5"]);
+    /// ```
+    pub fn generate_quiet_checks(&self, side: Side, moves: &mut MoveList) {
+        let their_king = self.bitboard_by_piece[PieceType::King.val()]
+            & self.bitboard_by_side[side.flip().val()];
+        let Some(king) = Square::from_bitboard(their_king) else {
+            return;
+        };
+
+        let king = king.index();
+        let our_bitboard = self.bitboard_by_side[side.val()];
+        let discoverers = self.discovered_check_candidates(side);
+
+        // Moves which could give check are generated first, and then tested properly.
+        let mut candidates = MoveList::new();
+        self.generate_pawn_pushes(side, &mut candidates);
+
+        for piece_type in NON_PAWN_PIECES {
+            let checking_squares = match piece_type {
+                PieceType::Knight => self.knight_attacks(king),
+                PieceType::Bishop => bishop_attacks(king, self.occupied),
+                PieceType::Rook => rook_attacks(king, self.occupied),
+                PieceType::Queen => {
+                    bishop_attacks(king, self.occupied) | rook_attacks(king, self.occupied)
+                }
+                _ => 0,
+            };
+
+            // A piece standing in the way of an attack gives check almost wherever it goes.
+            let pieces = self.bitboard_by_piece[piece_type.val()] & our_bitboard;
+            let targets = if pieces & discoverers != 0 {
+                self.empty()
+            } else {
+                self.empty() & checking_squares
+            };
+
+            self.generate_piece_moves(side, piece_type, targets, &mut candidates);
+        }
+
+        candidates
+            .iter()
+            .filter(|piece_move| self.quiet_move_gives_check(piece_move, side, king))
+            .for_each(|&piece_move| moves.push(piece_move));
+    }
+
+    // Whether a quiet move by `side` attacks the enemy king on the given square, either with the
+    // piece moved or by uncovering an attack from a slider behind it.
+    fn quiet_move_gives_check(&self, piece_move: &Move, side: Side, king: usize) -> bool {
+        let from_bitboard = piece_move.from.bitboard();
+        let to = piece_move.to.index();
+        let occupied = (self.occupied & !from_bitboard) | piece_move.to.bitboard();
+
+        let direct = match self.piece_type_at(piece_move.from.index()) {
+            Some(PieceType::Pawn) => self.pawn_attacks(to, side),
+            Some(PieceType::Knight) => self.knight_attacks(to),
+            Some(PieceType::Bishop) => bishop_attacks(to, occupied),
+            Some(PieceType::Rook) => rook_attacks(to, occupied),
+            Some(PieceType::Queen) => bishop_attacks(to, occupied) | rook_attacks(to, occupied),
+            _ => 0,
+        };
+
+        direct & (1 << king) != 0
+            || self.attackers_with_occupancy(
+                king,
+                side,
+                self.bitboard_by_side[side.val()] & !from_bitboard,
+                occupied,
+            ) != 0
+    }
+
+    // The pieces of either side which are alone in standing between the given square and a
+    // slider of `slider_side` aimed at it along a rank, file or diagonal.
+    fn blockers(&self, square: usize, slider_side: Side) -> Bitboard {
+        let queens = self.bitboard_by_piece[PieceType::Queen.val()];
+        let diagonal_sliders = self.bitboard_by_piece[PieceType::Bishop.val()] | queens;
+        let orthogonal_sliders = self.bitboard_by_piece[PieceType::Rook.val()] | queens;
+
+        // Sliders seen through exactly one piece, which is then the only one in the way.
+        let sliders = ((xray_bishop_attacks(square, self.occupied, self.occupied)
+            & diagonal_sliders)
+            | (xray_rook_attacks(square, self.occupied, self.occupied) & orthogonal_sliders))
+            & self.bitboard_by_side[slider_side.val()];
+
+        sliders.iter().fold(0, |blockers, slider| {
+            blockers | (squares_between(square, slider.trailing_zeros() as usize) & self.occupied)
+        })
+    }
+
+    /// Generates castling moves for the given side. Whatever the king and rook's starting
+    /// squares, the king ends up on the g-file or c-file and the rook next to it on the f-file or
+    /// d-file. The moves are encoded as the king moving to its destination, or as the king
+    /// capturing its own rook if `castling.chess960` is set.
+    ///
+    /// Unlike other generators, the moves added are fully legal: the king may not castle out
+    /// of, through, or into check.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side to generate castling moves for.
+    /// * `castling` - The castling rights of the position.
+    /// * `moves` - The list to add the moves to.
+    pub fn generate_castling_moves(&self, side: Side, castling: &Castling, moves: &mut MoveList) {
+        let (kingside, queenside) = match side {
+            Side::White => (CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE),
+            Side::Black => (CASTLE_BLACK_KINGSIDE, CASTLE_BLACK_QUEENSIDE),
+            _ => return,
+        };
+
+        if castling.rights & (kingside | queenside) == 0 {
+            return;
+        }
+
+        let our_king =
+            self.bitboard_by_piece[PieceType::King.val()] & self.bitboard_by_side[side.val()];
+        let Some(king) = Square::from_bitboard(our_king) else {
+            return;
+        };
+
+        let enemy = side.flip();
+        let back_rank = king.index() / 8 * 8;
+
+        for (right, kind, king_file, rook_file) in [
+            (kingside, MoveKind::KingsideCastle, 6, 5),
+            (queenside, MoveKind::QueensideCastle, 2, 3),
+        ] {
+            if castling.rights & right == 0 {
+                continue;
+            }
+
+            let rook = castling.rooks[right.trailing_zeros() as usize];
+            let (king_to, rook_to) = (back_rank + king_file, back_rank + rook_file);
+
+            // Every square the king or rook passes over or lands on must be empty, apart from
+            // the king and rook themselves.
+            let castling_pieces = king.bitboard() | rook.bitboard();
+            let path = rank_span(king.index(), king_to) | rank_span(rook.index(), rook_to);
+
+            if self.occupied & path & !castling_pieces != 0 {
+                continue;
+            }
+
+            // Nor may any square the king stands on, passes over or lands on be attacked. The
+            // rook is taken off the board first, as it may be blocking an attack along the rank.
+            let attacked = rank_span(king.index(), king_to).iter().any(|square| {
+                self.attackers_with_occupancy(
+                    square.trailing_zeros() as usize,
+                    enemy,
+                    self.bitboard_by_side[enemy.val()],
+                    self.occupied & !castling_pieces,
+                ) != 0
+            });
+
+            if attacked {
+                continue;
+            }
+
+            moves.push(Move {
+                from: king,
+                to: if castling.chess960 {
+                    rook
+                } else {
+                    Square::new(king_to)
+                },
+                promote: None,
+                captured: None,
+                kind,
+            });
+        }
+    }
+
+    // Pushes a move from the source square to every square in the targets bitboard.
+    fn push_moves(&self, moves: &mut MoveList, source: Square, targets: Bitboard) {
+        targets.iter().for_each(|target| {
+            let target = lowest_square(target);
+
+            moves.push(Move {
+                from: source,
+                to: target,
+                promote: None,
+                captured: self.piece_type_at(target.index()),
+                kind: MoveKind::Normal,
             });
         });
+    }
+}
 
-        moves
+// Pushes a pawn move to the last rank once for each piece it can promote to, best first.
+fn push_promotions(
+    moves: &mut MoveList,
+    from: Bitboard,
+    to: Bitboard,
+    captured: Option<PieceType>,
+) {
+    for promote in [
+        PieceType::Queen,
+        PieceType::Knight,
+        PieceType::Rook,
+        PieceType::Bishop,
+    ] {
+        moves.push(Move {
+            from: lowest_square(from),
+            to: lowest_square(to),
+            promote: Some(promote),
+            captured,
+            kind: MoveKind::Normal,
+        });
     }
 }
 
+// The rank on which the given side's pawns promote.
+// The squares on a rank from one square to another, including both.
+fn rank_span(from: usize, to: usize) -> Bitboard {
+    let (low, high) = (from.min(to), from.max(to));
+
+    ((1 << (high - low + 1)) - 1) << low
+}
+
+// The squares strictly between two squares on the same rank, file or diagonal, or none if they
+// don't share a line. These are the squares a slider's attack from one to the other passes over.
+fn squares_between(from: usize, to: usize) -> Bitboard {
+    let (from_bitboard, to_bitboard): (Bitboard, Bitboard) = (1 << from, 1 << to);
+
+    if rook_attacks(from, 0) & to_bitboard != 0 {
+        rook_attacks(from, to_bitboard) & rook_attacks(to, from_bitboard)
+    } else if bishop_attacks(from, 0) & to_bitboard != 0 {
+        bishop_attacks(from, to_bitboard) & bishop_attacks(to, from_bitboard)
+    } else {
+        0
+    }
+}
+
+fn promotion_rank(side: Side) -> Bitboard {
+    match side {
+        Side::White => RANK_8,
+        Side::Black => RANK_1,
+        _ => !0,
+    }
+}
+
+// The lowest square set in a bitboard, which must not be empty.
+fn lowest_square(bitboard: Bitboard) -> Square {
+    Square::new(bitboard.trailing_zeros() as usize)
+}
+
 impl Default for Board {
     fn default() -> Self {
         Self::new()
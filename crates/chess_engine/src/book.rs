@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::log::log;
+use crate::random::next_random;
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::{Game, GameResult, Move, MoveKind, PieceType, Position, Side, Square};
+
+// Each entry is 16 bytes: the position's key, the move, its weight and some learning data which
+// is ignored, all big-endian.
+const ENTRY_SIZE: usize = 16;
+
+// The piece types a move may promote to, as numbered in a book move.
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// A move found in an opening book, along with how often it should be played relative to the
+/// other moves for the same position.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BookMove {
+    pub book_move: Move,
+    pub weight: u16,
+}
+
+#[derive(Copy, Clone)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// An opening book in the Polyglot format, probed by the position's Zobrist hash, which is the
+/// Polyglot key of the position.
+pub struct OpeningBook {
+    // Sorted by key, so that all the moves for a position are together.
+    entries: Vec<BookEntry>,
+    random_state: u64,
+}
+
+impl OpeningBook {
+    /// Reads a book from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_bytes(&fs::read(path)?))
+    }
+
+    /// Reads a book from its contents. Any incomplete entry at the end is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, OpeningBook};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// // A single entry for 1. e4 from the initial position. Moves are stored as the destination
+    /// // square, then the origin square, six bits each.
+    /// let mut bytes = engine.hash().to_be_bytes().to_vec();
+    /// bytes.extend_from_slice(&((12u16 << 6) | 28).to_be_bytes());
+    /// bytes.extend_from_slice(&1u16.to_be_bytes());
+    /// bytes.extend_from_slice(&0u32.to_be_bytes());
+    ///
+    /// let mut book = OpeningBook::from_bytes(&bytes);
+    /// let book_move = book.pick_move(&engine).unwrap();
+    /// assert_eq!(book_move.to_string(), "e2e4");
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            log(&format!(
+                "ignoring {} bytes at the end of the opening book",
+                bytes.len() % ENTRY_SIZE
+            ));
+        }
+
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| BookEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+            })
+            .collect();
+
+        // Books should already be sorted, but don't rely on it.
+        entries.sort_by_key(|entry| entry.key);
+
+        // Only used to vary the moves played, so the time is a good enough seed.
+        let random_state = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+            | 1;
+
+        Self {
+            entries,
+            random_state,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the legal moves the book has for the current position, in the order they are
+    /// stored.
+    pub fn moves(&self, position: &Position) -> Vec<BookMove> {
+        let key = position.hash();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                decode_move(entry.raw_move, position).map(|book_move| BookMove {
+                    book_move,
+                    weight: entry.weight,
+                })
+            })
+            .collect()
+    }
+
+    /// Picks one of the book moves for the current position at random, with the chance of each
+    /// being proportional to its weight. Returns `None` if the position isn't in the book.
+    pub fn pick_move(&mut self, position: &Position) -> Option<Move> {
+        let moves = self.moves(position);
+        let total_weight: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+
+        if total_weight == 0 {
+            return moves.first().map(|book_move| book_move.book_move);
+        }
+
+        let mut choice = next_random(&mut self.random_state) % total_weight;
+
+        for book_move in &moves {
+            if choice < book_move.weight as u64 {
+                return Some(book_move.book_move);
+            }
+
+            choice -= book_move.weight as u64;
+        }
+
+        unreachable!()
+    }
+}
+
+// Converts a move as stored in a book to the matching legal move, if there is one. Book moves are
+// the destination and origin squares, six bits each, then the promotion piece, if any.
+fn decode_move(raw_move: u16, position: &Position) -> Option<Move> {
+    let mut to = Square::new((raw_move & 0x3F) as usize);
+    let from = Square::new(((raw_move >> 6) & 0x3F) as usize);
+    let promote = match (raw_move >> 12) & 0x7 {
+        0 => None,
+        piece => Some(*PROMOTIONS.get(piece as usize - 1)?),
+    };
+
+    // Castling is stored as the king capturing its own rook, which is how Chess960 castling moves
+    // are written anyway.
+    let kings = position.board().bitboard_by_piece[PieceType::King.val()];
+
+    if kings & from.bitboard() != 0 && !position.is_chess960() {
+        to = match (from.index(), to.index()) {
+            (4, 7) => Square::new(6),
+            (4, 0) => Square::new(2),
+            (60, 63) => Square::new(62),
+            (60, 56) => Square::new(58),
+            _ => to,
+        };
+    }
+
+    position.legal_moves().into_iter().find(|legal_move| {
+        legal_move.from == from && legal_move.to == to && legal_move.promote == promote
+    })
+}
+
+/// How the moves of a book built from games are weighted against each other.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BookWeighting {
+    // By how often the move was played.
+    Frequency,
+    // By how well the move scored for the side playing it: two points for each win and one for
+    // each draw, as Polyglot weights its books.
+    Score,
+}
+
+// How often a move was played from a position, and how the games went for the side playing it.
+#[derive(Copy, Clone, Default)]
+struct MoveRecord {
+    games: u32,
+    wins: u32,
+    draws: u32,
+}
+
+/// Builds an opening book in the Polyglot format from the opening moves of a collection of games.
+/// Positions are keyed by their Polyglot keys, so the book can be read by other Polyglot tools as
+/// well as by `OpeningBook`.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{parse_pgn, BookBuilder, BookWeighting, Engine, OpeningBook};
+///
+/// let games = parse_pgn("1. e4 e5 1-0\n1. e4 c5 0-1\n1. d4 d5 1/2-1/2\n1. c4 *").unwrap();
+///
+/// let mut builder = BookBuilder::new();
+/// builder.min_games = 1;
+/// builder.weighting = BookWeighting::Score;
+///
+/// for game in &games {
+///     builder.add_game(game);
+/// }
+///
+/// let book = OpeningBook::from_bytes(&builder.to_bytes());
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// // 1. e4 won one game of two, 1. d4 drew its only game, and 1. c4 has no result to count.
+/// let moves: Vec<_> = book
+///     .moves(&engine)
+///     .iter()
+///     .map(|book_move| (book_move.book_move.to_string(), book_move.weight))
+///     .collect();
+/// assert_eq!(moves, [("e2e4".to_string(), 2), ("d2d4".to_string(), 1)]);
+///
+/// // The first move for the initial position is stored under its Polyglot key.
+/// let bytes = builder.to_bytes();
+/// let entry = bytes
+///     .chunks_exact(16)
+///     .find(|entry| entry[..8] == 0x463b96181691fc9cu64.to_be_bytes())
+///     .unwrap();
+/// assert_eq!(entry[8..10], ((12u16 << 6) | 28).to_be_bytes());
+/// ```
+pub struct BookBuilder {
+    // Only moves made in the first this many plies of a game are added.
+    pub max_plies: usize,
+    // Moves played in fewer games than this are left out.
+    pub min_games: u32,
+    pub weighting: BookWeighting,
+
+    records: HashMap<(u64, u16), MoveRecord>,
+}
+
+impl BookBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_plies: 30,
+            min_games: 3,
+            weighting: BookWeighting::Frequency,
+            records: HashMap::new(),
+        }
+    }
+
+    /// Adds the opening moves of a game's main line. Games without a result still count towards
+    /// how often their moves were played.
+    pub fn add_game(&mut self, game: &Game) {
+        let mut position = game.start().position().clone();
+        let result = game.result();
+
+        for game_move in game.main_line().iter().take(self.max_plies) {
+            let side = position.side_to_move();
+            let record = self
+                .records
+                .entry((
+                    position.hash(),
+                    encode_move(game_move.piece_move, &position),
+                ))
+                .or_default();
+
+            record.games += 1;
+
+            match (result, side) {
+                (GameResult::WhiteWins, Side::White) | (GameResult::BlackWins, Side::Black) => {
+                    record.wins += 1
+                }
+                (result, _) if result.is_draw() => record.draws += 1,
+                _ => {}
+            }
+
+            position.make_move(game_move.piece_move);
+        }
+    }
+
+    /// Writes the book, with its entries sorted by key and, for each position, by weight. Moves
+    /// with no weight are left out, and the weights for a position are scaled down together if
+    /// any is too large to store.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut positions: HashMap<u64, Vec<(u16, u64)>> = HashMap::new();
+
+        for (&(key, raw_move), record) in &self.records {
+            let weight = match self.weighting {
+                BookWeighting::Frequency => record.games as u64,
+                BookWeighting::Score => 2 * record.wins as u64 + record.draws as u64,
+            };
+
+            if record.games >= self.min_games && weight > 0 {
+                positions.entry(key).or_default().push((raw_move, weight));
+            }
+        }
+
+        let mut keys: Vec<u64> = positions.keys().copied().collect();
+        keys.sort_unstable();
+
+        let mut bytes = vec![];
+
+        for key in keys {
+            let moves = &mut positions.get_mut(&key).unwrap();
+            moves.sort_by_key(|&(raw_move, weight)| (std::cmp::Reverse(weight), raw_move));
+
+            let max_weight = moves[0].1;
+
+            for &(raw_move, weight) in moves.iter() {
+                let weight = if max_weight > u16::MAX as u64 {
+                    (weight * u16::MAX as u64 / max_weight).max(1)
+                } else {
+                    weight
+                };
+
+                bytes.extend_from_slice(&key.to_be_bytes());
+                bytes.extend_from_slice(&raw_move.to_be_bytes());
+                bytes.extend_from_slice(&(weight as u16).to_be_bytes());
+                bytes.extend_from_slice(&0u32.to_be_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Writes the book to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Converts a legal move to how it is stored in a book, as `decode_move` reads it. Castling is
+// stored as the king capturing its own rook.
+fn encode_move(piece_move: Move, position: &Position) -> u16 {
+    let to = match piece_move.kind {
+        MoveKind::KingsideCastle | MoveKind::QueensideCastle => {
+            let right = usize::from(piece_move.kind == MoveKind::QueensideCastle);
+            position.castling().rooks[position.side_to_move().val() * 2 + right]
+        }
+        _ => piece_move.to,
+    };
+
+    let promote = piece_move.promote.map_or(0, |promote| {
+        PROMOTIONS
+            .iter()
+            .position(|&piece_type| piece_type == promote)
+            .map_or(0, |index| index as u16 + 1)
+    });
+
+    (promote << 12) | ((piece_move.from.index() as u16) << 6) | to.index() as u16
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::{Side, TimeLimits};
+
+/// The time each player has for their moves: a base time, either for the whole game or for each
+/// period of a number of moves, and an increment added after every move.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chess_engine::TimeControl;
+///
+/// let blitz = TimeControl::parse("180+2").unwrap();
+/// assert_eq!(blitz, TimeControl::increment(Duration::from_secs(180), Duration::from_secs(2)));
+///
+/// let classical = TimeControl::parse("40/5400").unwrap();
+/// assert_eq!(classical.moves, Some(40));
+/// assert_eq!(classical.to_string(), "40/5400");
+///
+/// assert!(TimeControl::parse("forever").is_none());
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+    // The number of moves in each period, after which the base time is added again, or `None` if
+    // the base time is for the whole game.
+    pub moves: Option<u32>,
+}
+
+impl TimeControl {
+    /// The whole game in the given time, with nothing added.
+    pub fn sudden_death(base: Duration) -> Self {
+        Self::increment(base, Duration::ZERO)
+    }
+
+    /// The whole game in the given time, with the increment added after every move.
+    pub fn increment(base: Duration, increment: Duration) -> Self {
+        Self {
+            base,
+            increment,
+            moves: None,
+        }
+    }
+
+    /// The given number of moves in the given time, repeated for as long as the game lasts.
+    pub fn moves_in(moves: u32, base: Duration) -> Self {
+        Self {
+            base,
+            increment: Duration::ZERO,
+            moves: Some(moves.max(1)),
+        }
+    }
+
+    /// Parses a time control in seconds as written in the PGN `TimeControl` tag: `base` for
+    /// sudden death, `base+increment`, or `moves/base`, optionally with `+increment`. Fractions
+    /// of a second are allowed, such as `10+0.1`.
+    pub fn parse(notation: &str) -> Option<Self> {
+        let (moves, times) = match notation.split_once('/') {
+            Some((moves, times)) => (Some(moves.parse().ok().filter(|&moves| moves > 0)?), times),
+            None => (None, notation),
+        };
+
+        let (base, increment) = times.split_once('+').unwrap_or((times, "0"));
+
+        Some(Self {
+            base: Duration::try_from_secs_f64(base.parse().ok()?).ok()?,
+            increment: Duration::try_from_secs_f64(increment.parse().ok()?).ok()?,
+            moves,
+        })
+    }
+}
+
+/// Writes the time control as `parse` reads it.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(moves) = self.moves {
+            write!(f, "{}/", moves)?;
+        }
+
+        write!(f, "{}", self.base.as_secs_f64())?;
+
+        if !self.increment.is_zero() {
+            write!(f, "+{}", self.increment.as_secs_f64())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A chess clock for both players under a time control. Each move is charged to the clock with
+/// `tick`, which adds any time due and notices when a player runs out of time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chess_engine::{Clock, Side, TimeControl};
+///
+/// let seconds = Duration::from_secs;
+///
+/// // Two moves in ten seconds.
+/// let mut clock = Clock::new(TimeControl::moves_in(2, seconds(10)));
+///
+/// assert!(clock.tick(Side::White, seconds(4)));
+/// assert!(clock.tick(Side::Black, seconds(3)));
+/// assert_eq!(clock.remaining(Side::White), seconds(6));
+/// assert_eq!(clock.time_limits().moves_to_go, Some(1));
+///
+/// // The second move completes the period, so ten more seconds are added.
+/// assert!(clock.tick(Side::White, seconds(5)));
+/// assert_eq!(clock.remaining(Side::White), seconds(11));
+///
+/// // Black runs out of time.
+/// assert!(!clock.tick(Side::Black, seconds(8)));
+/// assert_eq!(clock.flagged(), Some(Side::Black));
+/// assert_eq!(clock.remaining(Side::Black), Duration::ZERO);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Clock {
+    time_control: TimeControl,
+    // The time left for each side, indexed by side.
+    remaining: [Duration; 2],
+    // The moves each side has made, for counting periods of moves.
+    moves_made: [u32; 2],
+    // The first side to run out of time, if either has.
+    flagged: Option<Side>,
+}
+
+impl Clock {
+    /// Starts both clocks with the base time of the time control.
+    pub fn new(time_control: TimeControl) -> Self {
+        Self {
+            time_control,
+            remaining: [time_control.base; 2],
+            moves_made: [0; 2],
+            flagged: None,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// The time the side has left.
+    pub fn remaining(&self, side: Side) -> Duration {
+        self.remaining[side.val()]
+    }
+
+    /// The side which has run out of time, if either has.
+    pub fn flagged(&self) -> Option<Side> {
+        self.flagged
+    }
+
+    /// Charges a move which took the given time to the side's clock, then adds the increment and,
+    /// if the move completes a period, the base time. Returns false if the side ran out of time
+    /// making it, in which case its clock stops at zero and nothing is added.
+    pub fn tick(&mut self, side: Side, elapsed: Duration) -> bool {
+        let Some(remaining) = self.remaining[side.val()].checked_sub(elapsed) else {
+            self.remaining[side.val()] = Duration::ZERO;
+            self.flagged.get_or_insert(side);
+            return false;
+        };
+
+        self.moves_made[side.val()] += 1;
+        self.remaining[side.val()] = remaining + self.time_control.increment;
+
+        if let Some(moves) = self.time_control.moves {
+            if self.moves_made[side.val()].is_multiple_of(moves) {
+                self.remaining[side.val()] += self.time_control.base;
+            }
+        }
+
+        true
+    }
+
+    /// The clocks as search limits, for the engine to budget its time on the next move. The moves
+    /// to go are those left in the current period of the side to move.
+    pub fn time_limits(&self) -> TimeLimits {
+        // White moves first, so it is Black's move if White has made more moves.
+        let side = if self.moves_made[Side::White.val()] > self.moves_made[Side::Black.val()] {
+            Side::Black
+        } else {
+            Side::White
+        };
+
+        TimeLimits {
+            white_time: Some(self.remaining(Side::White)),
+            black_time: Some(self.remaining(Side::Black)),
+            white_increment: Some(self.time_control.increment),
+            black_increment: Some(self.time_control.increment),
+            moves_to_go: self
+                .time_control
+                .moves
+                .map(|moves| moves - self.moves_made[side.val()] % moves),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashSet;
+
+use crate::random::next_random;
+use crate::{Engine, GameResult, PieceType, Position, Search, SearchLimits, Side, Square};
+
+// The piece code marking a rook which can still castle, in place of the rook's own code.
+const UNMOVED_ROOK: u8 = 6;
+
+// The piece types in the order of their codes in a packed position.
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// A position from a self-play game, labelled with the search's score for it and the result of
+/// the game, for training an evaluation network.
+#[derive(Clone, Debug)]
+pub struct TrainingPosition {
+    pub position: Position,
+    // The search score in centipawns, from White's point of view.
+    pub score: i32,
+    pub result: GameResult,
+}
+
+impl TrainingPosition {
+    /// The size of a packed position in bytes.
+    pub const SIZE: usize = 32;
+
+    /// Packs the position into 32 bytes, laid out as in the marlinformat used by several network
+    /// trainers, all little-endian:
+    ///
+    /// * The occupied squares, as a bitboard (8 bytes).
+    /// * A nibble for each occupied piece, from a1 to h8, low nibble first: the piece type from
+    ///   pawn (0) to king (5), or 6 for a rook which can still castle, plus 8 for Black (16 bytes).
+    /// * The side to move in the top bit, and the en passant square, or 64 if there is none
+    ///   (1 byte).
+    /// * The halfmove clock (1 byte) and fullmove number (2 bytes).
+    /// * The score, clamped to fit (2 bytes).
+    /// * The result: 0 if Black won, 1 for a draw and 2 if White won (1 byte).
+    /// * An unused byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{GameResult, Position, TrainingPosition};
+    ///
+    /// let mut position = Position::default();
+    /// position
+    ///     .set_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1")
+    ///     .unwrap();
+    ///
+    /// let sample = TrainingPosition {
+    ///     position,
+    ///     score: -35,
+    ///     result: GameResult::WhiteWins,
+    /// };
+    ///
+    /// let unpacked = TrainingPosition::from_bytes(&sample.to_bytes()).unwrap();
+    /// assert_eq!(unpacked.position.fen(), sample.position.fen());
+    /// assert_eq!((unpacked.score, unpacked.result), (-35, GameResult::WhiteWins));
+    /// ```
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let position = &self.position;
+        let castling = position.castling();
+
+        let mut bytes = [0; Self::SIZE];
+        let mut occupancy = 0u64;
+        let mut piece_count = 0;
+
+        for index in 0..64 {
+            let square = Square::new(index);
+
+            let Some((piece_type, side)) = position.piece_at(square) else {
+                continue;
+            };
+
+            let can_castle = (0..4).any(|right| {
+                castling.rights & (1 << right) != 0 && castling.rooks[right] == square
+            });
+
+            let code = if piece_type == PieceType::Rook && can_castle {
+                UNMOVED_ROOK
+            } else {
+                piece_type.val() as u8
+            };
+
+            let nibble = code | if side == Side::Black { 8 } else { 0 };
+
+            occupancy |= square.bitboard();
+            bytes[8 + piece_count / 2] |= nibble << (4 * (piece_count % 2));
+            piece_count += 1;
+        }
+
+        let en_passant = position
+            .en_passant()
+            .map_or(64, |square| square.index() as u8);
+        let black_to_move = if position.side_to_move() == Side::Black {
+            0x80
+        } else {
+            0
+        };
+
+        bytes[0..8].copy_from_slice(&occupancy.to_le_bytes());
+        bytes[24] = black_to_move | en_passant;
+        bytes[25] = position.halfmove_clock().min(u8::MAX as u32) as u8;
+        bytes[26..28].copy_from_slice(
+            &(position.fullmove_number().min(u16::MAX as u32) as u16).to_le_bytes(),
+        );
+        bytes[28..30].copy_from_slice(
+            &(self.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16).to_le_bytes(),
+        );
+        bytes[30] = match self.result {
+            GameResult::WhiteWins => 2,
+            GameResult::BlackWins => 0,
+            _ => 1,
+        };
+
+        bytes
+    }
+
+    /// Unpacks a position packed by `to_bytes`. Returns `None` if the bytes don't describe a
+    /// valid position.
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Option<Self> {
+        let occupancy = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+
+        if occupancy.count_ones() > 32 {
+            return None;
+        }
+
+        let mut board = [None; 64];
+        let mut castling = String::new();
+        let mut black_castling = String::new();
+
+        for (piece_count, index) in (0..64)
+            .filter(|index| occupancy & (1 << index) != 0)
+            .enumerate()
+        {
+            let nibble = (bytes[8 + piece_count / 2] >> (4 * (piece_count % 2))) & 0xF;
+            let side = if nibble & 8 != 0 {
+                Side::Black
+            } else {
+                Side::White
+            };
+
+            let piece_type = match nibble & 7 {
+                UNMOVED_ROOK => {
+                    // Rights are written as the rook's file, as in Shredder-FEN.
+                    let file = (b'a' + (index % 8) as u8) as char;
+
+                    match side {
+                        Side::White => castling.push(file.to_ascii_uppercase()),
+                        _ => black_castling.push(file),
+                    }
+
+                    PieceType::Rook
+                }
+                code => *PIECE_TYPES.get(code as usize)?,
+            };
+
+            board[index] = Some((piece_type, side));
+        }
+
+        castling += &black_castling;
+
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                match board[rank * 8 + file] {
+                    Some((piece_type, side)) => {
+                        if empty > 0 {
+                            fen += &empty.to_string();
+                            empty = 0;
+                        }
+
+                        let c = b"pnbrqk"[piece_type.val()] as char;
+                        fen.push(if side == Side::White {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c
+                        });
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen += &empty.to_string();
+            }
+
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        let side_to_move = if bytes[24] & 0x80 != 0 { "b" } else { "w" };
+        let en_passant = match bytes[24] & 0x7F {
+            64 => "-".to_string(),
+            index if index < 64 => Square::new(index as usize).to_string(),
+            _ => return None,
+        };
+
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let fen = format!(
+            "{} {} {} {} {} {}",
+            fen,
+            side_to_move,
+            castling,
+            en_passant,
+            bytes[25],
+            u16::from_le_bytes([bytes[26], bytes[27]])
+        );
+
+        let mut position = Position::default();
+        position.set_fen(&fen).ok()?;
+
+        Some(Self {
+            position,
+            score: i16::from_le_bytes([bytes[28], bytes[29]]) as i32,
+            result: match bytes[30] {
+                0 => GameResult::BlackWins,
+                1 => GameResult::DrawAdjudicated,
+                2 => GameResult::WhiteWins,
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// Settings for generating training data.
+#[derive(Clone, Debug)]
+pub struct DatagenConfig {
+    // The nodes searched for each move, which keeps games fast.
+    pub nodes: u64,
+    // The number of random moves each game starts with, so that games differ.
+    pub random_plies: u32,
+    // Games still going after this many plies are adjudicated as draws.
+    pub max_plies: u32,
+    // A game is adjudicated as won once the search scores it this many centipawns for one side.
+    pub win_score: i32,
+    // Positions scored beyond this many centipawns either way aren't recorded.
+    pub max_score: i32,
+    // Leave out positions in check, or whose best move is a capture or promotion, which a static
+    // evaluation can't be expected to judge.
+    pub skip_noisy: bool,
+    pub seed: u64,
+}
+
+impl Default for DatagenConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 5000,
+            random_plies: 8,
+            max_plies: 400,
+            win_score: 2500,
+            max_score: 2000,
+            skip_noisy: true,
+            seed: 1,
+        }
+    }
+}
+
+/// Plays fast self-play games and labels their positions for training an evaluation network.
+/// Positions already recorded, from this game or an earlier one, are left out.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{DataGenerator, DatagenConfig, GameResult};
+///
+/// let mut generator = DataGenerator::new(DatagenConfig {
+///     nodes: 300,
+///     max_plies: 60,
+///     ..Default::default()
+/// });
+///
+/// let positions = generator.play_game();
+/// assert!(!positions.is_empty());
+///
+/// for sample in &positions {
+///     assert!(!sample.position.in_check());
+///     assert!(sample.score.abs() <= 2000);
+///     assert_eq!(sample.result, positions[0].result);
+///     assert_ne!(sample.result, GameResult::Ongoing);
+/// }
+/// ```
+pub struct DataGenerator {
+    pub config: DatagenConfig,
+
+    search: Search,
+    // The hashes of the positions recorded so far.
+    seen: HashSet<u64>,
+    random_state: u64,
+}
+
+impl DataGenerator {
+    pub fn new(config: DatagenConfig) -> Self {
+        Self {
+            // The generator never leaves a state of zero.
+            random_state: config.seed | 1,
+            config,
+            search: Search::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Plays one game and returns the positions recorded from it, each labelled with the game's
+    /// result. Games ending during the random opening give no positions.
+    pub fn play_game(&mut self) -> Vec<TrainingPosition> {
+        let mut engine = Engine::default();
+        engine.set_initial_position();
+
+        for _ in 0..self.config.random_plies {
+            let moves = engine.legal_moves();
+
+            if moves.is_empty() {
+                return vec![];
+            }
+
+            let index = (next_random(&mut self.random_state) % moves.len() as u64) as usize;
+            engine.make_move(moves[index]);
+        }
+
+        let limits = SearchLimits {
+            nodes: Some(self.config.nodes),
+            ..Default::default()
+        };
+
+        self.search.new_game();
+
+        let mut recorded = vec![];
+        let mut plies = self.config.random_plies;
+
+        let result = loop {
+            if engine.game_result() != GameResult::Ongoing {
+                break engine.game_result();
+            }
+
+            if plies >= self.config.max_plies {
+                break GameResult::DrawAdjudicated;
+            }
+
+            let search_result = self.search.best_move_in_game(&engine, &limits);
+
+            let Some(best_move) = search_result.best_move else {
+                break engine.game_result();
+            };
+
+            let white_score = match engine.side_to_move() {
+                Side::White => search_result.score,
+                _ => -search_result.score,
+            };
+
+            if white_score >= self.config.win_score {
+                break GameResult::WhiteWins;
+            } else if white_score <= -self.config.win_score {
+                break GameResult::BlackWins;
+            }
+
+            let noisy = engine.in_check() || best_move.is_capture() || best_move.is_promotion();
+
+            if !(self.config.skip_noisy && noisy)
+                && white_score.abs() <= self.config.max_score
+                && self.seen.insert(engine.hash())
+            {
+                recorded.push((engine.position().clone(), white_score));
+            }
+
+            engine.make_move(best_move);
+            plies += 1;
+        };
+
+        recorded
+            .into_iter()
+            .map(|(position, score)| TrainingPosition {
+                position,
+                score,
+                result,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,480 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::pawns::distance;
+use crate::{
+    front_span, king_attacks, pawn_east_attacks, pawn_west_attacks, PieceType, Position, Side,
+    Square, DARK_SQUARES, ENDGAME_VALUES, FILE_A, FILE_H, LIGHT_SQUARES,
+};
+
+/// A bonus for positions known to be won, well above any ordinary evaluation but below mate
+/// scores, so the engine heads for them and then converts them.
+pub const KNOWN_WIN: i32 = 10000;
+
+// Bonuses for driving the lone king towards a corner, per square of distance from the centre, and
+// for bringing the kings together, per square closer than the furthest they can be apart.
+const PUSH_TO_CORNER: i32 = 20;
+const PUSH_CLOSE: i32 = 10;
+
+/// The scale factor of an endgame with nothing drawish about it, which keeps its endgame score
+/// whole.
+pub const SCALE_NORMAL: i32 = 64;
+
+// Scale factors for drawish endgames: bishops on opposite colours with no other pieces, and with
+// other pieces as well, and a rook and pawn against a rook with the defending king in front of the
+// pawn, or with the pawn on a rook's file.
+const SCALE_OPPOSITE_BISHOPS: i32 = 16;
+const SCALE_OPPOSITE_BISHOPS_WITH_PIECES: i32 = 48;
+const SCALE_ROOK_PAWN_BLOCKED: i32 = 8;
+const SCALE_ROOK_PAWN_EDGE: i32 = 32;
+
+// A bonus per rank for the pawn in a won king and pawn endgame, so the engine pushes it.
+const KPK_PAWN_ADVANCE: i32 = 10;
+
+// The bitbase covers a white pawn on files a to d and ranks 2 to 7, with every placement of the
+// kings and either side to move. Positions with the pawn on files e to h are looked up mirrored.
+const KPK_PAWN_SQUARES: usize = 24;
+const KPK_SIZE: usize = 2 * KPK_PAWN_SQUARES * 64 * 64;
+
+// The results of positions while the bitbase is being generated. They are bit flags so the results
+// of every move from a position can be combined.
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+impl Position {
+    /// Scores the position if it is an endgame the engine has specific knowledge of, in
+    /// centipawns from the perspective of the side to move, or returns `None` otherwise.
+    ///
+    /// King and pawn against king is looked up in a bitbase: a win scores `KNOWN_WIN` plus a
+    /// bonus for the pawn's advance, and a draw scores 0. A lone king against a queen or rook
+    /// scores `KNOWN_WIN` plus the stronger side's material, with bonuses for driving the lone
+    /// king into a corner and bringing the other king close to help mate it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, KNOWN_WIN};
+    ///
+    /// let mut engine = Engine::default();
+    ///
+    /// // The black king holds the draw in front of the rook's pawn.
+    /// engine.set_fen("k7/8/P7/1K6/8/8/8/8 w - - 0 1").unwrap();
+    /// assert_eq!(engine.evaluate_endgame(), Some(0));
+    ///
+    /// // The rook mates more easily with the black king in the corner than in the centre.
+    /// engine.set_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1").unwrap();
+    /// let cornered = engine.evaluate_endgame().unwrap();
+    /// engine.set_fen("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap();
+    /// let centred = engine.evaluate_endgame().unwrap();
+    /// assert!(cornered > centred && centred > KNOWN_WIN);
+    ///
+    /// // Other endgames are left to the ordinary evaluation.
+    /// engine.set_initial_position();
+    /// assert_eq!(engine.evaluate_endgame(), None);
+    /// ```
+    pub fn evaluate_endgame(&self) -> Option<i32> {
+        let board = self.board();
+        let sides = &board.bitboard_by_side;
+
+        // Every endgame known so far has one side left with a bare king.
+        let strong_side = match (
+            sides[Side::White.val()].count_ones(),
+            sides[Side::Black.val()].count_ones(),
+        ) {
+            (_, 1) => Side::White,
+            (1, _) => Side::Black,
+            _ => return None,
+        };
+        let weak_side = strong_side.flip();
+
+        let king_square = |side: Side| {
+            Square::from_bitboard(
+                board.bitboard_by_piece[PieceType::King.val()] & sides[side.val()],
+            )
+        };
+        let strong_king = king_square(strong_side)?;
+        let weak_king = king_square(weak_side)?;
+
+        let score = if sides[strong_side.val()].count_ones() == 2
+            && board.count(PieceType::Pawn, strong_side) == 1
+        {
+            let pawn = Square::from_bitboard(
+                board.bitboard_by_piece[PieceType::Pawn.val()] & sides[strong_side.val()],
+            )?;
+
+            if !kpk_wins(
+                strong_side,
+                strong_king,
+                pawn,
+                weak_king,
+                self.side_to_move(),
+            ) {
+                0
+            } else {
+                let rank = match strong_side {
+                    Side::White => pawn.rank().index(),
+                    _ => 7 - pawn.rank().index(),
+                };
+
+                KNOWN_WIN + ENDGAME_VALUES[PieceType::Pawn.val()] + KPK_PAWN_ADVANCE * rank as i32
+            }
+        } else if board.count(PieceType::Queen, strong_side)
+            + board.count(PieceType::Rook, strong_side)
+            > 0
+        {
+            // The lone king may be stalemated, which quiescence search wouldn't notice.
+            if self.side_to_move() == weak_side && !self.in_check() && self.legal_moves().is_empty()
+            {
+                return Some(0);
+            }
+
+            let close = 7 - distance(strong_king.index(), weak_king.index());
+
+            KNOWN_WIN
+                + self.material_scores()[strong_side.val()].1
+                + PUSH_TO_CORNER * centre_distance(weak_king.index())
+                + PUSH_CLOSE * close
+        } else {
+            return None;
+        };
+
+        Some(if self.side_to_move() == strong_side {
+            score
+        } else {
+            -score
+        })
+    }
+
+    /// Returns how much of its endgame advantage the given side can expect to keep, out of
+    /// `SCALE_NORMAL`. Material configurations which are hard to win even a pawn or two up, such
+    /// as bishops on opposite colours or a rook and pawn against a rook with the defending king
+    /// in front of the pawn, are scaled down so the search doesn't overvalue them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Side, SCALE_NORMAL};
+    ///
+    /// let mut engine = Engine::default();
+    ///
+    /// // Bishops on the same colour squares, then on opposite colours.
+    /// engine.set_fen("8/4k3/8/2bP4/8/8/3BK3/8 w - - 0 1").unwrap();
+    /// assert_eq!(engine.endgame_scale(Side::White), SCALE_NORMAL);
+    /// engine.set_fen("8/4k3/8/1b1P4/8/8/3BK3/8 w - - 0 1").unwrap();
+    /// assert!(engine.endgame_scale(Side::White) < SCALE_NORMAL / 2);
+    ///
+    /// // The black king blocks the pawn, and White's extra pawn is worth little.
+    /// engine.set_fen("3k4/8/8/3P4/8/8/r7/3RK3 w - - 0 1").unwrap();
+    /// assert!(engine.endgame_scale(Side::White) < SCALE_NORMAL / 4);
+    /// ```
+    pub fn endgame_scale(&self, strong_side: Side) -> i32 {
+        let board = self.board();
+        let weak_side = strong_side.flip();
+        let count = |piece_type, side| board.count(piece_type, side);
+        let pieces = |side| {
+            [
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ]
+            .map(|piece_type| count(piece_type, side))
+        };
+
+        let bishops = board.bitboard_by_piece[PieceType::Bishop.val()];
+
+        if count(PieceType::Bishop, Side::White) == 1
+            && count(PieceType::Bishop, Side::Black) == 1
+            && bishops & LIGHT_SQUARES != 0
+            && bishops & DARK_SQUARES != 0
+        {
+            return if pieces(Side::White) == [0, 1, 0, 0] && pieces(Side::Black) == [0, 1, 0, 0] {
+                SCALE_OPPOSITE_BISHOPS
+            } else {
+                SCALE_OPPOSITE_BISHOPS_WITH_PIECES
+            };
+        }
+
+        if pieces(strong_side) == [0, 0, 1, 0]
+            && pieces(weak_side) == [0, 0, 1, 0]
+            && count(PieceType::Pawn, strong_side) == 1
+            && count(PieceType::Pawn, weak_side) == 0
+        {
+            let side_pieces = |piece_type: PieceType, side: Side| {
+                board.bitboard_by_piece[piece_type.val()] & board.bitboard_by_side[side.val()]
+            };
+            let pawn = side_pieces(PieceType::Pawn, strong_side).trailing_zeros() as usize;
+            let weak_king = side_pieces(PieceType::King, weak_side);
+
+            if front_span(pawn, strong_side) & weak_king != 0 {
+                return SCALE_ROOK_PAWN_BLOCKED;
+            }
+
+            if (FILE_A | FILE_H) & (1 << pawn) != 0 {
+                return SCALE_ROOK_PAWN_EDGE;
+            }
+        }
+
+        SCALE_NORMAL
+    }
+}
+
+/// Returns whether the side with the pawn wins a king and pawn against king endgame with best
+/// play. The bitbase behind this is generated the first time it is needed.
+///
+/// # Arguments
+///
+/// * `strong_side` - The side with the pawn.
+/// * `strong_king` - The square of the king of the side with the pawn.
+/// * `pawn` - The square of the pawn.
+/// * `weak_king` - The square of the lone king.
+/// * `side_to_move` - The side to move.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{kpk_wins, Side, Square};
+///
+/// let square = |name: &str| name.parse::<Square>().unwrap();
+///
+/// // With the white king on the sixth rank in front of its pawn, White always wins.
+/// for side_to_move in [Side::White, Side::Black] {
+///     assert!(kpk_wins(Side::White, square("e6"), square("e4"), square("e8"), side_to_move));
+/// }
+///
+/// // Whoever has the opposition decides the result.
+/// assert!(kpk_wins(Side::Black, square("e4"), square("e5"), square("e2"), Side::White));
+/// assert!(!kpk_wins(Side::Black, square("e4"), square("e5"), square("e2"), Side::Black));
+/// ```
+pub fn kpk_wins(
+    strong_side: Side,
+    strong_king: Square,
+    pawn: Square,
+    weak_king: Square,
+    side_to_move: Side,
+) -> bool {
+    // Look the position up as if White had the pawn, on one of files a to d.
+    let mut squares = [strong_king.index(), pawn.index(), weak_king.index()];
+
+    if strong_side == Side::Black {
+        squares = squares.map(|square| square ^ 56);
+    }
+
+    if squares[1] % 8 > 3 {
+        squares = squares.map(|square| square ^ 7);
+    }
+
+    let [strong_king, pawn, weak_king] = squares;
+    let index = kpk_index(side_to_move != strong_side, strong_king, pawn, weak_king);
+    let bitbase = kpk_bitbase();
+
+    bitbase[index / 64] & (1 << (index % 64)) != 0
+}
+
+// The bitbase, built the first time it is needed.
+#[cfg(feature = "std")]
+fn kpk_bitbase() -> &'static [u64] {
+    static KPK_BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+    KPK_BITBASE.get_or_init(generate_kpk_bitbase)
+}
+
+// Without std there is no `OnceLock`, so the bitbase is published through an atomic pointer.
+// Threads which find it missing at the same time each build it, and all but the first to finish
+// throw theirs away.
+#[cfg(not(feature = "std"))]
+fn kpk_bitbase() -> &'static [u64] {
+    static KPK_BITBASE: AtomicPtr<Vec<u64>> = AtomicPtr::new(core::ptr::null_mut());
+
+    let mut bitbase = KPK_BITBASE.load(Ordering::Acquire);
+
+    if bitbase.is_null() {
+        let built = alloc::boxed::Box::into_raw(alloc::boxed::Box::new(generate_kpk_bitbase()));
+
+        bitbase = match KPK_BITBASE.compare_exchange(
+            core::ptr::null_mut(),
+            built,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => built,
+            Err(existing) => {
+                // SAFETY: `built` came from `Box::into_raw` just above and was never shared.
+                drop(unsafe { alloc::boxed::Box::from_raw(built) });
+                existing
+            }
+        };
+    }
+
+    // SAFETY: The pointer is only ever set once, to a box which is never freed.
+    unsafe { &*bitbase }
+}
+
+// The index of a position in the bitbase, with White's pawn on files a to d and ranks 2 to 7.
+fn kpk_index(black_to_move: bool, white_king: usize, pawn: usize, black_king: usize) -> usize {
+    let pawn_index = (pawn / 8 - 1) * 4 + pawn % 8;
+
+    ((black_to_move as usize * KPK_PAWN_SQUARES + pawn_index) * 64 + white_king) * 64 + black_king
+}
+
+// Works out the result of every position by retrograde analysis: positions won or drawn outright
+// are marked first, then the others are resolved from the results of their moves until nothing
+// changes. Positions still unresolved at the end are draws, as White can't force progress.
+fn generate_kpk_bitbase() -> Vec<u64> {
+    let mut results = vec![INVALID; KPK_SIZE];
+
+    for black_to_move in [false, true] {
+        for pawn in kpk_pawn_squares() {
+            for white_king in 0..64 {
+                for black_king in 0..64 {
+                    let index = kpk_index(black_to_move, white_king, pawn, black_king);
+                    results[index] = classify_kpk(black_to_move, white_king, pawn, black_king);
+                }
+            }
+        }
+    }
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for black_to_move in [false, true] {
+            for pawn in kpk_pawn_squares() {
+                for white_king in 0..64 {
+                    for black_king in 0..64 {
+                        let index = kpk_index(black_to_move, white_king, pawn, black_king);
+
+                        if results[index] != UNKNOWN {
+                            continue;
+                        }
+
+                        let result =
+                            resolve_kpk(&results, black_to_move, white_king, pawn, black_king);
+
+                        if result != UNKNOWN {
+                            results[index] = result;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut bitbase = vec![0; KPK_SIZE / 64];
+
+    for (index, &result) in results.iter().enumerate() {
+        if result == WIN {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    bitbase
+}
+
+fn kpk_pawn_squares() -> impl Iterator<Item = usize> {
+    (1..7).flat_map(|rank| (0..4).map(move |file| rank * 8 + file))
+}
+
+// The result of a position which can be told without looking at its moves, or `UNKNOWN`.
+fn classify_kpk(black_to_move: bool, white_king: usize, pawn: usize, black_king: usize) -> u8 {
+    let white_king_attacks = king_attacks(1 << white_king);
+    let black_king_attacks = king_attacks(1 << black_king);
+    let pawn_attacks = pawn_east_attacks(1 << pawn, !0, Side::White)
+        | pawn_west_attacks(1 << pawn, !0, Side::White);
+
+    if distance(white_king, black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (!black_to_move && pawn_attacks & (1 << black_king) != 0)
+    {
+        return INVALID;
+    }
+
+    let promotion = pawn + 8;
+
+    if !black_to_move
+        && pawn / 8 == 6
+        && promotion != white_king
+        && promotion != black_king
+        && (distance(black_king, promotion) > 1 || distance(white_king, promotion) == 1)
+    {
+        // The pawn promotes and the new queen can't be taken.
+        return WIN;
+    }
+
+    if black_to_move
+        && (black_king_attacks & !(white_king_attacks | pawn_attacks) == 0
+            || black_king_attacks & !white_king_attacks & (1 << pawn) != 0)
+    {
+        // Black is stalemated, or takes the undefended pawn.
+        return DRAW;
+    }
+
+    UNKNOWN
+}
+
+// Combines the results of every move from a position. The side to move wins or draws if any of
+// its moves does, and otherwise is held to the other result once every move is resolved.
+fn resolve_kpk(
+    results: &[u8],
+    black_to_move: bool,
+    white_king: usize,
+    pawn: usize,
+    black_king: usize,
+) -> u8 {
+    let mut combined = INVALID;
+
+    if black_to_move {
+        for to in 0..64 {
+            if king_attacks(1 << black_king) & (1 << to) != 0 {
+                combined |= results[kpk_index(false, white_king, pawn, to)];
+            }
+        }
+    } else {
+        for to in 0..64 {
+            if king_attacks(1 << white_king) & (1 << to) != 0 {
+                combined |= results[kpk_index(true, to, pawn, black_king)];
+            }
+        }
+
+        // Promotions are already resolved, so only pushes within the bitbase are left.
+        if pawn / 8 < 6 {
+            combined |= results[kpk_index(true, white_king, pawn + 8, black_king)];
+        }
+
+        if pawn / 8 == 1 && pawn + 8 != white_king && pawn + 8 != black_king {
+            combined |= results[kpk_index(true, white_king, pawn + 16, black_king)];
+        }
+    }
+
+    let (good, bad) = if black_to_move {
+        (DRAW, WIN)
+    } else {
+        (WIN, DRAW)
+    };
+
+    if combined & good != 0 {
+        good
+    } else if combined & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+// How many files and ranks a square is from the four centre squares, from 0 in the centre up to
+// 6 in a corner.
+fn centre_distance(square: usize) -> i32 {
+    let file = square % 8;
+    let rank = square / 8;
+
+    (file.max(7 - file) + rank.max(7 - rank)) as i32 - 8
+}
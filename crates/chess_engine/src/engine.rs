@@ -1,6 +1,13 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
 use crate::board::*;
+use crate::{Position, Square};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn = 0,
     Knight = 1,
@@ -17,7 +24,8 @@ impl PieceType {
     }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     White = 0,
     Black = 1,
@@ -48,175 +56,443 @@ impl CheckIndex for Bitboard {
     }
 }
 
-#[derive(Copy, Clone)]
+/// What kind of move a move is, beyond moving a piece from one square to another. Captures and
+/// promotions are given by the `captured` and `promote` fields of [`Move`] instead.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveKind {
+    Normal,
+    DoublePush,
+    EnPassant,
+    KingsideCastle,
+    QueensideCastle,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
-    pub from: u32,
-    pub to: u32,
+    pub from: Square,
+    pub to: Square,
     pub promote: Option<PieceType>,
+    // The type of piece captured by the move, if any. Filled in by move generation.
+    pub captured: Option<PieceType>,
+    // Filled in by move generation, like `captured`.
+    pub kind: MoveKind,
 }
 
-pub struct Engine {
-    // Which type of piece, if any, is on a given square.
-    squares_by_type: [Option<PieceType>; 64],
+impl Move {
+    /// Parses a move in the coordinate notation used by UCI, such as `e2e4`, or `e7e8q` for a
+    /// promotion. The move isn't checked against any position, so `captured` is left unset and
+    /// `kind` is [`MoveKind::Normal`]; use [`Engine::legal_moves`] to find the matching legal
+    /// move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Move, PieceType};
+    ///
+    /// let piece_move = Move::from_uci_str("e7e8q").unwrap();
+    /// assert_eq!((piece_move.from.index(), piece_move.to.index()), (52, 60));
+    /// assert_eq!(piece_move.promote, Some(PieceType::Queen));
+    /// assert_eq!(piece_move.to_string(), "e7e8q");
+    ///
+    /// assert!(Move::from_uci_str("e2e9").is_none());
+    /// ```
+    pub fn from_uci_str(s: &str) -> Option<Move> {
+        let from = s.get(0..2)?.parse().ok()?;
+        let to = s.get(2..4)?.parse().ok()?;
+
+        let promote = match s.get(4..)? {
+            "" => None,
+            "n" => Some(PieceType::Knight),
+            "b" => Some(PieceType::Bishop),
+            "r" => Some(PieceType::Rook),
+            "q" => Some(PieceType::Queen),
+            _ => return None,
+        };
 
-    board: Board,
-}
+        Some(Move {
+            from,
+            to,
+            promote,
+            captured: None,
+            kind: MoveKind::Normal,
+        })
+    }
 
-impl Engine {
-    pub fn name(&self) -> &str {
-        "Chess Engine"
+    /// Returns true if the move captures a piece, including en passant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, MoveKind, PieceType};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    ///
+    /// let en_passant = engine
+    ///     .legal_moves()
+    ///     .into_iter()
+    ///     .find(|piece_move| piece_move.is_en_passant())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(en_passant.to_string(), "e5d6");
+    /// assert!(en_passant.is_capture());
+    /// assert_eq!(en_passant.captured, Some(PieceType::Pawn));
+    /// assert_eq!(en_passant.kind, MoveKind::EnPassant);
+    /// ```
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
     }
 
-    pub fn author(&self) -> &str {
-        "Nathan Gardiner"
+    pub fn is_promotion(&self) -> bool {
+        self.promote.is_some()
     }
 
-    pub fn set_initial_position(&mut self) {
-        for file in 1..=8 {
-            self.set_square(7 + file, Side::White, Some(PieceType::Pawn));
-            self.set_square(47 + file, Side::Black, Some(PieceType::Pawn));
-        }
+    /// Returns true if the move is a pawn moving two squares forward from its starting rank.
+    pub fn is_double_push(&self) -> bool {
+        self.kind == MoveKind::DoublePush
+    }
 
-        self.set_square(1, Side::White, Some(PieceType::Knight));
-        self.set_square(6, Side::White, Some(PieceType::Knight));
-        self.set_square(57, Side::Black, Some(PieceType::Knight));
-        self.set_square(62, Side::Black, Some(PieceType::Knight));
+    pub fn is_en_passant(&self) -> bool {
+        self.kind == MoveKind::EnPassant
+    }
 
-        self.set_square(2, Side::White, Some(PieceType::Bishop));
-        self.set_square(5, Side::White, Some(PieceType::Bishop));
-        self.set_square(58, Side::Black, Some(PieceType::Bishop));
-        self.set_square(61, Side::Black, Some(PieceType::Bishop));
+    /// Returns true if the move castles to either side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, MoveKind};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    ///
+    /// let castles: Vec<MoveKind> = engine
+    ///     .legal_moves()
+    ///     .into_iter()
+    ///     .filter(|piece_move| piece_move.is_castle())
+    ///     .map(|piece_move| piece_move.kind)
+    ///     .collect();
+    ///
+    /// assert_eq!(castles, [MoveKind::KingsideCastle, MoveKind::QueensideCastle]);
+    /// ```
+    pub fn is_castle(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::KingsideCastle | MoveKind::QueensideCastle
+        )
+    }
 
-        self.set_square(0, Side::White, Some(PieceType::Rook));
-        self.set_square(7, Side::White, Some(PieceType::Rook));
-        self.set_square(56, Side::Black, Some(PieceType::Rook));
-        self.set_square(63, Side::Black, Some(PieceType::Rook));
+    /// Returns true if the move neither captures nor promotes.
+    pub fn is_quiet(&self) -> bool {
+        !self.is_capture() && !self.is_promotion()
+    }
+}
 
-        self.set_square(3, Side::White, Some(PieceType::Queen));
-        self.set_square(59, Side::Black, Some(PieceType::Queen));
+impl fmt::Display for Move {
+    /// Formats the move in the coordinate notation used by UCI.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+
+        match self.promote {
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Queen) => write!(f, "q"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The state of a game, as determined by the current position and the moves leading up to it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    Ongoing,
+    WhiteWins,
+    BlackWins,
+    DrawStalemate,
+    DrawRepetition,
+    DrawFiftyMove,
+    DrawMaterial,
+    // Declared a draw without the rules requiring it, such as by agreement or adjudication.
+    DrawAdjudicated,
+}
 
-        self.set_square(4, Side::White, Some(PieceType::King));
-        self.set_square(60, Side::Black, Some(PieceType::King));
+impl GameResult {
+    pub fn is_draw(&self) -> bool {
+        matches!(
+            self,
+            GameResult::DrawStalemate
+                | GameResult::DrawRepetition
+                | GameResult::DrawFiftyMove
+                | GameResult::DrawMaterial
+                | GameResult::DrawAdjudicated
+        )
     }
+}
 
-    fn set_square(&mut self, square_idx: usize, side: Side, piece_type: Option<PieceType>) {
-        self.squares_by_type[square_idx] = piece_type;
+/// The reason a FEN string could not be parsed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FenError {
+    MissingField(&'static str),
+    InvalidPlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidClock(String),
+}
 
-        if let Some(piece_type) = piece_type {
-            // Set the square.
-            self.board.bitboard_by_side[side.val()] |= 1 << square_idx;
-            self.board.bitboard_by_side[side.flip().val()] &= !(1 << square_idx);
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::InvalidPlacement(placement) => {
+                write!(f, "invalid piece placement '{}'", placement)
+            }
+            FenError::InvalidSideToMove(side) => write!(f, "invalid side to move '{}'", side),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "invalid castling rights '{}'", castling)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square '{}'", square)
+            }
+            FenError::InvalidClock(clock) => write!(f, "invalid move clock '{}'", clock),
+        }
+    }
+}
 
-            self.board.bitboard_by_piece[piece_type.val()] |= 1 << square_idx;
-        } else {
-            // Clear the square.
-            self.board.bitboard_by_side[side.val()] &= !(1 << square_idx);
+impl core::error::Error for FenError {}
+
+/// The reason a move could not be made.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    // There is no piece on the square moved from.
+    EmptySquare(Square),
+    // The piece on the square moved from belongs to the side not to move.
+    WrongSide(Square),
+    // The move isn't legal in the position, such as one leaving the king in check.
+    IllegalMove(Move),
+}
 
-            for i in 0..(PieceType::Count.val()) {
-                self.board.bitboard_by_piece[i] &= !(1 << square_idx);
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::EmptySquare(square) => write!(f, "no piece on {}", square),
+            MoveError::WrongSide(square) => {
+                write!(f, "the piece on {} belongs to the side not to move", square)
             }
+            MoveError::IllegalMove(piece_move) => write!(f, "illegal move {}", piece_move),
         }
     }
+}
 
-    pub fn make_move(&mut self, piece_move: Move) {
-        let from_index = piece_move.from as usize;
-        let to_index = piece_move.to as usize;
+impl core::error::Error for MoveError {}
+
+/// A chess game in progress: the current position, along with the history of the game needed to
+/// detect repetitions. An engine derefs to its current [`Position`], so all of the position's
+/// methods can be called on it directly.
+///
+/// The attack tables, magics and hash keys used to play and search are statics shared by every
+/// engine, built at compile time or on first use, so an engine holds only its own game. Engines
+/// are cheap to create and clone, and a server or tournament runner can keep one for each game
+/// it plays.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::Engine;
+///
+/// let engines: Vec<Engine> = (0..1000)
+///     .map(|_| {
+///         let mut engine = Engine::default();
+///         engine.set_initial_position();
+///         engine
+///     })
+///     .collect();
+///
+/// assert!(std::mem::size_of::<Engine>() < 512);
+/// assert!(engines.iter().all(|engine| engine.legal_moves().len() == 20));
+/// ```
+#[derive(Clone, Default)]
+pub struct Engine {
+    position: Position,
 
-        // Ascertain which side is making the move.
-        let side = if self.board.bitboard_by_side[Side::White.val()] & (1 << piece_move.from) != 0 {
-            Side::White
-        } else {
-            Side::Black
-        };
+    // Hashes of every previous position in the game, used to detect repetitions.
+    history: Vec<u64>,
+}
+
+impl Engine {
+    pub fn name(&self) -> &str {
+        "Chess Engine"
+    }
 
-        // Ascertain the piece type.
-        let from_piece_type = self.squares_by_type[from_index];
+    pub fn author(&self) -> &str {
+        "Nathan Gardiner"
+    }
 
-        if from_piece_type.is_none() {
-            println!("Invalid move made! Square {} has no piece!", from_index);
-            return;
-        }
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
 
-        let to_piece_type = piece_move
-            .promote
-            .unwrap_or_else(|| from_piece_type.unwrap());
+    /// The hashes of the positions before the current one, oldest first, back to the last time
+    /// the position was set.
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
 
-        self.set_square(from_index, side, None);
-        self.set_square(to_index, side, Some(to_piece_type));
+    /// Sets up the initial position, forgetting the moves played so far.
+    pub fn set_initial_position(&mut self) {
+        self.position.set_initial_position();
+        self.history.clear();
     }
 
-    pub fn print_board(&self) {
-        let mut out = String::from("    a   b   c   d   e   f   g   h\n");
-        out += "  +---+---+---+---+---+---+---+---+\n";
+    /// Sets up the position described by a FEN string, as [`Position::set_fen`] does, forgetting
+    /// the moves played so far. If the string is invalid, the game is left unchanged.
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        self.position.set_fen(fen)?;
+        self.history.clear();
+
+        Ok(())
+    }
 
-        for rank in (1..=8).rev() {
-            out += format!("{} |", rank).as_str();
+    /// Sets up one of the Chess960 starting positions, as [`Position::set_chess960_position`]
+    /// does, forgetting the moves played so far.
+    pub fn set_chess960_position(&mut self, number: u32) {
+        self.position.set_chess960_position(number);
+        self.history.clear();
+    }
 
-            for file in 0..8 {
-                let index = ((rank - 1) * 8) + file;
+    /// Sets whether castling moves are written as in Chess960. See [`Position::set_chess960`].
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.position.set_chess960(chess960);
+    }
 
-                let c = if let Some(piece_type) = self.squares_by_type[index] {
-                    let side = if self.board.bitboard_by_side[Side::White.val()].check_index(index)
-                    {
-                        Side::White
-                    } else {
-                        Side::Black
-                    };
+    /// Sets up a position, forgetting the moves played so far.
+    pub fn set_position(&mut self, position: Position) {
+        self.position = position;
+        self.history.clear();
+    }
 
-                    self.char_from_piece(piece_type, side)
-                } else {
-                    ' '
-                };
+    /// Starts a new game from the initial position, forgetting the previous game's history. Any
+    /// search state kept between moves should be reset too, with `Search::new_game`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Engine;
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    /// let initial_hash = engine.hash();
+    ///
+    /// let first_move = engine.legal_moves()[0];
+    /// engine.make_move(first_move);
+    ///
+    /// engine.new_game();
+    /// assert_eq!(engine.hash(), initial_hash);
+    /// ```
+    pub fn new_game(&mut self) {
+        self.set_initial_position();
+    }
 
-                out += format!(" {} |", c).as_str();
-            }
+    /// Makes a move in the current position without checking that it is legal, remembering the
+    /// position it was made from. See [`Position::make_move`].
+    pub fn make_move(&mut self, piece_move: Move) {
+        self.history.push(self.position.hash());
+        self.position.make_move(piece_move);
+    }
 
-            out += format!(" {}\n", rank).as_str();
-            out += "  +---+---+---+---+---+---+---+---+\n";
-        }
+    /// Makes a move in the current position if it is legal, remembering the position it was made
+    /// from. See [`Position::try_make_move`].
+    pub fn try_make_move(&mut self, piece_move: Move) -> Result<(), MoveError> {
+        let hash = self.position.hash();
 
-        out += "    a   b   c   d   e   f   g   h";
+        self.position.try_make_move(piece_move)?;
+        self.history.push(hash);
 
-        println!("{}", out);
+        Ok(())
     }
 
-    fn char_from_piece(&self, piece_type: PieceType, side: Side) -> char {
-        let char = match piece_type {
-            PieceType::Pawn => 'P',
-            PieceType::Knight => 'N',
-            PieceType::Bishop => 'B',
-            PieceType::Rook => 'R',
-            PieceType::Queen => 'Q',
-            PieceType::King => 'K',
-            PieceType::Count => '?',
-        };
+    /// Determines whether the game has ended, and if so, how.
+    ///
+    /// Checkmate and stalemate take precedence over the other draw conditions, which are checked
+    /// in the order: fifty-move rule, threefold repetition, insufficient material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, GameResult, Move};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    /// assert_eq!(engine.game_result(), GameResult::Ongoing);
+    ///
+    /// // Fool's mate: 1. f3 e5 2. g4 Qh4#
+    /// for notation in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+    ///     engine.make_move(Move::from_uci_str(notation).unwrap());
+    /// }
+    /// assert_eq!(engine.game_result(), GameResult::BlackWins);
+    /// ```
+    pub fn game_result(&self) -> GameResult {
+        if self.legal_moves().is_empty() {
+            return if !self.in_check() {
+                GameResult::DrawStalemate
+            } else if self.side_to_move() == Side::White {
+                GameResult::BlackWins
+            } else {
+                GameResult::WhiteWins
+            };
+        }
 
-        if side == Side::White {
-            char
+        if self.halfmove_clock() >= 100 {
+            GameResult::DrawFiftyMove
+        } else if self.is_threefold_repetition() {
+            GameResult::DrawRepetition
+        } else if self.is_insufficient_material() {
+            GameResult::DrawMaterial
         } else {
-            char.to_ascii_lowercase()
+            GameResult::Ongoing
         }
     }
 
-    pub fn generate_moves(&self, side: Side) -> Vec<Move> {
-        let mut moves: Vec<Move> = vec![];
-
-        let mut pawn_moves = self.board.generate_pawn_moves(side);
-        moves.append(&mut pawn_moves);
+    fn is_threefold_repetition(&self) -> bool {
+        // Only positions since the last irreversible move, with the same side to move, can repeat.
+        let repetitions = self
+            .history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock() as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|hash| **hash == self.hash())
+            .count();
+
+        repetitions >= 2
+    }
+}
 
-        // Knight moves are not yet fully implemented, so leave out for now.
-        //let mut knight_moves = self.board.generate_knight_moves(side);
-        //moves.append(&mut knight_moves);
+impl fmt::Display for Engine {
+    /// Writes a diagram of the current position, as [`Position`] does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.position.fmt(f)
+    }
+}
 
-        moves
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Engine")
+            .field("position", &self.position)
+            .field("history", &self.history.len())
+            .finish()
     }
 }
 
-impl Default for Engine {
-    fn default() -> Self {
-        Self {
-            squares_by_type: [None; 64],
-            board: Board::new(),
-        }
+impl Deref for Engine {
+    type Target = Position;
+
+    fn deref(&self) -> &Position {
+        &self.position
     }
 }
@@ -0,0 +1,297 @@
+use std::fmt;
+
+use crate::log::log;
+use crate::{Engine, FenError, Move, Position, Search, SearchLimits};
+
+/// An operation from an EPD record, such as `bm Nf3;` or `id "WAC.001";`. Quoted operands are
+/// stored without their quotes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// The reason an EPD record could not be parsed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EpdError {
+    InvalidPosition(FenError),
+    InvalidOperation(String),
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpdError::InvalidPosition(error) => write!(f, "{}", error),
+            EpdError::InvalidOperation(operation) => {
+                write!(f, "invalid operation '{}'", operation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+impl Engine {
+    /// Sets up the position from an EPD record, returning its operations. The halfmove clock and
+    /// fullmove number are taken from the `hmvc` and `fmvn` operations, if present. If the record
+    /// is invalid, the position is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Engine;
+    ///
+    /// let mut engine = Engine::default();
+    /// let operations = engine
+    ///     .set_position_from_epd(r#"4k3/8/8/8/8/8/8/R3K3 w Q - bm Ra8+; id "test 1";"#)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(operations[0].opcode, "bm");
+    /// assert_eq!(operations[0].operands, ["Ra8+"]);
+    /// assert_eq!(operations[1].operands, ["test 1"]);
+    /// ```
+    pub fn set_position_from_epd(&mut self, epd: &str) -> Result<Vec<EpdOperation>, EpdError> {
+        let mut fields = epd.trim_start().splitn(5, char::is_whitespace);
+        let position: Vec<&str> = fields.by_ref().take(4).collect();
+        let operations = parse_operations(fields.next().unwrap_or_default())?;
+
+        let clock = |opcode: &str, default: &str| {
+            operations
+                .iter()
+                .find(|operation| operation.opcode == opcode)
+                .and_then(|operation| operation.operands.first().cloned())
+                .unwrap_or_else(|| default.to_string())
+        };
+
+        let fen = format!(
+            "{} {} {}",
+            position.join(" "),
+            clock("hmvc", "0"),
+            clock("fmvn", "1")
+        );
+
+        self.set_fen(&fen).map_err(EpdError::InvalidPosition)?;
+
+        Ok(operations)
+    }
+}
+
+impl Position {
+    /// Writes the current position as an EPD record with the given operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, EpdOperation};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let id = EpdOperation {
+    ///     opcode: "id".to_string(),
+    ///     operands: vec!["start".to_string()],
+    /// };
+    /// assert_eq!(
+    ///     engine.to_epd(&[id]),
+    ///     r#"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id "start";"#
+    /// );
+    /// ```
+    pub fn to_epd(&self, operations: &[EpdOperation]) -> String {
+        let fen = self.fen();
+        let mut epd = fen.split(' ').take(4).collect::<Vec<_>>().join(" ");
+
+        for operation in operations {
+            epd += " ";
+            epd += &operation.opcode;
+
+            for operand in &operation.operands {
+                // Strings are always quoted, as is anything which wouldn't read back as one
+                // operand.
+                let is_string = operation.opcode == "id"
+                    || (operation.opcode.len() == 2
+                        && operation.opcode.starts_with('c')
+                        && operation.opcode.as_bytes()[1].is_ascii_digit());
+
+                if is_string || operand.is_empty() || operand.contains([' ', ';', '"']) {
+                    epd += &format!(" \"{}\"", operand.replace('"', "'"));
+                } else {
+                    epd += &format!(" {}", operand);
+                }
+            }
+
+            epd.push(';');
+        }
+
+        epd
+    }
+
+    // Returns the legal moves given by the operands of an operation, such as `bm`, which lists
+    // moves in SAN.
+    fn epd_moves(&self, operations: &[EpdOperation], opcode: &str) -> Vec<Move> {
+        operations
+            .iter()
+            .filter(|operation| operation.opcode == opcode)
+            .flat_map(|operation| &operation.operands)
+            .filter_map(|san| self.move_from_san(san))
+            .collect()
+    }
+}
+
+// Splits the operations of an EPD record, each of which ends with a semicolon.
+fn parse_operations(text: &str) -> Result<Vec<EpdOperation>, EpdError> {
+    let mut operations = vec![];
+    let mut chars = text.chars().peekable();
+
+    loop {
+        let mut tokens: Vec<String> = vec![];
+        let mut token = String::new();
+        let mut finished = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                ';' => {
+                    finished = true;
+                    break;
+                }
+                '"' => {
+                    let mut quoted = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => quoted.push(c),
+                            None => return Err(EpdError::InvalidOperation(text.to_string())),
+                        }
+                    }
+
+                    tokens.push(quoted);
+                }
+                c if c.is_whitespace() => {
+                    if !token.is_empty() {
+                        tokens.push(std::mem::take(&mut token));
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+
+        if !token.is_empty() {
+            tokens.push(token);
+        }
+
+        match (tokens.is_empty(), finished) {
+            (true, false) => break,
+            (true, true) => continue,
+            // The last operation may be missing its semicolon.
+            _ => {
+                let opcode = tokens.remove(0);
+                operations.push(EpdOperation {
+                    opcode,
+                    operands: tokens,
+                });
+
+                if !finished {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(operations)
+}
+
+/// A position from a test suite which the search got wrong.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SuiteFailure {
+    // The `id` of the position, or its line number if it has none.
+    pub id: String,
+    pub found: Option<Move>,
+}
+
+/// The results of running an EPD test suite.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SuiteResult {
+    pub solved: usize,
+    pub total: usize,
+    pub failures: Vec<SuiteFailure>,
+    // Records which were invalid, or gave no moves to check.
+    pub skipped: usize,
+}
+
+/// Searches each position of an EPD test suite, counting it as solved if the search chooses one
+/// of its `bm` moves, and none of its `am` moves.
+///
+/// # Arguments
+///
+/// * `suite` - The suite, with one EPD record per line.
+/// * `search` - The search to use, which is reset before each position.
+/// * `limits` - The limits for searching each position.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{run_epd_suite, Search, SearchLimits};
+///
+/// let suite = "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";";
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     ..Default::default()
+/// };
+///
+/// let result = run_epd_suite(suite, &mut Search::new(), &limits);
+/// assert_eq!((result.solved, result.total), (1, 1));
+/// ```
+pub fn run_epd_suite(suite: &str, search: &mut Search, limits: &SearchLimits) -> SuiteResult {
+    let mut result = SuiteResult::default();
+    let mut engine = Engine::default();
+
+    for (line_number, line) in suite.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let operations = match engine.set_position_from_epd(line) {
+            Ok(operations) => operations,
+            Err(error) => {
+                log(&format!("skipping line {}: {}", line_number + 1, error));
+                result.skipped += 1;
+                continue;
+            }
+        };
+
+        let best_moves = engine.epd_moves(&operations, "bm");
+        let avoid_moves = engine.epd_moves(&operations, "am");
+
+        if best_moves.is_empty() && avoid_moves.is_empty() {
+            log(&format!(
+                "skipping line {}: no legal bm or am moves",
+                line_number + 1
+            ));
+            result.skipped += 1;
+            continue;
+        }
+
+        search.new_game();
+        let found = search.best_move(&engine, limits).best_move;
+
+        let solved = found.is_some_and(|found| {
+            (best_moves.is_empty() || best_moves.contains(&found)) && !avoid_moves.contains(&found)
+        });
+
+        result.total += 1;
+
+        if solved {
+            result.solved += 1;
+        } else {
+            let id = operations
+                .iter()
+                .find(|operation| operation.opcode == "id")
+                .and_then(|operation| operation.operands.first().cloned())
+                .unwrap_or_else(|| format!("line {}", line_number + 1));
+
+            result.failures.push(SuiteFailure { id, found });
+        }
+    }
+
+    result
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use crate::{EpdError, FenError, MoveError, ParseSquareError, PgnError, SetupError};
+
+/// Any of the errors returned by the library, so that callers using several of its APIs can
+/// handle them with one type. Each of the more specific errors converts into it, so `?` works.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, Error, Move};
+///
+/// fn play(engine: &mut Engine, fen: &str, notation: &str) -> Result<(), Error> {
+///     let piece_move =
+///         Move::from_uci_str(notation).ok_or_else(|| Error::InvalidMove(notation.to_string()))?;
+///
+///     engine.set_fen(fen)?;
+///     engine.try_make_move(piece_move)?;
+///
+///     Ok(())
+/// }
+///
+/// let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+/// let mut engine = Engine::default();
+///
+/// assert!(play(&mut engine, fen, "e1e2").is_ok());
+/// assert!(matches!(play(&mut engine, "4k3/8 w", "e1e2"), Err(Error::InvalidFen(_))));
+/// assert!(matches!(play(&mut engine, fen, "e1e9"), Err(Error::InvalidMove(_))));
+/// assert!(matches!(play(&mut engine, fen, "e8e7"), Err(Error::IllegalMove(_))));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    InvalidFen(FenError),
+    InvalidEpd(EpdError),
+    InvalidPgn(PgnError),
+    InvalidSquare(ParseSquareError),
+    // A move which couldn't be read, such as malformed coordinate notation or SAN.
+    InvalidMove(String),
+    IllegalMove(MoveError),
+    InvalidSetup(SetupError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidFen(error) => write!(f, "invalid FEN: {}", error),
+            Error::InvalidEpd(error) => write!(f, "invalid EPD: {}", error),
+            Error::InvalidPgn(error) => write!(f, "invalid PGN: {}", error),
+            Error::InvalidSquare(error) => write!(f, "{}", error),
+            Error::InvalidMove(notation) => write!(f, "invalid move '{}'", notation),
+            Error::IllegalMove(error) => write!(f, "{}", error),
+            Error::InvalidSetup(error) => write!(f, "invalid position: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidFen(error) => Some(error),
+            Error::InvalidEpd(error) => Some(error),
+            Error::InvalidPgn(error) => Some(error),
+            Error::InvalidSquare(error) => Some(error),
+            Error::InvalidMove(_) => None,
+            Error::IllegalMove(error) => Some(error),
+            Error::InvalidSetup(error) => Some(error),
+        }
+    }
+}
+
+impl From<FenError> for Error {
+    fn from(error: FenError) -> Self {
+        Error::InvalidFen(error)
+    }
+}
+
+impl From<EpdError> for Error {
+    fn from(error: EpdError) -> Self {
+        Error::InvalidEpd(error)
+    }
+}
+
+impl From<PgnError> for Error {
+    fn from(error: PgnError) -> Self {
+        Error::InvalidPgn(error)
+    }
+}
+
+impl From<ParseSquareError> for Error {
+    fn from(error: ParseSquareError) -> Self {
+        Error::InvalidSquare(error)
+    }
+}
+
+impl From<MoveError> for Error {
+    fn from(error: MoveError) -> Self {
+        Error::IllegalMove(error)
+    }
+}
+
+impl From<SetupError> for Error {
+    fn from(error: SetupError) -> Self {
+        Error::InvalidSetup(error)
+    }
+}
@@ -0,0 +1,492 @@
+use core::fmt;
+
+use crate::{
+    evaluate_mobility, evaluate_passed_pawns, evaluate_piece_activity, PawnInfo, PawnTable,
+    PieceType, Position, Side, SCALE_NORMAL,
+};
+
+// Material values by piece type, in centipawns, in the middlegame and the endgame.
+pub const MIDDLEGAME_VALUES: [i32; 6] = [82, 337, 365, 477, 1025, 0];
+pub const ENDGAME_VALUES: [i32; 6] = [94, 281, 297, 512, 936, 0];
+
+// Piece-square tables, giving a bonus for each piece type depending on where it stands. The tables
+// are from White's point of view and laid out as the board is seen from White's side, so rank 8
+// comes first. White's entry for a square is at `square ^ 56`, and Black's at `square`.
+#[rustfmt::skip]
+const MIDDLEGAME_PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const ENDGAME_PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_KNIGHT_TABLE: [i32; 64] = [
+   -167, -89, -34, -49,  61, -97, -15,-107,
+    -73, -41,  72,  36,  23,  62,   7, -17,
+    -47,  60,  37,  65,  84, 129,  73,  44,
+     -9,  17,  19,  53,  37,  69,  18,  22,
+    -13,   4,  16,  13,  28,  19,  21,  -8,
+    -23,  -9,  12,  10,  19,  17,  25, -16,
+    -29, -53, -12,  -3,  -1,  18, -14, -19,
+   -105, -21, -58, -33, -17, -28, -19, -23,
+];
+
+#[rustfmt::skip]
+const ENDGAME_KNIGHT_TABLE: [i32; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_BISHOP_TABLE: [i32; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const ENDGAME_BISHOP_TABLE: [i32; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_ROOK_TABLE: [i32; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const ENDGAME_ROOK_TABLE: [i32; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_QUEEN_TABLE: [i32; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const ENDGAME_QUEEN_TABLE: [i32; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_KING_TABLE: [i32; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const ENDGAME_KING_TABLE: [i32; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+// The piece-square tables in the same order as `PieceType`.
+const MIDDLEGAME_TABLES: [[i32; 64]; 6] = [
+    MIDDLEGAME_PAWN_TABLE,
+    MIDDLEGAME_KNIGHT_TABLE,
+    MIDDLEGAME_BISHOP_TABLE,
+    MIDDLEGAME_ROOK_TABLE,
+    MIDDLEGAME_QUEEN_TABLE,
+    MIDDLEGAME_KING_TABLE,
+];
+
+const ENDGAME_TABLES: [[i32; 64]; 6] = [
+    ENDGAME_PAWN_TABLE,
+    ENDGAME_KNIGHT_TABLE,
+    ENDGAME_BISHOP_TABLE,
+    ENDGAME_ROOK_TABLE,
+    ENDGAME_QUEEN_TABLE,
+    ENDGAME_KING_TABLE,
+];
+
+// How much each piece type counts towards the game phase. Pawns and kings don't count, since they
+// stay on the board into the endgame.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+// The game phase with every piece on the board.
+pub const MAX_PHASE: i32 = 24;
+
+/// How much each term of the evaluation counts, as (middlegame, endgame) percentages, in the same
+/// order as `EvalTrace::terms`. These can be tuned with the `tuner` crate.
+pub const TERM_WEIGHTS: [(i32, i32); 7] = [(100, 100); 7];
+
+// Returns the index into a piece-square table for a piece of the given side.
+const fn table_index(square: usize, side: Side) -> usize {
+    match side {
+        Side::White => square ^ 56,
+        _ => square,
+    }
+}
+
+// The (middlegame, endgame) material and piece-square scores of a piece on the given square.
+pub(crate) fn piece_scores(
+    piece_type: PieceType,
+    side: Side,
+    square: usize,
+) -> ((i32, i32), (i32, i32)) {
+    let (piece, index) = (piece_type.val(), table_index(square, side));
+
+    (
+        (MIDDLEGAME_VALUES[piece], ENDGAME_VALUES[piece]),
+        (
+            MIDDLEGAME_TABLES[piece][index],
+            ENDGAME_TABLES[piece][index],
+        ),
+    )
+}
+
+/// The evaluation of a position broken down into its terms, to help diagnose evaluation problems.
+/// Each term is a (middlegame, endgame) pair for each side, indexed by side, in centipawns from
+/// that side's perspective.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvalTrace {
+    pub material: [(i32, i32); 2],
+    pub piece_squares: [(i32, i32); 2],
+    pub pawns: [(i32, i32); 2],
+    pub passed_pawns: [(i32, i32); 2],
+    pub mobility: [(i32, i32); 2],
+    pub activity: [(i32, i32); 2],
+    pub king_safety: [(i32, i32); 2],
+    // The game phase used to blend the middlegame and endgame scores.
+    pub phase: i32,
+    // The share of the endgame score kept, out of `SCALE_NORMAL`, lower for drawish material.
+    pub scale: i32,
+    // The final score, from the perspective of the side to move.
+    pub score: i32,
+}
+
+impl EvalTrace {
+    /// Returns each term of the evaluation by name, in the same order as `TERM_WEIGHTS`.
+    pub fn terms(&self) -> [(&'static str, [(i32, i32); 2]); 7] {
+        [
+            ("Material", self.material),
+            ("Piece-square", self.piece_squares),
+            ("Pawns", self.pawns),
+            ("Passed pawns", self.passed_pawns),
+            ("Mobility", self.mobility),
+            ("Activity", self.activity),
+            ("King safety", self.king_safety),
+        ]
+    }
+
+    // Returns the weighted difference between the sides for each term, from White's perspective.
+    fn weighted_terms(&self) -> [(i32, i32); 7] {
+        let terms = self.terms();
+
+        core::array::from_fn(|i| {
+            let (white, black) = (terms[i].1[Side::White.val()], terms[i].1[Side::Black.val()]);
+            let weight = TERM_WEIGHTS[i];
+
+            (
+                (white.0 - black.0) * weight.0,
+                (white.1 - black.1) * weight.1,
+            )
+        })
+    }
+
+    // Returns the weighted sum of all terms for both sides, from White's perspective.
+    fn total(&self) -> (i32, i32) {
+        let (middlegame, endgame) = self
+            .weighted_terms()
+            .iter()
+            .fold((0, 0), |total, term| (total.0 + term.0, total.1 + term.1));
+
+        (middlegame / 100, endgame / 100)
+    }
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} | {:>13} | {:>13} | {:>13}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+
+        // The totals for each term include its weight.
+        for ((name, term), weighted) in self.terms().iter().zip(self.weighted_terms()) {
+            let white = term[Side::White.val()];
+            let black = term[Side::Black.val()];
+
+            writeln!(
+                f,
+                "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+                name,
+                white.0,
+                white.1,
+                black.0,
+                black.1,
+                weighted.0 / 100,
+                weighted.1 / 100
+            )?;
+        }
+
+        let (middlegame, endgame) = self.total();
+        writeln!(
+            f,
+            "{:>12} | {:>13} | {:>13} | {:>6} {:>6}",
+            "Total", "", "", middlegame, endgame
+        )?;
+
+        write!(
+            f,
+            "Phase {}/{}, endgame scale {}/{}, final score {} for the side to move",
+            self.phase, MAX_PHASE, self.scale, SCALE_NORMAL, self.score
+        )
+    }
+}
+
+impl Position {
+    /// Evaluates the current position from material, piece placement, pawn structure, mobility,
+    /// piece activity and king safety, in centipawns from the perspective of the side to move.
+    /// Endgames the engine has specific knowledge of are scored by `evaluate_endgame` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move, MoveKind, Square};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// // The initial position is symmetrical.
+    /// assert_eq!(engine.evaluate(), 0);
+    ///
+    /// // After 1. e4 White controls the centre, which is bad for Black to move.
+    /// engine.make_move(Move {
+    ///     from: Square::new(12),
+    ///     to: Square::new(28),
+    ///     promote: None,
+    ///     captured: None,
+    ///     kind: MoveKind::DoublePush,
+    /// });
+    /// assert!(engine.evaluate() < 0);
+    /// ```
+    pub fn evaluate(&self) -> i32 {
+        self.evaluate_with(None).score
+    }
+
+    /// Evaluates the current position as `evaluate` does, but caching the pawn structure
+    /// evaluation in the given table. This is much faster when evaluating many similar positions.
+    pub fn evaluate_with_pawn_table(&self, pawn_table: &mut PawnTable) -> i32 {
+        self.evaluate_with(Some(pawn_table)).score
+    }
+
+    /// Evaluates the current position as `evaluate` does, returning the score of each term of the
+    /// evaluation for each side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Side};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let trace = engine.evaluate_verbose();
+    /// assert_eq!(trace.score, engine.evaluate());
+    /// assert_eq!(trace.material[Side::White.val()], trace.material[Side::Black.val()]);
+    /// ```
+    pub fn evaluate_verbose(&self) -> EvalTrace {
+        self.evaluate_with(None)
+    }
+
+    fn evaluate_with(&self, pawn_table: Option<&mut PawnTable>) -> EvalTrace {
+        let board = self.board();
+        let mut trace = EvalTrace {
+            material: self.material_scores(),
+            piece_squares: self.piece_square_scores(),
+            ..Default::default()
+        };
+
+        debug_assert_eq!(
+            trace,
+            self.count_piece_scores(),
+            "incremental piece scores out of date"
+        );
+
+        for side in [Side::White, Side::Black] {
+            trace.mobility[side.val()] = evaluate_mobility(board, side);
+            trace.activity[side.val()] = evaluate_piece_activity(board, side);
+        }
+
+        let white_pawns = board.bitboard_by_piece[PieceType::Pawn.val()]
+            & board.bitboard_by_side[Side::White.val()];
+        let black_pawns = board.bitboard_by_piece[PieceType::Pawn.val()]
+            & board.bitboard_by_side[Side::Black.val()];
+
+        let pawn_info = match pawn_table {
+            Some(pawn_table) => pawn_table.probe(white_pawns, black_pawns),
+            None => PawnInfo::new(white_pawns, black_pawns),
+        };
+        trace.pawns = pawn_info.score;
+
+        // Pawn shelter only counts in the middlegame, while there are pieces to attack the king.
+        for side in [Side::White, Side::Black] {
+            trace.passed_pawns[side.val()] =
+                evaluate_passed_pawns(board, side, pawn_info.passed[side.val()]);
+
+            let king =
+                board.bitboard_by_piece[PieceType::King.val()] & board.bitboard_by_side[side.val()];
+
+            if king != 0 {
+                let king_file = king.trailing_zeros() as usize % 8;
+                trace.king_safety[side.val()] = (pawn_info.king_shelter[side.val()][king_file], 0);
+            }
+        }
+
+        // Blend the two scores according to how far the game has progressed, so that the
+        // evaluation changes smoothly as pieces are traded.
+        // The endgame score is scaled down when the side ahead will struggle to win with the
+        // material left.
+        let (middlegame, endgame) = trace.total();
+        let strong_side = if endgame >= 0 {
+            Side::White
+        } else {
+            Side::Black
+        };
+        trace.scale = self.endgame_scale(strong_side);
+        let endgame = endgame * trace.scale / SCALE_NORMAL;
+
+        trace.phase = self.game_phase();
+        let score = (middlegame * trace.phase + endgame * (MAX_PHASE - trace.phase)) / MAX_PHASE;
+
+        trace.score = if self.side_to_move() == Side::White {
+            score
+        } else {
+            -score
+        };
+
+        // Endgames the engine knows how to play are scored by that knowledge instead, leaving the
+        // terms as they are.
+        if let Some(score) = self.evaluate_endgame() {
+            trace.score = score;
+        }
+
+        trace
+    }
+
+    // Adds up the material and piece-square scores of every piece from scratch, to check the
+    // scores kept as moves are made. The other terms are left at zero.
+    fn count_piece_scores(&self) -> EvalTrace {
+        let mut trace = EvalTrace::default();
+
+        for (square, piece_type, side) in self.pieces() {
+            let (material, piece_square) = piece_scores(piece_type, side, square.index());
+
+            trace.material[side.val()].0 += material.0;
+            trace.material[side.val()].1 += material.1;
+            trace.piece_squares[side.val()].0 += piece_square.0;
+            trace.piece_squares[side.val()].1 += piece_square.1;
+        }
+
+        trace
+    }
+
+    /// Returns how far the game is from the endgame, from `MAX_PHASE` with all pieces on the board
+    /// down to 0 with only kings and pawns left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, MAX_PHASE};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// assert_eq!(engine.game_phase(), MAX_PHASE);
+    /// ```
+    pub fn game_phase(&self) -> i32 {
+        let board = self.board();
+
+        let phase: i32 = PHASE_WEIGHTS
+            .iter()
+            .enumerate()
+            .map(|(piece, weight)| weight * board.bitboard_by_piece[piece].count_ones() as i32)
+            .sum();
+
+        // Promotions can take the phase above the starting value.
+        phase.min(MAX_PHASE)
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{PawnTable, Position};
+
+// The number of bits of the position hash used to index the cache.
+const EVAL_CACHE_BITS: u32 = 16;
+
+/// How often the evaluation cache has been probed and hit since the current search began.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct EvalCacheStats {
+    pub probes: u64,
+    // Probes which found the evaluation of the position, so it didn't need evaluating.
+    pub hits: u64,
+}
+
+impl EvalCacheStats {
+    /// The fraction of probes which found the evaluation of the position, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.probes.max(1) as f64
+    }
+}
+
+#[derive(Copy, Clone)]
+struct EvalEntry {
+    hash: u64,
+    score: i32,
+}
+
+/// A small hash table of evaluated positions, indexed by Zobrist hash, so that positions reached
+/// again through transpositions or re-searches aren't evaluated from scratch. Entries are always
+/// replaced, as an evaluation is as cheap to store as it is useful.
+pub struct EvalCache {
+    entries: Vec<Option<EvalEntry>>,
+
+    // Counts of probes since the start of the current search.
+    probes: u64,
+    hits: u64,
+}
+
+impl EvalCache {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; 1 << EVAL_CACHE_BITS],
+            probes: 0,
+            hits: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Resets the probe counts. Called at the start of each search.
+    pub fn new_search(&mut self) {
+        self.probes = 0;
+        self.hits = 0;
+    }
+
+    /// Returns the evaluation of the position from the perspective of the side to move, as
+    /// `Position::evaluate_with_pawn_table` gives it, evaluating it only if it isn't in the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{EvalCache, Engine, PawnTable};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+    ///
+    /// let mut eval_cache = EvalCache::new();
+    /// let mut pawn_table = PawnTable::new();
+    ///
+    /// assert_eq!(eval_cache.evaluate(&engine, &mut pawn_table), engine.evaluate());
+    /// assert_eq!(eval_cache.evaluate(&engine, &mut pawn_table), engine.evaluate());
+    ///
+    /// let stats = eval_cache.stats();
+    /// assert_eq!((stats.probes, stats.hits), (2, 1));
+    /// ```
+    pub fn evaluate(&mut self, position: &Position, pawn_table: &mut PawnTable) -> i32 {
+        let hash = position.hash();
+        let index = (hash >> (64 - EVAL_CACHE_BITS)) as usize;
+
+        self.probes += 1;
+
+        if let Some(entry) = self.entries[index] {
+            if entry.hash == hash {
+                self.hits += 1;
+                return entry.score;
+            }
+        }
+
+        let score = position.evaluate_with_pawn_table(pawn_table);
+        self.entries[index] = Some(EvalEntry { hash, score });
+
+        score
+    }
+
+    /// Returns how often the cache has been probed and hit since the current search began.
+    pub fn stats(&self) -> EvalCacheStats {
+        EvalCacheStats {
+            probes: self.probes,
+            hits: self.hits,
+        }
+    }
+}
+
+impl Default for EvalCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+
+use crate::random::next_random;
+use crate::{compare_move_generators, Move, MovegenMismatch, PieceType, Position, Side};
+
+/// An invariant of a position found broken by `check_invariants`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvariantError {
+    // The hash kept up to date as moves are made differs from the hash worked out from scratch.
+    HashMismatch { incremental: u64, computed: u64 },
+    // The FEN of the position doesn't read back as the same position, or as the same FEN.
+    FenRoundTrip(String),
+    // The bitboard move generator disagrees with the mailbox reference generator.
+    MoveGeneration(MovegenMismatch),
+    // Making the move gives a position whose hash differs from the hash worked out from scratch.
+    MoveHashMismatch(Move),
+    // Making the move leaves a side without exactly one king.
+    KingCount(Move),
+    // Making the move takes away one of the moving side's own pieces, other than a pawn which
+    // promotes.
+    OwnPieceLost(Move),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvariantError::HashMismatch {
+                incremental,
+                computed,
+            } => write!(
+                f,
+                "hash {:016x} differs from {:016x} computed from scratch",
+                incremental, computed
+            ),
+            InvariantError::FenRoundTrip(fen) => write!(f, "FEN {} doesn't round-trip", fen),
+            InvariantError::MoveGeneration(mismatch) => write!(
+                f,
+                "move generation missed {:?} and wrongly generated {:?}",
+                mismatch.missing, mismatch.extra
+            ),
+            InvariantError::MoveHashMismatch(piece_move) => {
+                write!(f, "hash out of date after {}", piece_move)
+            }
+            InvariantError::KingCount(piece_move) => {
+                write!(f, "a side doesn't have one king after {}", piece_move)
+            }
+            InvariantError::OwnPieceLost(piece_move) => {
+                write!(f, "{} takes away a piece of the side moving", piece_move)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+/// Generates random legal positions for fuzzing, by playing a random number of random legal moves
+/// from a starting position. Playouts stop early when the game ends. The same seed always gives
+/// the same positions.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{check_invariants, Position, RandomPositions};
+///
+/// let mut start = Position::default();
+/// start.set_initial_position();
+///
+/// for position in RandomPositions::new(start, 80, 1).take(200) {
+///     assert_eq!(check_invariants(&position), Ok(()), "{}", position.fen());
+/// }
+/// ```
+pub struct RandomPositions {
+    start: Position,
+    max_plies: u32,
+    random_state: u64,
+}
+
+impl RandomPositions {
+    /// Starts generating positions reached from `start` in at most `max_plies` moves.
+    pub fn new(start: Position, max_plies: u32, seed: u64) -> Self {
+        Self {
+            start,
+            max_plies,
+            // The generator never leaves a state of zero.
+            random_state: seed | 1,
+        }
+    }
+}
+
+impl Iterator for RandomPositions {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Position> {
+        let mut position = self.start.clone();
+        let plies = next_random(&mut self.random_state) % (self.max_plies as u64 + 1);
+
+        for _ in 0..plies {
+            let moves = position.legal_moves();
+
+            if moves.is_empty() {
+                break;
+            }
+
+            let index = (next_random(&mut self.random_state) % moves.len() as u64) as usize;
+            position.make_move(moves[index]);
+        }
+
+        Some(position)
+    }
+}
+
+/// Checks that the position is consistent: its hash matches one worked out from scratch, both
+/// before and after each legal move, its FEN reads back as the same position, and its legal moves
+/// match the mailbox reference generator's. After each legal move, each side must still have one
+/// king, and the moving side must still have all its pieces, but for a pawn which promotes. The
+/// first problem found is returned.
+///
+/// Positions are copied rather than unmade, so there is no unmake to check restores the position;
+/// checking each move's hash covers the state a move updates instead.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{check_invariants, Position};
+///
+/// let mut position = Position::default();
+/// position
+///     .set_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+///     .unwrap();
+///
+/// assert_eq!(check_invariants(&position), Ok(()));
+/// ```
+pub fn check_invariants(position: &Position) -> Result<(), InvariantError> {
+    let computed = position.compute_hash();
+
+    if position.hash() != computed {
+        return Err(InvariantError::HashMismatch {
+            incremental: position.hash(),
+            computed,
+        });
+    }
+
+    // Reading the FEN into a copy keeps whether castling is written as in Chess960, which the
+    // FEN doesn't record.
+    let fen = position.fen();
+    let mut parsed = position.clone();
+
+    if parsed.set_fen(&fen).is_err() || parsed != *position || parsed.fen() != fen {
+        return Err(InvariantError::FenRoundTrip(fen));
+    }
+
+    compare_move_generators(position).map_err(InvariantError::MoveGeneration)?;
+
+    for piece_move in position.legal_moves() {
+        let mut child = position.clone();
+        child.make_move(piece_move);
+
+        if child.hash() != child.compute_hash() {
+            return Err(InvariantError::MoveHashMismatch(piece_move));
+        }
+
+        let board = child.board();
+
+        if [Side::White, Side::Black]
+            .iter()
+            .any(|&side| board.count(PieceType::King, side) != 1)
+        {
+            return Err(InvariantError::KingCount(piece_move));
+        }
+
+        let side = position.side_to_move();
+        let piece_lost = [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ]
+        .iter()
+        .any(|&piece_type| {
+            let promoted = piece_type == PieceType::Pawn && piece_move.is_promotion();
+
+            board.count(piece_type, side) + u32::from(promoted)
+                < position.board().count(piece_type, side)
+        });
+
+        if piece_lost {
+            return Err(InvariantError::OwnPieceLost(piece_move));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,642 @@
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::{Engine, GameResult, Move, MoveError, Side};
+
+// The longest line allowed in PGN movetext.
+const PGN_LINE_LENGTH: usize = 80;
+
+/// The numeric annotation glyphs assessing a move, written after it as `!`, `?`, `!!`, `??`, `!?`
+/// and `?!`.
+pub const NAG_GOOD: u8 = 1;
+pub const NAG_MISTAKE: u8 = 2;
+pub const NAG_BRILLIANT: u8 = 3;
+pub const NAG_BLUNDER: u8 = 4;
+pub const NAG_INTERESTING: u8 = 5;
+pub const NAG_DUBIOUS: u8 = 6;
+
+/// A move in a game's record, with its SAN, when it was made, any comment and annotations on it,
+/// and the alternatives to it which have been explored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameMove {
+    pub piece_move: Move,
+    pub san: String,
+    // When the move was made, in milliseconds since the Unix epoch, or 0 if that isn't known, as
+    // for moves read from PGN.
+    pub timestamp: u64,
+    pub comment: Option<String>,
+    // Numeric annotation glyphs, such as 2 for a mistake (`?`) or 6 for a dubious move (`?!`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub nags: Vec<u8>,
+    // Lines played instead of this move, from the same position, each starting with its first
+    // move.
+    pub variations: Vec<Vec<GameMove>>,
+}
+
+/// A game played from some starting position, recording the moves made so that it can be saved
+/// or browsed. The current position can be moved back and forth along the main line with `undo`,
+/// `redo` and `go_to`, and making a different move from the middle of the game starts a new main
+/// line from there, keeping the moves it replaces as a variation.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Game, Move};
+///
+/// let play = |game: &mut Game, notation| game.make_move(Move::from_uci_str(notation).unwrap());
+///
+/// let mut game = Game::new();
+/// play(&mut game, "e2e4");
+/// play(&mut game, "e7e5");
+///
+/// assert!(game.undo());
+/// assert_eq!(game.ply(), 1);
+/// assert!(game.redo());
+/// assert!(!game.redo());
+///
+/// // Playing 1... c5 instead keeps 1... e5 as a variation.
+/// game.go_to(1);
+/// play(&mut game, "c7c5");
+/// game.set_comment("The Sicilian");
+///
+/// assert_eq!(game.main_line()[1].san, "c5");
+/// assert_eq!(game.main_line()[1].variations[0][0].san, "e5");
+/// assert!(game.to_pgn().ends_with("1. e4 c5 {The Sicilian} (1... e5) *\n"));
+/// ```
+#[derive(Clone)]
+pub struct Game {
+    start: Engine,
+    position: Engine,
+    // The main line, including any moves after the current position which `redo` replays.
+    line: Vec<GameMove>,
+    // The number of moves of the main line leading to the current position.
+    ply: usize,
+    // PGN tags, in the order they are written. The result is not stored, as it comes from the
+    // position, unless the game was adjudicated.
+    tags: Vec<(String, String)>,
+    adjudicated: Option<GameResult>,
+}
+
+impl Game {
+    /// Starts a game from the initial position.
+    pub fn new() -> Self {
+        let mut start = Engine::default();
+        start.set_initial_position();
+
+        Self::from_position(start)
+    }
+
+    /// Starts a game from the given position.
+    pub fn from_position(start: Engine) -> Self {
+        let tags = [
+            ("Event", "?".to_string()),
+            ("Site", "?".to_string()),
+            ("Date", today()),
+            ("Round", "?".to_string()),
+            ("White", "?".to_string()),
+            ("Black", "?".to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+
+        Self {
+            position: start.clone(),
+            start,
+            line: vec![],
+            ply: 0,
+            tags,
+            adjudicated: None,
+        }
+    }
+
+    // Replaces the moves and any adjudicated result of the game, moving to the end of the new
+    // main line. The moves must already have been checked to be legal.
+    pub(crate) fn set_record(&mut self, line: Vec<GameMove>, adjudicated: Option<GameResult>) {
+        self.line = line;
+        self.adjudicated = adjudicated;
+        self.position = self.start.clone();
+        self.ply = 0;
+        self.go_to(self.line.len());
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> &Engine {
+        &self.start
+    }
+
+    /// The current position.
+    pub fn position(&self) -> &Engine {
+        &self.position
+    }
+
+    /// The result of the game: the one it was adjudicated with, if any, and otherwise the one
+    /// given by the current position.
+    pub fn result(&self) -> GameResult {
+        self.adjudicated
+            .unwrap_or_else(|| self.position.game_result())
+    }
+
+    /// Ends the game with a result not given by the position, such as a loss on time, recording
+    /// the reason in the `Termination` tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Game, GameResult};
+    ///
+    /// let mut game = Game::new();
+    /// game.adjudicate(GameResult::BlackWins, "time forfeit");
+    ///
+    /// let pgn = game.to_pgn();
+    /// assert!(pgn.contains("[Result \"0-1\"]"));
+    /// assert!(pgn.contains("[Termination \"time forfeit\"]"));
+    /// ```
+    pub fn adjudicate(&mut self, result: GameResult, termination: &str) {
+        self.adjudicated = Some(result);
+        self.set_tag("Termination", termination);
+    }
+
+    /// The moves leading to the current position, in order.
+    pub fn moves(&self) -> Vec<Move> {
+        self.line[..self.ply]
+            .iter()
+            .map(|game_move| game_move.piece_move)
+            .collect()
+    }
+
+    /// The whole main line, including any moves after the current position.
+    pub fn main_line(&self) -> &[GameMove] {
+        &self.line
+    }
+
+    /// The number of moves leading to the current position.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Makes a move in the current position without checking that it is legal. If the main line
+    /// continues from here with the same move, this steps forward as `redo` does. Otherwise the
+    /// move starts a new main line, and any moves it replaces are kept as a variation of it.
+    pub fn make_move(&mut self, piece_move: Move) {
+        if self.line.get(self.ply).map(|next| next.piece_move) == Some(piece_move) {
+            self.redo();
+            return;
+        }
+
+        let game_move = GameMove {
+            piece_move,
+            san: self.position.move_to_san(piece_move),
+            timestamp: now_millis(),
+            comment: None,
+            nags: vec![],
+            variations: vec![],
+        };
+
+        self.position.make_move(piece_move);
+
+        let mut replaced = self.line.split_off(self.ply);
+        self.line.push(game_move);
+        self.ply += 1;
+
+        // Any moves made instead of the replaced one are alternatives to the new move too.
+        if let Some(first) = replaced.first_mut() {
+            let alternatives = std::mem::take(&mut first.variations);
+
+            match alternatives
+                .iter()
+                .position(|variation| variation[0].piece_move == piece_move)
+            {
+                // The move was already explored, so the variation becomes the main line.
+                Some(index) => {
+                    let mut alternatives = alternatives;
+                    let mut promoted = alternatives.remove(index);
+
+                    promoted[0].variations =
+                        std::iter::once(replaced).chain(alternatives).collect();
+                    self.line.pop();
+                    self.line.extend(promoted);
+                }
+                None => {
+                    self.line[self.ply - 1].variations =
+                        std::iter::once(replaced).chain(alternatives).collect();
+                }
+            }
+        }
+    }
+
+    /// Makes a move if it is legal in the current position, as [`Engine::try_make_move`] does,
+    /// recording it as `make_move` does.
+    pub fn try_make_move(&mut self, piece_move: Move) -> Result<(), MoveError> {
+        self.position.clone().try_make_move(piece_move)?;
+        self.make_move(piece_move);
+
+        Ok(())
+    }
+
+    /// Steps back one move along the main line, keeping the move to `redo`. Returns false at the
+    /// start of the game.
+    pub fn undo(&mut self) -> bool {
+        self.ply > 0 && self.go_to(self.ply - 1)
+    }
+
+    /// Steps forward one move along the main line. Returns false at the end of it.
+    pub fn redo(&mut self) -> bool {
+        self.go_to(self.ply + 1)
+    }
+
+    /// Moves to the position after the given number of moves of the main line, 0 being the start
+    /// of the game. Returns false, leaving the position as it was, if the main line is shorter.
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.line.len() {
+            return false;
+        }
+
+        // Positions are copied rather than unmade, so going back replays from the start.
+        if ply < self.ply {
+            self.position = self.start.clone();
+            self.ply = 0;
+        }
+
+        for game_move in &self.line[self.ply..ply] {
+            self.position.make_move(game_move.piece_move);
+        }
+
+        self.ply = ply;
+
+        true
+    }
+
+    /// Sets the comment on the move leading to the current position, replacing any earlier one.
+    /// Returns false at the start of the game, where there is no move to comment on.
+    pub fn set_comment(&mut self, comment: &str) -> bool {
+        match self.ply.checked_sub(1) {
+            Some(index) => {
+                self.line[index].comment = Some(comment.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a numeric annotation glyph to the move leading to the current position, unless it
+    /// already has it. Returns false at the start of the game, where there is no move to annotate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Game, Move, NAG_MISTAKE};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Move::from_uci_str("f2f3").unwrap());
+    ///
+    /// assert!(game.add_nag(NAG_MISTAKE));
+    /// assert!(game.to_pgn().ends_with("1. f3? *\n"));
+    /// ```
+    pub fn add_nag(&mut self, nag: u8) -> bool {
+        let Some(index) = self.ply.checked_sub(1) else {
+            return false;
+        };
+
+        let nags = &mut self.line[index].nags;
+
+        if !nags.contains(&nag) {
+            nags.push(nag);
+        }
+
+        true
+    }
+
+    /// Makes one of the variations of the next move the main line, keeping the main line it
+    /// replaces as a variation. The current position is unchanged. Returns false if there is no
+    /// such variation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Game, Move};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Move::from_uci_str("e2e4").unwrap());
+    /// game.undo();
+    /// game.make_move(Move::from_uci_str("d2d4").unwrap());
+    /// game.undo();
+    ///
+    /// assert!(game.promote_variation(0));
+    /// assert_eq!(game.main_line()[0].san, "e4");
+    /// assert_eq!(game.main_line()[0].variations[0][0].san, "d4");
+    /// ```
+    pub fn promote_variation(&mut self, index: usize) -> bool {
+        let Some(next) = self.line.get_mut(self.ply) else {
+            return false;
+        };
+
+        if index >= next.variations.len() {
+            return false;
+        }
+
+        let mut promoted = next.variations.remove(index);
+        let alternatives = std::mem::take(&mut next.variations);
+        let replaced = self.line.split_off(self.ply);
+
+        promoted[0].variations = std::iter::once(replaced).chain(alternatives).collect();
+        self.line.extend(promoted);
+
+        true
+    }
+
+    /// Sets a PGN tag, such as `Event` or `White`, replacing any previous value.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Writes the game in PGN, with the whole main line, comments and variations. The seven
+    /// standard tags come first, followed by `SetUp` and `FEN` if the game didn't start from the
+    /// initial position, then any other tags set. The result is the one at the end of the main
+    /// line, wherever the current position is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Game, Move};
+    ///
+    /// let mut game = Game::new();
+    /// game.set_tag("White", "Engine");
+    ///
+    /// for notation in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+    ///     game.make_move(Move::from_uci_str(notation).unwrap());
+    /// }
+    ///
+    /// let pgn = game.to_pgn();
+    /// assert!(pgn.contains("[White \"Engine\"]"));
+    /// assert!(pgn.contains("[Result \"0-1\"]"));
+    /// assert!(pgn.ends_with("1. f3 e5 2. g4 Qh4# 0-1\n"));
+    /// ```
+    pub fn to_pgn(&self) -> String {
+        let mut end = self.position.clone();
+
+        for game_move in &self.line[self.ply..] {
+            end.make_move(game_move.piece_move);
+        }
+
+        let result = pgn_result(self.adjudicated.unwrap_or_else(|| end.game_result()));
+
+        let mut pgn = String::new();
+        let (standard_tags, other_tags) = self.tags.split_at(6.min(self.tags.len()));
+
+        for (name, value) in standard_tags {
+            pgn += &format_tag(name, value);
+        }
+
+        pgn += &format_tag("Result", result);
+
+        let mut initial = Engine::default();
+        initial.set_initial_position();
+
+        if self.start.fen() != initial.fen() {
+            pgn += &format_tag("SetUp", "1");
+            pgn += &format_tag("FEN", &self.start.fen());
+        }
+
+        for (name, value) in other_tags {
+            pgn += &format_tag(name, value);
+        }
+
+        pgn.push('\n');
+
+        let mut tokens = line_tokens(&self.start, &self.line);
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > PGN_LINE_LENGTH {
+                pgn += &line;
+                pgn.push('\n');
+                line.clear();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            line += &token;
+        }
+
+        pgn + &line + "\n"
+    }
+}
+
+/// Games are written as their starting position, their moves with everything recorded about
+/// them, the current ply and their tags. The moves are replayed when a game is read back, so one
+/// containing an illegal move, in the main line or a variation, is rejected.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Game, GameResult, Move};
+///
+/// let mut game = Game::new();
+/// game.set_tag("White", "Player");
+///
+/// for notation in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+///     game.make_move(Move::from_uci_str(notation).unwrap());
+/// }
+///
+/// game.set_comment("Fool's mate");
+///
+/// let json = serde_json::to_string(&game).unwrap();
+/// let restored: Game = serde_json::from_str(&json).unwrap();
+///
+/// assert_eq!(restored.moves(), game.moves());
+/// assert_eq!(restored.main_line(), game.main_line());
+/// assert_eq!(restored.position().game_result(), GameResult::BlackWins);
+/// assert_eq!(restored.to_pgn(), game.to_pgn());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Game {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Game", 5)?;
+        state.serialize_field("start", self.start.position())?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("ply", &self.ply)?;
+        state.serialize_field("tags", &self.tags)?;
+        state.serialize_field("adjudicated", &self.adjudicated)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Game {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct GameRecord {
+            start: crate::Position,
+            line: Vec<GameMove>,
+            ply: usize,
+            tags: Vec<(String, String)>,
+            #[serde(default)]
+            adjudicated: Option<GameResult>,
+        }
+
+        // Checks every move of a line and its variations is legal where it is played.
+        fn check_line(position: &Engine, line: &[GameMove]) -> Result<(), MoveError> {
+            let mut position = position.clone();
+
+            for game_move in line {
+                for variation in &game_move.variations {
+                    check_line(&position, variation)?;
+                }
+
+                position.try_make_move(game_move.piece_move)?;
+            }
+
+            Ok(())
+        }
+
+        let record = GameRecord::deserialize(deserializer)?;
+
+        let mut start = Engine::default();
+        start.set_position(record.start);
+
+        check_line(&start, &record.line).map_err(serde::de::Error::custom)?;
+
+        let mut game = Self::from_position(start);
+        game.line = record.line;
+        game.tags = record.tags;
+        game.adjudicated = record.adjudicated;
+        game.go_to(record.ply.min(game.line.len()));
+
+        Ok(game)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The PGN movetext of a line played from the given position, numbering White's moves, and
+// Black's where a line or the moves after a comment or variation start with Black to move.
+fn line_tokens(position: &Engine, line: &[GameMove]) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut position = position.clone();
+    let mut needs_number = true;
+
+    for game_move in line {
+        if position.side_to_move() == Side::White {
+            tokens.push(format!("{}.", position.fullmove_number()));
+        } else if needs_number {
+            tokens.push(format!("{}...", position.fullmove_number()));
+        }
+
+        // The first move assessment, such as `?`, is written after the move as it usually is,
+        // and any other annotations as numbers.
+        let glyph = game_move
+            .nags
+            .iter()
+            .find_map(|&nag| nag_glyph(nag).map(|glyph| (nag, glyph)));
+
+        tokens.push(game_move.san.clone() + glyph.map_or("", |(_, glyph)| glyph));
+        tokens.extend(
+            game_move
+                .nags
+                .iter()
+                .filter(|&&nag| glyph.is_none_or(|(glyph_nag, _)| nag != glyph_nag))
+                .map(|nag| format!("${}", nag)),
+        );
+        needs_number = false;
+
+        if let Some(comment) = &game_move.comment {
+            // A comment ends at the first closing brace, so none may appear inside one.
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+            needs_number = true;
+        }
+
+        for variation in &game_move.variations {
+            let mut variation_tokens = line_tokens(&position, variation);
+
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+
+            tokens.extend(variation_tokens);
+            needs_number = true;
+        }
+
+        position.make_move(game_move.piece_move);
+    }
+
+    tokens
+}
+
+// The symbol written after a move for a move assessment annotation.
+pub(crate) fn nag_glyph(nag: u8) -> Option<&'static str> {
+    match nag {
+        NAG_GOOD => Some("!"),
+        NAG_MISTAKE => Some("?"),
+        NAG_BRILLIANT => Some("!!"),
+        NAG_BLUNDER => Some("??"),
+        NAG_INTERESTING => Some("!?"),
+        NAG_DUBIOUS => Some("?!"),
+        _ => None,
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+fn pgn_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::WhiteWins => "1-0",
+        GameResult::BlackWins => "0-1",
+        _ => "1/2-1/2",
+    }
+}
+
+// The current time in milliseconds since the Unix epoch, or 0 if the clock is before it.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64)
+}
+
+// Today's date in the PGN format, YYYY.MM.DD, in UTC.
+fn today() -> String {
+    let Ok(time) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+        return "????.??.??".to_string();
+    };
+
+    // Convert days since the epoch to a civil date, treating March as the first month of the
+    // year so that leap days come last.
+    let days = (time.as_secs() / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use crate::Move;
+
+/// The progress of a search, reported after each completed iteration.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    // The greatest ply reached, including the quiescence search.
+    pub seldepth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    // Nodes searched per second.
+    pub nps: u64,
+    // The time spent searching so far.
+    pub time: Duration,
+    // How full the transposition table is, in permille.
+    pub hashfull: u32,
+    pub pv: Vec<Move>,
+}
+
+/// Receives progress reports from a search, so they can be shown to the user however the caller
+/// likes. Implemented for closures taking a `&SearchInfo`.
+pub trait InfoCallback {
+    fn on_info(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo)> InfoCallback for F {
+    fn on_info(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
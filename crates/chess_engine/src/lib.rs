@@ -1,5 +1,168 @@
+// Without the `std` feature, only the board, move generation and evaluation are built, which
+// need an allocator but not the standard library.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod activity;
+pub use activity::*;
+
+#[cfg(feature = "std")]
+mod analysis;
+
+#[cfg(feature = "std")]
+mod bench;
+#[cfg(feature = "std")]
+pub use bench::*;
+
 mod board;
 pub use board::*;
 
+#[cfg(feature = "std")]
+mod book;
+#[cfg(feature = "std")]
+pub use book::*;
+
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
+pub use clock::*;
+
+#[cfg(feature = "std")]
+mod datagen;
+#[cfg(feature = "std")]
+pub use datagen::*;
+
+mod endgame;
+pub use endgame::*;
+
 mod engine;
 pub use engine::*;
+
+#[cfg(feature = "std")]
+mod epd;
+#[cfg(feature = "std")]
+pub use epd::*;
+
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+pub use error::*;
+
+mod eval;
+pub use eval::*;
+
+mod eval_cache;
+pub use eval_cache::*;
+
+#[cfg(feature = "std")]
+mod fuzz;
+#[cfg(feature = "std")]
+pub use fuzz::*;
+
+#[cfg(feature = "std")]
+mod game;
+#[cfg(feature = "std")]
+pub use game::*;
+
+#[cfg(feature = "std")]
+mod info;
+#[cfg(feature = "std")]
+pub use info::*;
+
+#[cfg(feature = "std")]
+mod log;
+#[cfg(feature = "std")]
+pub use log::{clear_log_callback, set_log_callback, LogCallback};
+
+mod magic;
+
+mod mailbox;
+pub use mailbox::*;
+
+mod movelist;
+pub use movelist::*;
+
+#[cfg(feature = "std")]
+mod options;
+#[cfg(feature = "std")]
+pub use options::*;
+
+#[cfg(feature = "std")]
+mod ordering;
+#[cfg(feature = "std")]
+pub use ordering::*;
+
+mod pawns;
+pub use pawns::*;
+
+#[cfg(feature = "std")]
+mod picker;
+#[cfg(feature = "std")]
+pub use picker::*;
+
+#[cfg(feature = "std")]
+mod pgn;
+#[cfg(feature = "std")]
+pub use pgn::*;
+
+mod position;
+pub use position::*;
+
+#[cfg(feature = "std")]
+mod random;
+
+mod render;
+pub use render::*;
+
+mod san;
+
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+pub use search::*;
+
+// Threads can't be spawned in the browser.
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+mod search_handle;
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub use search_handle::*;
+
+mod setup;
+pub use setup::*;
+
+mod square;
+pub use square::*;
+
+#[cfg(feature = "std")]
+mod time;
+
+#[cfg(feature = "std")]
+mod time_manager;
+#[cfg(feature = "std")]
+pub use time_manager::*;
+
+#[cfg(feature = "std")]
+mod trace;
+#[cfg(feature = "std")]
+pub use trace::*;
+
+#[cfg(feature = "std")]
+mod tt;
+#[cfg(feature = "std")]
+pub use tt::*;
+
+// The UCI loop searches on a thread of its own, which the browser doesn't provide.
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub mod uci;
+
+mod zobrist;
@@ -0,0 +1,55 @@
+use std::sync::Mutex;
+
+/// Receives diagnostic messages from the library, such as why a record of a test suite was
+/// skipped, so they can be shown or recorded however the caller likes. Implemented for closures
+/// taking a `&str`.
+pub trait LogCallback {
+    fn on_log(&mut self, message: &str);
+}
+
+impl<F: FnMut(&str)> LogCallback for F {
+    fn on_log(&mut self, message: &str) {
+        self(message)
+    }
+}
+
+static LOG_CALLBACK: Mutex<Option<Box<dyn LogCallback + Send>>> = Mutex::new(None);
+
+/// Sets the callback which is given the library's diagnostic messages, replacing any previous
+/// one. Until a callback is set, the messages are discarded.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::mpsc;
+///
+/// use chess_engine::{run_epd_suite, set_log_callback, Search, SearchLimits};
+///
+/// let (sender, receiver) = mpsc::channel();
+/// set_log_callback(move |message: &str| sender.send(message.to_string()).unwrap());
+///
+/// let result = run_epd_suite("not an epd record", &mut Search::new(), &SearchLimits::default());
+/// assert_eq!(result.skipped, 1);
+/// assert!(receiver.try_recv().unwrap().starts_with("skipping line 1"));
+/// ```
+pub fn set_log_callback(callback: impl LogCallback + Send + 'static) {
+    if let Ok(mut log_callback) = LOG_CALLBACK.lock() {
+        *log_callback = Some(Box::new(callback));
+    }
+}
+
+/// Removes the log callback, so that diagnostic messages are discarded again.
+pub fn clear_log_callback() {
+    if let Ok(mut log_callback) = LOG_CALLBACK.lock() {
+        *log_callback = None;
+    }
+}
+
+// Passes a diagnostic message on to the log callback, if there is one.
+pub(crate) fn log(message: &str) {
+    if let Ok(mut log_callback) = LOG_CALLBACK.lock() {
+        if let Some(callback) = log_callback.as_mut() {
+            callback.on_log(message);
+        }
+    }
+}
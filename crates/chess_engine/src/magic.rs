@@ -0,0 +1,38 @@
+use crate::Bitboard;
+
+// The lookup of a sliding piece's attacks from one square. The occupied squares which could block
+// it are picked out by `mask`, then multiplied by `magic` so that the top bits form an index into
+// the square's part of `SLIDING_ATTACKS`, starting at `offset`. Where the CPU supports BMI2, the
+// index is instead taken with PEXT, which packs the masked bits directly, from `PEXT_ATTACKS`.
+pub(crate) struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+// The tables themselves are generated by the build script.
+include!(concat!(env!("OUT_DIR"), "/magics.rs"));
+
+impl Magic {
+    #[inline]
+    pub(crate) fn attacks(&self, occupied: Bitboard) -> Bitboard {
+        #[cfg(all(feature = "std", target_arch = "x86_64"))]
+        if std::is_x86_feature_detected!("bmi2") {
+            // SAFETY: The CPU has just been checked to support BMI2.
+            return unsafe { self.pext_attacks(occupied) };
+        }
+
+        let index = ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+
+        SLIDING_ATTACKS[self.offset + index]
+    }
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    #[target_feature(enable = "bmi2")]
+    unsafe fn pext_attacks(&self, occupied: Bitboard) -> Bitboard {
+        let index = core::arch::x86_64::_pext_u64(occupied, self.mask) as usize;
+
+        PEXT_ATTACKS[self.offset + index]
+    }
+}
@@ -0,0 +1,416 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{
+    Move, MoveKind, PieceType, Position, Side, Square, CASTLE_BLACK_KINGSIDE,
+    CASTLE_BLACK_QUEENSIDE, CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE,
+};
+
+// The board as an array of squares, each holding the piece on it, if any.
+type Mailbox = [Option<(PieceType, Side)>; 64];
+
+// The steps pieces move by, as (file, rank) offsets.
+const KING_STEPS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const ROOK_RAYS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP_RAYS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+/// Where the bitboard and mailbox move generators disagree about a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MovegenMismatch {
+    // Moves the mailbox generator found which the bitboard generator didn't.
+    pub missing: Vec<Move>,
+    // Moves the bitboard generator found which the mailbox generator didn't.
+    pub extra: Vec<Move>,
+}
+
+/// Generates the legal moves of the side to move by walking a square-by-square copy of the board,
+/// without bitboards, magics or incremental state. It is far slower than `Position::legal_moves`
+/// and is meant only to check it, as a reference simple enough to be obviously correct.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{mailbox_legal_moves, Position};
+///
+/// let mut position = Position::default();
+/// position.set_initial_position();
+///
+/// assert_eq!(mailbox_legal_moves(&position).len(), 20);
+/// ```
+pub fn mailbox_legal_moves(position: &Position) -> Vec<Move> {
+    let board: Mailbox = core::array::from_fn(|index| position.piece_at(Square::new(index)));
+    let side = position.side_to_move();
+    let mut moves = vec![];
+
+    for from in 0..64 {
+        match board[from] {
+            Some((PieceType::Pawn, piece_side)) if piece_side == side => {
+                pawn_moves(&board, from, side, position.en_passant(), &mut moves)
+            }
+            Some((PieceType::Knight, piece_side)) if piece_side == side => {
+                step_moves(&board, from, side, &KNIGHT_JUMPS, &mut moves)
+            }
+            Some((PieceType::King, piece_side)) if piece_side == side => {
+                step_moves(&board, from, side, &KING_STEPS, &mut moves)
+            }
+            Some((PieceType::Bishop, piece_side)) if piece_side == side => {
+                slide_moves(&board, from, side, &BISHOP_RAYS, &mut moves)
+            }
+            Some((PieceType::Rook, piece_side)) if piece_side == side => {
+                slide_moves(&board, from, side, &ROOK_RAYS, &mut moves)
+            }
+            Some((PieceType::Queen, piece_side)) if piece_side == side => {
+                slide_moves(&board, from, side, &BISHOP_RAYS, &mut moves);
+                slide_moves(&board, from, side, &ROOK_RAYS, &mut moves);
+            }
+            _ => {}
+        }
+    }
+
+    moves.retain(|&piece_move| !king_attacked_after(&board, piece_move, side));
+    castling_moves(&board, position, &mut moves);
+
+    moves
+}
+
+/// Compares the legal moves from `Position::legal_moves` with those from `mailbox_legal_moves`,
+/// returning the moves only one of them found if they differ. Running this over many positions
+/// is a cheap way to find move generation bugs.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{compare_move_generators, Position};
+///
+/// // Random games from positions with castling, en passant, promotions and pins to get wrong.
+/// let fens = [
+///     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+///     "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+///     "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+///     "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+///     "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 0 1",
+/// ];
+///
+/// let mut random_state = 0x2545F4914F6CDD1Du64;
+/// let mut positions = 0;
+///
+/// for fen in fens {
+///     for _ in 0..10 {
+///         let mut position = Position::default();
+///         position.set_fen(fen).unwrap();
+///
+///         for _ in 0..60 {
+///             assert_eq!(compare_move_generators(&position), Ok(()), "{}", position.fen());
+///             positions += 1;
+///
+///             let moves = position.legal_moves();
+///             if moves.is_empty() {
+///                 break;
+///             }
+///
+///             random_state ^= random_state << 13;
+///             random_state ^= random_state >> 7;
+///             random_state ^= random_state << 17;
+///             position.make_move(moves[(random_state % moves.len() as u64) as usize]);
+///         }
+///     }
+/// }
+///
+/// assert!(positions > 1000);
+/// ```
+pub fn compare_move_generators(position: &Position) -> Result<(), MovegenMismatch> {
+    let moves = position.legal_moves();
+    let reference = mailbox_legal_moves(position);
+
+    let missing: Vec<Move> = reference
+        .iter()
+        .filter(|piece_move| !moves.contains(piece_move))
+        .copied()
+        .collect();
+    let extra: Vec<Move> = moves
+        .iter()
+        .filter(|piece_move| !reference.contains(piece_move))
+        .copied()
+        .collect();
+
+    if missing.is_empty() && extra.is_empty() && moves.len() == reference.len() {
+        Ok(())
+    } else {
+        Err(MovegenMismatch { missing, extra })
+    }
+}
+
+// The square reached by stepping from a square by a (file, rank) offset, if it is on the board.
+fn step(square: usize, (file_step, rank_step): (i32, i32)) -> Option<usize> {
+    let file = (square % 8) as i32 + file_step;
+    let rank = (square / 8) as i32 + rank_step;
+
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then(|| (rank * 8 + file) as usize)
+}
+
+fn normal_move(board: &Mailbox, from: usize, to: usize) -> Move {
+    Move {
+        from: Square::new(from),
+        to: Square::new(to),
+        promote: None,
+        captured: board[to].map(|(piece_type, _)| piece_type),
+        kind: MoveKind::Normal,
+    }
+}
+
+fn pawn_moves(
+    board: &Mailbox,
+    from: usize,
+    side: Side,
+    en_passant: Option<Square>,
+    moves: &mut Vec<Move>,
+) {
+    let (forward, start_rank, last_rank) = match side {
+        Side::White => (1, 1, 7),
+        _ => (-1, 6, 0),
+    };
+
+    // Adds the move, or one for each promotion if it reaches the last rank.
+    let mut add = |piece_move: Move| {
+        if piece_move.to.index() / 8 == last_rank {
+            for promote in PROMOTIONS {
+                moves.push(Move {
+                    promote: Some(promote),
+                    ..piece_move
+                });
+            }
+        } else {
+            moves.push(piece_move);
+        }
+    };
+
+    if let Some(to) = step(from, (0, forward)).filter(|&to| board[to].is_none()) {
+        add(normal_move(board, from, to));
+
+        if from / 8 == start_rank {
+            if let Some(to) = step(to, (0, forward)).filter(|&to| board[to].is_none()) {
+                add(Move {
+                    kind: MoveKind::DoublePush,
+                    ..normal_move(board, from, to)
+                });
+            }
+        }
+    }
+
+    for file_step in [-1, 1] {
+        let Some(to) = step(from, (file_step, forward)) else {
+            continue;
+        };
+
+        match board[to] {
+            Some((_, piece_side)) if piece_side != side => add(normal_move(board, from, to)),
+            None if en_passant == Some(Square::new(to)) => add(Move {
+                captured: Some(PieceType::Pawn),
+                kind: MoveKind::EnPassant,
+                ..normal_move(board, from, to)
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn step_moves(
+    board: &Mailbox,
+    from: usize,
+    side: Side,
+    steps: &[(i32, i32)],
+    moves: &mut Vec<Move>,
+) {
+    for &offset in steps {
+        if let Some(to) = step(from, offset) {
+            if board[to].is_none_or(|(_, piece_side)| piece_side != side) {
+                moves.push(normal_move(board, from, to));
+            }
+        }
+    }
+}
+
+fn slide_moves(
+    board: &Mailbox,
+    from: usize,
+    side: Side,
+    rays: &[(i32, i32)],
+    moves: &mut Vec<Move>,
+) {
+    for &offset in rays {
+        let mut square = from;
+
+        while let Some(to) = step(square, offset) {
+            match board[to] {
+                None => moves.push(normal_move(board, from, to)),
+                Some((_, piece_side)) => {
+                    if piece_side != side {
+                        moves.push(normal_move(board, from, to));
+                    }
+
+                    break;
+                }
+            }
+
+            square = to;
+        }
+    }
+}
+
+// Whether a piece of the given side attacks the square.
+fn is_attacked(board: &Mailbox, square: usize, by_side: Side) -> bool {
+    let is_piece = |offset, piece_types: &[PieceType]| {
+        step(square, offset).is_some_and(|from| {
+            board[from].is_some_and(|(piece_type, side)| {
+                side == by_side && piece_types.contains(&piece_type)
+            })
+        })
+    };
+
+    // Pawns attack diagonally forwards, so are found diagonally behind the square.
+    let behind = match by_side {
+        Side::White => -1,
+        _ => 1,
+    };
+
+    if [(-1, behind), (1, behind)]
+        .into_iter()
+        .any(|offset| is_piece(offset, &[PieceType::Pawn]))
+        || KNIGHT_JUMPS
+            .into_iter()
+            .any(|offset| is_piece(offset, &[PieceType::Knight]))
+        || KING_STEPS
+            .into_iter()
+            .any(|offset| is_piece(offset, &[PieceType::King]))
+    {
+        return true;
+    }
+
+    for (rays, piece_types) in [
+        (ROOK_RAYS, [PieceType::Rook, PieceType::Queen]),
+        (BISHOP_RAYS, [PieceType::Bishop, PieceType::Queen]),
+    ] {
+        for offset in rays {
+            let mut from = square;
+
+            while let Some(next) = step(from, offset) {
+                if let Some((piece_type, side)) = board[next] {
+                    if side == by_side && piece_types.contains(&piece_type) {
+                        return true;
+                    }
+
+                    break;
+                }
+
+                from = next;
+            }
+        }
+    }
+
+    false
+}
+
+// Whether the side's king is attacked once the move is played. Castling moves aren't handled, as
+// they are checked as they are generated.
+fn king_attacked_after(board: &Mailbox, piece_move: Move, side: Side) -> bool {
+    let mut board = *board;
+    let (from, to) = (piece_move.from.index(), piece_move.to.index());
+
+    // The pawn taken en passant is beside the capturing pawn, not on the square it moves to.
+    if piece_move.kind == MoveKind::EnPassant {
+        board[from / 8 * 8 + to % 8] = None;
+    }
+
+    board[to] = match piece_move.promote {
+        Some(promote) => Some((promote, side)),
+        None => board[from],
+    };
+    board[from] = None;
+
+    let king = (0..64).find(|&square| board[square] == Some((PieceType::King, side)));
+
+    king.is_some_and(|king| is_attacked(&board, king, side.flip()))
+}
+
+fn castling_moves(board: &Mailbox, position: &Position, moves: &mut Vec<Move>) {
+    let side = position.side_to_move();
+    let castling = position.castling();
+
+    let rights = match side {
+        Side::White => [
+            (CASTLE_WHITE_KINGSIDE, MoveKind::KingsideCastle, 6, 5),
+            (CASTLE_WHITE_QUEENSIDE, MoveKind::QueensideCastle, 2, 3),
+        ],
+        _ => [
+            (CASTLE_BLACK_KINGSIDE, MoveKind::KingsideCastle, 62, 61),
+            (CASTLE_BLACK_QUEENSIDE, MoveKind::QueensideCastle, 58, 59),
+        ],
+    };
+
+    let Some(king) = (0..64).find(|&square| board[square] == Some((PieceType::King, side))) else {
+        return;
+    };
+
+    for (right, kind, king_to, rook_to) in rights {
+        if castling.rights & right == 0 {
+            continue;
+        }
+
+        let rook = castling.rooks[right.trailing_zeros() as usize].index();
+
+        // The board with the king and rook lifted off it.
+        let mut lifted = *board;
+        lifted[king] = None;
+        lifted[rook] = None;
+
+        let between = |a: usize, b: usize| a.min(b)..=a.max(b);
+
+        // Every square the king and rook cross or land on must be empty, and none the king
+        // stands on, crosses or lands on may be attacked.
+        let blocked = between(king, king_to)
+            .chain(between(rook, rook_to))
+            .any(|square| lifted[square].is_some());
+        let attacked =
+            between(king, king_to).any(|square| is_attacked(&lifted, square, side.flip()));
+
+        if !blocked && !attacked {
+            moves.push(Move {
+                from: Square::new(king),
+                to: Square::new(if position.is_chess960() {
+                    rook
+                } else {
+                    king_to
+                }),
+                promote: None,
+                captured: None,
+                kind,
+            });
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Move, MoveKind, Square};
+
+/// The most moves a list can hold. No legal position has more than 218 moves.
+pub const MAX_MOVES: usize = 256;
+
+// Fills the unused part of a list.
+const EMPTY_MOVE: Move = Move {
+    from: Square::new(0),
+    to: Square::new(0),
+    promote: None,
+    captured: None,
+    kind: MoveKind::Normal,
+};
+
+/// A list of moves stored inline rather than on the heap, which move generation pushes into.
+/// Derefs to a slice of the moves it holds.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, MoveList, Side};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// let mut moves = MoveList::new();
+/// engine.generate_moves(Side::White, &mut moves);
+/// assert_eq!(moves.len(), 20);
+///
+/// moves.retain(|piece_move| piece_move.is_double_push());
+/// assert_eq!(moves.len(), 8);
+/// assert!(moves.iter().all(|piece_move| piece_move.from.rank().index() == 1));
+/// ```
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub const fn new() -> Self {
+        Self {
+            moves: [EMPTY_MOVE; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Adds a move to the end of the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list already holds [`MAX_MOVES`] moves.
+    pub fn push(&mut self, piece_move: Move) {
+        self.moves[self.len] = piece_move;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Shortens the list to the first `len` moves, if it is longer.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Keeps only the moves for which `keep` returns true, preserving their order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+
+        for i in 0..self.len {
+            if keep(&self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                kept += 1;
+            }
+        }
+
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = core::iter::Take<core::array::IntoIter<Move, MAX_MOVES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.len)
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
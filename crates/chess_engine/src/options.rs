@@ -0,0 +1,250 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::{DEFAULT_TT_SIZE_MB, MAX_SKILL_LEVEL};
+
+/// The type of an option, along with the values it may take.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OptionKind {
+    // A boolean.
+    Check { default: bool },
+    // An integer within an inclusive range.
+    Spin { default: i64, min: i64, max: i64 },
+    // Any text, such as a file path.
+    String { default: &'static str },
+}
+
+/// Describes an option which can be set, so that frontends can advertise it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct OptionDescription {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+// The opening book used unless another is given.
+const DEFAULT_BOOK_FILE: &str = "book.bin";
+
+/// Every option supported by `EngineOptions`, with their defaults.
+pub const ENGINE_OPTIONS: [OptionDescription; 13] = [
+    OptionDescription {
+        name: "Hash",
+        kind: OptionKind::Spin {
+            default: DEFAULT_TT_SIZE_MB as i64,
+            min: 1,
+            max: 65536,
+        },
+    },
+    OptionDescription {
+        name: "Threads",
+        kind: OptionKind::Spin {
+            default: 1,
+            min: 1,
+            max: 1,
+        },
+    },
+    OptionDescription {
+        name: "MultiPV",
+        kind: OptionKind::Spin {
+            default: 1,
+            min: 1,
+            max: 1,
+        },
+    },
+    OptionDescription {
+        name: "Ponder",
+        kind: OptionKind::Check { default: false },
+    },
+    OptionDescription {
+        name: "Move Overhead",
+        kind: OptionKind::Spin {
+            default: 10,
+            min: 0,
+            max: 5000,
+        },
+    },
+    OptionDescription {
+        name: "OwnBook",
+        kind: OptionKind::Check { default: false },
+    },
+    OptionDescription {
+        name: "BookFile",
+        kind: OptionKind::String {
+            default: DEFAULT_BOOK_FILE,
+        },
+    },
+    OptionDescription {
+        name: "Skill Level",
+        kind: OptionKind::Spin {
+            default: MAX_SKILL_LEVEL as i64,
+            min: 0,
+            max: MAX_SKILL_LEVEL as i64,
+        },
+    },
+    OptionDescription {
+        name: "Contempt",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: -100,
+            max: 100,
+        },
+    },
+    OptionDescription {
+        name: "UCI_Chess960",
+        kind: OptionKind::Check { default: false },
+    },
+    OptionDescription {
+        name: "Deterministic",
+        kind: OptionKind::Check { default: false },
+    },
+    OptionDescription {
+        name: "Random Plies",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: 0,
+            max: 100,
+        },
+    },
+    OptionDescription {
+        name: "Random Margin",
+        kind: OptionKind::Spin {
+            default: 20,
+            min: 0,
+            max: 200,
+        },
+    },
+];
+
+/// The reason an option could not be set.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum OptionError {
+    UnknownOption(String),
+    InvalidValue { name: String, value: String },
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "unknown option '{}'", name),
+            OptionError::InvalidValue { name, value } => {
+                write!(f, "invalid value '{}' for option '{}'", value, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+/// The user configurable settings of the engine.
+#[derive(Clone, Debug)]
+pub struct EngineOptions {
+    // The size of the transposition table, in megabytes.
+    pub hash_size_mb: usize,
+    // The number of search threads. Only a single thread is supported for now.
+    pub threads: usize,
+    // The number of principal variations to report. Only a single line is supported for now.
+    pub multi_pv: usize,
+    // Whether the GUI may ask the engine to ponder.
+    pub ponder: bool,
+    // Time kept in reserve on each move, to allow for delays between the engine and the GUI.
+    pub move_overhead: Duration,
+    // Whether to play moves from the opening book, rather than searching, while it has any.
+    pub own_book: bool,
+    // The path of the opening book, in the Polyglot format.
+    pub book_file: String,
+    // How well to play, where `MAX_SKILL_LEVEL` is full strength.
+    pub skill_level: u32,
+    // How much worse than equal the engine considers a draw, in centipawns. Negative values make
+    // it prefer draws.
+    pub contempt: i32,
+    // Whether castling moves are written as in Chess960, with the king capturing its own rook.
+    pub chess960: bool,
+    // Whether searches ignore the clock and forget earlier searches, so that they can be
+    // repeated exactly.
+    pub deterministic: bool,
+    // How many plies from the start of the game the engine picks at random between the moves
+    // scoring within `random_margin` centipawns of the best.
+    pub random_plies: u32,
+    pub random_margin: i32,
+}
+
+impl EngineOptions {
+    /// Sets an option from its name and value as given over UCI. Names are not case sensitive.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the option, as in `ENGINE_OPTIONS`.
+    /// * `value` - The new value of the option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{EngineOptions, OptionError};
+    ///
+    /// let mut options = EngineOptions::default();
+    /// options.set("hash", "64").unwrap();
+    /// assert_eq!(options.hash_size_mb, 64);
+    ///
+    /// assert!(matches!(options.set("Hash", "0"), Err(OptionError::InvalidValue { .. })));
+    /// assert!(matches!(options.set("Colour", "blue"), Err(OptionError::UnknownOption(_))));
+    /// ```
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        let description = ENGINE_OPTIONS
+            .iter()
+            .find(|description| description.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| OptionError::UnknownOption(name.to_string()))?;
+
+        // Check options must be `true` or `false`, which are stored as 1 or 0, and spin options
+        // must be within their range. String options are used as given.
+        let parsed = match description.kind {
+            OptionKind::Check { .. } => value.parse::<bool>().map(i64::from).ok(),
+            OptionKind::Spin { min, max, .. } => value
+                .parse::<i64>()
+                .ok()
+                .filter(|value| (min..=max).contains(value)),
+            OptionKind::String { .. } => Some(0),
+        }
+        .ok_or_else(|| OptionError::InvalidValue {
+            name: description.name.to_string(),
+            value: value.to_string(),
+        })?;
+
+        match description.name {
+            "Hash" => self.hash_size_mb = parsed as usize,
+            "Threads" => self.threads = parsed as usize,
+            "MultiPV" => self.multi_pv = parsed as usize,
+            "Ponder" => self.ponder = parsed != 0,
+            "Move Overhead" => self.move_overhead = Duration::from_millis(parsed as u64),
+            "OwnBook" => self.own_book = parsed != 0,
+            "BookFile" => self.book_file = value.to_string(),
+            "Skill Level" => self.skill_level = parsed as u32,
+            "Contempt" => self.contempt = parsed as i32,
+            "UCI_Chess960" => self.chess960 = parsed != 0,
+            "Deterministic" => self.deterministic = parsed != 0,
+            "Random Plies" => self.random_plies = parsed as u32,
+            "Random Margin" => self.random_margin = parsed as i32,
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            hash_size_mb: DEFAULT_TT_SIZE_MB,
+            threads: 1,
+            multi_pv: 1,
+            ponder: false,
+            move_overhead: Duration::from_millis(10),
+            own_book: false,
+            book_file: DEFAULT_BOOK_FILE.to_string(),
+            skill_level: MAX_SKILL_LEVEL,
+            contempt: 0,
+            chess960: false,
+            deterministic: false,
+            random_plies: 0,
+            random_margin: 20,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{Board, Move, PieceType, Side};
+
+// The maximum ply for which killer moves are stored.
+pub const MAX_PLY: usize = 128;
+
+// Ordering scores by category. Captures are ordered above killers, which are ordered above quiet
+// moves ranked by the history heuristic.
+const PV_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const FIRST_KILLER_SCORE: i32 = 90_000;
+const SECOND_KILLER_SCORE: i32 = 80_000;
+
+// History scores are capped below the killer scores.
+const HISTORY_MAX: i32 = 50_000;
+
+// Piece values used for MVV-LVA, in the same order as `PieceType`.
+pub(crate) const MVV_LVA_VALUES: [i32; 6] = [1, 3, 3, 5, 9, 20];
+
+/// Returns the Most Valuable Victim - Least Valuable Aggressor score for a capture, which orders
+/// captures of valuable pieces by cheap pieces first.
+///
+/// # Arguments
+///
+/// * `victim` - The type of the piece being captured.
+/// * `attacker` - The type of the piece making the capture.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{mvv_lva, PieceType};
+///
+/// // Pawn takes queen is better than queen takes pawn.
+/// assert!(mvv_lva(PieceType::Queen, PieceType::Pawn) > mvv_lva(PieceType::Pawn, PieceType::Queen));
+/// ```
+pub fn mvv_lva(victim: PieceType, attacker: PieceType) -> i32 {
+    MVV_LVA_VALUES[victim.val()] * 100 - MVV_LVA_VALUES[attacker.val()]
+}
+
+/// Move ordering state kept across the nodes of a search: killer moves per ply, and a history
+/// table of quiet moves which have caused beta cutoffs.
+pub struct MoveOrdering {
+    killers: [[Option<Move>; 2]; MAX_PLY],
+
+    // History scores indexed by side, from square, and to square.
+    history: [[[i32; 64]; 64]; 2],
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self {
+            killers: [[None; 2]; MAX_PLY],
+            history: [[[0; 64]; 64]; 2],
+        }
+    }
+
+    /// Clears all killer moves and history scores.
+    pub fn clear(&mut self) {
+        self.killers = [[None; 2]; MAX_PLY];
+        self.history = [[[0; 64]; 64]; 2];
+    }
+
+    /// Prepares for a new search, forgetting the killers and aging the history scores so that
+    /// recent information carries more weight.
+    pub fn new_search(&mut self) {
+        self.killers = [[None; 2]; MAX_PLY];
+
+        self.history
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .for_each(|score| *score /= 2);
+    }
+
+    /// Sorts the moves so that the most promising are searched first.
+    ///
+    /// # Arguments
+    ///
+    /// * `moves` - The moves to sort.
+    /// * `board` - The board the moves are to be made on.
+    /// * `side` - The side making the moves.
+    /// * `ply` - The distance from the root of the search.
+    /// * `pv_move` - The best move from a previous search of this position, if known.
+    pub fn order_moves(
+        &self,
+        moves: &mut [Move],
+        board: &Board,
+        side: Side,
+        ply: usize,
+        pv_move: Option<Move>,
+    ) {
+        moves.sort_by_cached_key(|piece_move| {
+            -self.score_move(piece_move, board, side, ply, pv_move)
+        });
+    }
+
+    fn score_move(
+        &self,
+        piece_move: &Move,
+        board: &Board,
+        side: Side,
+        ply: usize,
+        pv_move: Option<Move>,
+    ) -> i32 {
+        if pv_move == Some(*piece_move) {
+            return PV_MOVE_SCORE;
+        }
+
+        if let Some(victim) = piece_move.captured {
+            let attacker = board
+                .piece_type_at(piece_move.from.index())
+                .unwrap_or(PieceType::Pawn);
+
+            return CAPTURE_SCORE + mvv_lva(victim, attacker);
+        }
+
+        // Promotions are nearly always good, so try them with the captures.
+        if piece_move.promote == Some(PieceType::Queen) {
+            return CAPTURE_SCORE;
+        }
+
+        let killers = self.killers(ply);
+
+        if killers[0] == Some(*piece_move) {
+            return FIRST_KILLER_SCORE;
+        } else if killers[1] == Some(*piece_move) {
+            return SECOND_KILLER_SCORE;
+        }
+
+        self.history_score(piece_move, side)
+    }
+
+    /// Returns the killer moves for a ply, the most recent first.
+    pub fn killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers.get(ply).copied().unwrap_or([None; 2])
+    }
+
+    /// Returns the history score of a quiet move: higher for moves which have caused more beta
+    /// cutoffs, especially at high depths.
+    pub fn history_score(&self, piece_move: &Move, side: Side) -> i32 {
+        self.history[side.val()][piece_move.from.index()][piece_move.to.index()]
+    }
+
+    /// Records a quiet move which caused a beta cutoff, as both a killer move for its ply and in
+    /// the history table.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_move` - The move which caused the cutoff.
+    /// * `side` - The side which made the move.
+    /// * `ply` - The distance from the root of the search.
+    /// * `depth` - The remaining depth at which the cutoff occurred.
+    pub fn record_cutoff(&mut self, piece_move: Move, side: Side, ply: usize, depth: u32) {
+        if piece_move.is_capture() {
+            return;
+        }
+
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0] != Some(piece_move) {
+                killers[1] = killers[0];
+                killers[0] = Some(piece_move);
+            }
+        }
+
+        let score = &mut self.history[side.val()][piece_move.from.index()][piece_move.to.index()];
+        *score = (*score + (depth * depth) as i32).min(HISTORY_MAX);
+    }
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,453 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{
+    pawn_east_attacks, pawn_west_attacks, rook_attacks, Bitboard, BitboardOps, Board, PieceType,
+    Side, Square, FILES,
+};
+
+// Pawn structure terms as (middlegame, endgame) pairs, in centipawns.
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-15, -10);
+const BACKWARD_PAWN: (i32, i32) = (-10, -8);
+
+// Bonuses for passed pawns by the number of ranks they have advanced from their own back rank.
+const PASSED_PAWN: [(i32, i32); 8] = [
+    (0, 0),
+    (5, 10),
+    (10, 15),
+    (15, 25),
+    (25, 45),
+    (40, 75),
+    (60, 120),
+    (0, 0),
+];
+
+// Bonuses for passed pawns with no piece of either side in front of them, by the number of ranks
+// they have advanced.
+const PASSED_PAWN_FREE_PATH: [(i32, i32); 8] = [
+    (0, 0),
+    (0, 5),
+    (0, 5),
+    (5, 10),
+    (10, 20),
+    (15, 35),
+    (20, 50),
+    (0, 0),
+];
+
+// Penalty for a passed pawn with an enemy piece on the square in front of it.
+const PASSED_PAWN_BLOCKADE: (i32, i32) = (-5, -15);
+
+// Endgame bonus for each square by which the enemy king is further than the side's own king from
+// the square in front of a passed pawn, per rank the pawn has advanced.
+const PASSED_PAWN_KING_PROXIMITY: i32 = 2;
+
+// Bonus for a rook behind a passed pawn of its own side on the same file, supporting its advance.
+const ROOK_BEHIND_PASSED_PAWN: (i32, i32) = (10, 25);
+
+// Middlegame king shelter by the rank of the side's own rearmost pawn on each file around its
+// king, counted from its back rank, where 0 means the file has no pawn of its own.
+const PAWN_SHELTER: [i32; 8] = [-25, 25, 15, 5, 0, 0, 0, 0];
+
+// Middlegame penalties for enemy pawns storming the king, by the rank of the enemy pawn nearest
+// the side's back rank on each file around its king, counted from that back rank, where 0 means
+// the file has no enemy pawn.
+const PAWN_STORM: [i32; 8] = [0, -10, -30, -15, -5, 0, 0, 0];
+
+// The pawn table has 2^PAWN_TABLE_BITS entries.
+const PAWN_TABLE_BITS: u32 = 14;
+
+/// Evaluates the pawn structure of one side: doubled, isolated and backward pawns. Passed pawns
+/// depend on the pieces as well, so are left to `evaluate_passed_pawns`. Returns the middlegame
+/// and endgame scores, in centipawns from that side's perspective.
+///
+/// # Arguments
+///
+/// * `pawns` - The bitboard of the side's pawns.
+/// * `enemy_pawns` - The bitboard of the other side's pawns.
+/// * `side` - The side to evaluate.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{evaluate_pawns, Side, RANK_2, RANK_7};
+///
+/// // The initial pawn structure has no weaknesses.
+/// assert_eq!(evaluate_pawns(RANK_2, RANK_7, Side::White), (0, 0));
+///
+/// // A lone white pawn on e5 is isolated.
+/// let (_, endgame) = evaluate_pawns(1 << 36, 0, Side::White);
+/// assert!(endgame < 0);
+/// ```
+pub fn evaluate_pawns(pawns: Bitboard, enemy_pawns: Bitboard, side: Side) -> (i32, i32) {
+    let enemy_attacks = pawn_east_attacks(enemy_pawns, !0, side.flip())
+        | pawn_west_attacks(enemy_pawns, !0, side.flip());
+
+    let mut middlegame = 0;
+    let mut endgame = 0;
+
+    let mut add = |(term_middlegame, term_endgame): (i32, i32)| {
+        middlegame += term_middlegame;
+        endgame += term_endgame;
+    };
+
+    for file in FILES {
+        let count = (pawns & file).count_ones() as i32;
+
+        if count > 1 {
+            add((DOUBLED_PAWN.0 * (count - 1), DOUBLED_PAWN.1 * (count - 1)));
+        }
+    }
+
+    for pawn in pawns.iter() {
+        let square = pawn.trailing_zeros() as usize;
+        let (file, rank) = (square % 8, square / 8);
+
+        let adjacent_files = adjacent_files(file);
+        let ahead = ranks_ahead(rank, side);
+
+        let stop_square = match side {
+            Side::White => pawn << 8,
+            _ => pawn >> 8,
+        };
+
+        if pawns & adjacent_files == 0 {
+            add(ISOLATED_PAWN);
+        } else if pawns & adjacent_files & !ahead == 0 && stop_square & enemy_attacks != 0 {
+            // No pawn can come alongside to support it, and it can't advance safely either.
+            add(BACKWARD_PAWN);
+        }
+    }
+
+    (middlegame, endgame)
+}
+
+/// Evaluates the passed pawns of one side: a bonus growing as each advances, more when nothing
+/// stands in its way and less when an enemy piece blockades it, a bonus for a rook behind it, and
+/// in the endgame, for the side's king being nearer to it than the enemy king. Returns the
+/// middlegame and endgame scores, in centipawns from that side's perspective.
+///
+/// # Arguments
+///
+/// * `board` - The board the pawns are on.
+/// * `side` - The side to evaluate.
+/// * `passed` - The side's passed pawns, as found by `passed_pawns`.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{evaluate_passed_pawns, passed_pawns, Engine, Side};
+///
+/// let mut engine = Engine::default();
+/// let mut score = |fen: &str| {
+///     engine.set_fen(fen).unwrap();
+///
+///     let (white_pawns, black_pawns) = (1 << 36, 0);
+///     let passed = passed_pawns(white_pawns, black_pawns, Side::White);
+///     evaluate_passed_pawns(engine.board(), Side::White, passed)
+/// };
+///
+/// // The e5 pawn is worth less when blockaded by the bishop.
+/// let free = score("k7/8/8/4P3/8/8/8/K7 w - - 0 1");
+/// let blockaded = score("k7/8/4b3/4P3/8/8/8/K7 w - - 0 1");
+/// assert!(blockaded.1 < free.1);
+///
+/// // And more with a rook behind it, and the white king close by.
+/// let supported = score("k7/8/8/4P3/3K4/8/8/4R3 w - - 0 1");
+/// assert!(supported.1 > free.1);
+/// ```
+pub fn evaluate_passed_pawns(board: &Board, side: Side, passed: Bitboard) -> (i32, i32) {
+    let our_pieces = board.bitboard_by_side[side.val()];
+    let enemy_pieces = board.bitboard_by_side[side.flip().val()];
+    let kings = board.bitboard_by_piece[PieceType::King.val()];
+    let rooks = board.bitboard_by_piece[PieceType::Rook.val()] & our_pieces;
+
+    let mut middlegame = 0;
+    let mut endgame = 0;
+
+    let mut add = |(term_middlegame, term_endgame): (i32, i32)| {
+        middlegame += term_middlegame;
+        endgame += term_endgame;
+    };
+
+    for pawn in passed.iter() {
+        let square = pawn.trailing_zeros() as usize;
+        let advanced = match side {
+            Side::White => square / 8,
+            _ => 7 - square / 8,
+        };
+
+        // A pawn is never on its last rank, so there is always a square in front of it.
+        let stop_square = match side {
+            Side::White => square + 8,
+            _ => square - 8,
+        };
+
+        add(PASSED_PAWN[advanced]);
+
+        if front_span(square, side) & board.occupied() == 0 {
+            add(PASSED_PAWN_FREE_PATH[advanced]);
+        } else if enemy_pieces & (1 << stop_square) != 0 {
+            add(PASSED_PAWN_BLOCKADE);
+        }
+
+        if let (Some(our_king), Some(enemy_king)) = (
+            Square::from_bitboard(kings & our_pieces),
+            Square::from_bitboard(kings & enemy_pieces),
+        ) {
+            let proximity =
+                distance(enemy_king.index(), stop_square) - distance(our_king.index(), stop_square);
+            add((0, PASSED_PAWN_KING_PROXIMITY * advanced as i32 * proximity));
+        }
+
+        // The rook must see the pawn along the file, from behind it.
+        let rooks_behind = rooks & front_span(square, side.flip());
+
+        if rooks_behind
+            .iter()
+            .any(|rook| rook_attacks(rook.trailing_zeros() as usize, board.occupied()) & pawn != 0)
+        {
+            add(ROOK_BEHIND_PASSED_PAWN);
+        }
+    }
+
+    (middlegame, endgame)
+}
+
+/// Returns the squares in front of the given square on its file, from the given side's point of
+/// view: the path a pawn of that side on the square takes to promotion.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{front_span, Side, FILE_E, RANK_1, RANK_2, RANK_8};
+///
+/// // e2 is square 12.
+/// assert_eq!(front_span(12, Side::White), FILE_E & !RANK_1 & !RANK_2);
+/// assert_eq!(front_span(12, Side::Black), FILE_E & RANK_1);
+/// assert_eq!(front_span(60, Side::White) & RANK_8, 0);
+/// ```
+pub fn front_span(square: usize, side: Side) -> Bitboard {
+    FILES[square % 8] & ranks_ahead(square / 8, side)
+}
+
+/// Returns the squares in front of the given square on its own file and the files either side,
+/// from the given side's point of view. A pawn of that side on the square is passed if no enemy
+/// pawns are on these squares.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{passed_pawn_mask, Side, FILE_D, FILE_E, FILE_F, RANK_7, RANK_8};
+///
+/// // e7 is square 52.
+/// assert_eq!(passed_pawn_mask(52, Side::White), (FILE_D | FILE_E | FILE_F) & RANK_8);
+/// assert_eq!(passed_pawn_mask(52, Side::Black) & (RANK_7 | RANK_8), 0);
+/// ```
+pub fn passed_pawn_mask(square: usize, side: Side) -> Bitboard {
+    let file = square % 8;
+
+    (FILES[file] | adjacent_files(file)) & ranks_ahead(square / 8, side)
+}
+
+// Returns the number of king moves between two squares.
+pub(crate) fn distance(from: usize, to: usize) -> i32 {
+    let file_distance = (from % 8).abs_diff(to % 8);
+    let rank_distance = (from / 8).abs_diff(to / 8);
+
+    file_distance.max(rank_distance) as i32
+}
+
+/// Returns the pawns of a side which have no enemy pawns in front of them on their own or an
+/// adjacent file, and so can't be stopped by pawns alone.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{passed_pawns, Side};
+///
+/// // White pawns on a4 and e4, and a black pawn on d6 which can stop the e-pawn.
+/// let (white, black) = (1 << 24 | 1 << 28, 1 << 43);
+/// assert_eq!(passed_pawns(white, black, Side::White), 1 << 24);
+/// assert_eq!(passed_pawns(black, white, Side::Black), 0);
+/// ```
+pub fn passed_pawns(pawns: Bitboard, enemy_pawns: Bitboard, side: Side) -> Bitboard {
+    pawns
+        .iter()
+        .filter(|pawn| enemy_pawns & passed_pawn_mask(pawn.trailing_zeros() as usize, side) == 0)
+        .fold(0, |passed, pawn| passed | pawn)
+}
+
+/// Evaluates the pawn shelter in front of a side's king on the given file, and the enemy pawns
+/// storming it, over that file and the files either side. Ranks are counted from the side's back
+/// rank, where its king usually stands while shelter matters. Returns a middlegame score, in
+/// centipawns from that side's perspective.
+///
+/// # Arguments
+///
+/// * `pawns` - The bitboard of the side's pawns.
+/// * `enemy_pawns` - The bitboard of the other side's pawns.
+/// * `side` - The side whose king is sheltered.
+/// * `king_file` - The file of the king, from 0 for the a-file to 7 for the h-file.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{king_shelter, Side, RANK_2};
+///
+/// // Advancing the pawns in front of a castled king weakens its shelter.
+/// let unmoved = king_shelter(RANK_2, 0, Side::White, 6);
+/// let pushed = king_shelter(RANK_2 & !(1 << 14) | 1 << 30, 0, Side::White, 6);
+/// assert!(pushed < unmoved);
+///
+/// // An enemy pawn on g3 storms it.
+/// assert!(king_shelter(RANK_2, 1 << 22, Side::White, 6) < unmoved);
+/// ```
+pub fn king_shelter(pawns: Bitboard, enemy_pawns: Bitboard, side: Side, king_file: usize) -> i32 {
+    // Shelter is counted over three files, even with the king on the edge of the board.
+    let centre = king_file.clamp(1, 6);
+
+    // The rank of the pawn on a file nearest the side's back rank, counted from it, or 0 if there
+    // is none.
+    let nearest_rank = |pawns: Bitboard| match (pawns, side) {
+        (0, _) => 0,
+        (pawns, Side::White) => pawns.trailing_zeros() as usize / 8,
+        (pawns, _) => 7 - (63 - pawns.leading_zeros() as usize) / 8,
+    };
+
+    (centre - 1..=centre + 1)
+        .map(|file| {
+            PAWN_SHELTER[nearest_rank(pawns & FILES[file])]
+                + PAWN_STORM[nearest_rank(enemy_pawns & FILES[file])]
+        })
+        .sum()
+}
+
+/// The pawn structure terms which depend only on where the pawns are, as cached by a
+/// [`PawnTable`]. Each array is indexed by side.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PawnInfo {
+    // The scores from `evaluate_pawns`.
+    pub score: [(i32, i32); 2],
+    // The passed pawns, from `passed_pawns`.
+    pub passed: [Bitboard; 2],
+    // The scores from `king_shelter`, by the file of the king.
+    pub king_shelter: [[i32; 8]; 2],
+}
+
+impl PawnInfo {
+    /// Works out the pawn structure terms of both sides.
+    pub fn new(white_pawns: Bitboard, black_pawns: Bitboard) -> Self {
+        let sides = [
+            (Side::White, white_pawns, black_pawns),
+            (Side::Black, black_pawns, white_pawns),
+        ];
+
+        Self {
+            score: sides.map(|(side, pawns, enemy_pawns)| evaluate_pawns(pawns, enemy_pawns, side)),
+            passed: sides.map(|(side, pawns, enemy_pawns)| passed_pawns(pawns, enemy_pawns, side)),
+            king_shelter: sides.map(|(side, pawns, enemy_pawns)| {
+                core::array::from_fn(|file| king_shelter(pawns, enemy_pawns, side, file))
+            }),
+        }
+    }
+}
+
+// Returns the files either side of the given file.
+pub(crate) fn adjacent_files(file: usize) -> Bitboard {
+    let west = if file > 0 { FILES[file - 1] } else { 0 };
+    let east = if file < 7 { FILES[file + 1] } else { 0 };
+
+    west | east
+}
+
+// Returns the squares on the ranks in front of the given rank, from the given side's point of view.
+pub(crate) fn ranks_ahead(rank: usize, side: Side) -> Bitboard {
+    match side {
+        Side::White => u64::MAX.checked_shl(8 * (rank as u32 + 1)).unwrap_or(0),
+        _ => (1 << (8 * rank)) - 1,
+    }
+}
+
+#[derive(Copy, Clone)]
+struct PawnEntry {
+    white_pawns: Bitboard,
+    black_pawns: Bitboard,
+    info: PawnInfo,
+}
+
+/// A cache of pawn structure evaluations. Pawns move rarely, so many positions in a search share
+/// the same pawn structure. Entries store the pawns themselves, so there are no false hits.
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; 1 << PAWN_TABLE_BITS],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Returns the scores from `evaluate_pawns` for both sides, indexed by side, using the stored
+    /// result if the pawn structure has been evaluated before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{evaluate_pawns, PawnTable, Side, RANK_2};
+    ///
+    /// let mut pawn_table = PawnTable::new();
+    /// let white_score = evaluate_pawns(RANK_2, 1 << 52, Side::White);
+    ///
+    /// assert_eq!(pawn_table.evaluate(RANK_2, 1 << 52)[Side::White.val()], white_score);
+    /// assert_eq!(pawn_table.evaluate(RANK_2, 1 << 52)[Side::White.val()], white_score);
+    /// ```
+    pub fn evaluate(&mut self, white_pawns: Bitboard, black_pawns: Bitboard) -> [(i32, i32); 2] {
+        self.probe(white_pawns, black_pawns).score
+    }
+
+    /// Returns all of the pawn structure terms for the given pawns, working them out only if the
+    /// pawn structure hasn't been seen before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{PawnInfo, PawnTable, RANK_2, RANK_7};
+    ///
+    /// let mut pawn_table = PawnTable::new();
+    /// assert_eq!(pawn_table.probe(RANK_2, RANK_7), PawnInfo::new(RANK_2, RANK_7));
+    /// ```
+    pub fn probe(&mut self, white_pawns: Bitboard, black_pawns: Bitboard) -> PawnInfo {
+        let index = ((white_pawns.wrapping_mul(0x9E3779B97F4A7C15)
+            ^ black_pawns.wrapping_mul(0xC2B2AE3D27D4EB4F))
+            >> (64 - PAWN_TABLE_BITS)) as usize;
+
+        if let Some(entry) = self.entries[index] {
+            if entry.white_pawns == white_pawns && entry.black_pawns == black_pawns {
+                return entry.info;
+            }
+        }
+
+        let info = PawnInfo::new(white_pawns, black_pawns);
+
+        self.entries[index] = Some(PawnEntry {
+            white_pawns,
+            black_pawns,
+            info,
+        });
+
+        info
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,407 @@
+use std::fmt;
+use std::vec::IntoIter;
+
+use crate::game::nag_glyph;
+use crate::{Engine, FenError, Game, GameMove, GameResult};
+
+/// The reason PGN could not be read.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnError {
+    // A tag pair which isn't written as `[Name "value"]`.
+    InvalidTag(String),
+    InvalidFen(FenError),
+    // A move which isn't legal where it is played, or can't be read as SAN.
+    InvalidMove(String),
+    // A variation without a move before it to be an alternative to, or a bracket left unclosed.
+    InvalidVariation,
+    UnterminatedComment,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag '{}'", tag),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::InvalidMove(san) => write!(f, "invalid move '{}'", san),
+            PgnError::InvalidVariation => write!(f, "misplaced or unclosed variation"),
+            PgnError::UnterminatedComment => write!(f, "unterminated comment"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PgnError::InvalidFen(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    StartVariation,
+    EndVariation,
+    Nag(u8),
+    // A move in SAN, with any annotation symbols after it.
+    Move(String),
+    Result(GameResult),
+}
+
+/// Reads every game in a PGN file, with their tags, comments, annotations and variations. Games
+/// start from the position in the `FEN` tag if there is one, and castling is written as in
+/// Chess960 if the `Variant` tag says it is. A game whose result isn't given by its final
+/// position, such as one lost on time or drawn by agreement, keeps the result it was given.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{parse_pgn, GameResult, NAG_BLUNDER};
+///
+/// let pgn = r#"[Event "Casual game"]
+/// [White "Anderssen"]
+///
+/// 1. f3 e5 2. g4?? {Losing at once.} (2. e4 Nc6) 2... Qh4# 0-1
+///
+/// [Event "Casual game"]
+///
+/// 1. e4 e5 1/2-1/2
+/// "#;
+///
+/// let games = parse_pgn(pgn).unwrap();
+/// assert_eq!(games.len(), 2);
+///
+/// let line = games[0].main_line();
+/// assert_eq!(line[2].nags, [NAG_BLUNDER]);
+/// assert_eq!(line[2].comment.as_deref(), Some("Losing at once."));
+/// assert_eq!(line[2].variations[0][1].san, "Nc6");
+/// assert_eq!(games[0].result(), GameResult::BlackWins);
+/// assert!(games[0].to_pgn().ends_with("2. g4?? {Losing at once.} (2. e4 Nc6) 2... Qh4# 0-1\n"));
+///
+/// // The draw was agreed rather than reached on the board.
+/// assert_eq!(games[1].result(), GameResult::DrawAdjudicated);
+///
+/// // A tag ends on the line it starts, even after an escape.
+/// assert!(parse_pgn("[Event \"Casual\\\n\"]\n\n1. e4 *").is_err());
+/// ```
+pub fn parse_pgn(pgn: &str) -> Result<Vec<Game>, PgnError> {
+    let mut games = vec![];
+    let mut tokens = tokenize(pgn)?.into_iter().peekable();
+
+    while tokens.peek().is_some() {
+        let mut tags = vec![];
+
+        while let Some(Token::Tag(name, value)) =
+            tokens.next_if(|token| matches!(token, Token::Tag(..)))
+        {
+            tags.push((name, value));
+        }
+
+        // The movetext runs until the result, or until the tags of the next game if the result
+        // is missing.
+        let mut movetext = vec![];
+        let mut result = GameResult::Ongoing;
+
+        while let Some(token) = tokens.next_if(|token| !matches!(token, Token::Tag(..))) {
+            if let Token::Result(game_result) = token {
+                result = game_result;
+                break;
+            }
+
+            movetext.push(token);
+        }
+
+        games.push(read_game(&tags, movetext, result)?);
+    }
+
+    Ok(games)
+}
+
+impl Game {
+    /// Reads the first game in some PGN, as `parse_pgn` does. Returns `None` if there isn't one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Game;
+    ///
+    /// let game = Game::from_pgn("1. e4 e5 2. Nf3 *").unwrap().unwrap();
+    /// assert_eq!(game.ply(), 3);
+    /// assert_eq!(game.main_line()[2].san, "Nf3");
+    /// ```
+    pub fn from_pgn(pgn: &str) -> Result<Option<Game>, PgnError> {
+        Ok(parse_pgn(pgn)?.into_iter().next())
+    }
+}
+
+// Builds a game from its tags and the tokens of its movetext, which end before the result.
+fn read_game(
+    tags: &[(String, String)],
+    movetext: Vec<Token>,
+    result: GameResult,
+) -> Result<Game, PgnError> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut start = Engine::default();
+    start.set_initial_position();
+
+    if tag("Variant").is_some_and(|variant| variant.contains("960")) {
+        start.set_chess960(true);
+    }
+
+    if let Some(fen) = tag("FEN") {
+        start.set_fen(fen).map_err(PgnError::InvalidFen)?;
+    }
+
+    let line = read_line(&start, &mut movetext.into_iter(), false)?;
+
+    let mut end = start.clone();
+
+    for game_move in &line {
+        end.make_move(game_move.piece_move);
+    }
+
+    let final_result = end.game_result();
+    let adjudicated = match result {
+        GameResult::Ongoing => None,
+        _ if result == final_result || (result.is_draw() && final_result.is_draw()) => None,
+        _ => Some(result),
+    };
+
+    let mut game = Game::from_position(start);
+
+    // Games without a date shouldn't be given today's.
+    game.set_tag("Date", "????.??.??");
+
+    for (name, value) in tags {
+        // These are written from the game itself.
+        if !["Result", "SetUp", "FEN"].contains(&name.as_str()) {
+            game.set_tag(name, value);
+        }
+    }
+
+    game.set_record(line, adjudicated);
+
+    Ok(game)
+}
+
+// Reads a line of moves played from the given position, with the comments, annotations and
+// variations on them, up to the end of the movetext or of the variation being read.
+fn read_line(
+    position: &Engine,
+    tokens: &mut IntoIter<Token>,
+    in_variation: bool,
+) -> Result<Vec<GameMove>, PgnError> {
+    let mut line: Vec<GameMove> = vec![];
+    let mut position = position.clone();
+    // The position before the last move, which its variations are played from.
+    let mut previous = position.clone();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Move(san) => {
+                let piece_move = position
+                    .move_from_san(&san)
+                    .ok_or_else(|| PgnError::InvalidMove(san.clone()))?;
+
+                // Annotation symbols written after the move are kept as their glyphs.
+                let symbols = &san[san.trim_end_matches(['!', '?']).len()..];
+                let nags = (1..=6)
+                    .filter(|&nag| nag_glyph(nag) == Some(symbols))
+                    .collect();
+
+                line.push(GameMove {
+                    piece_move,
+                    san: position.move_to_san(piece_move),
+                    timestamp: 0,
+                    comment: None,
+                    nags,
+                    variations: vec![],
+                });
+
+                previous = position.clone();
+                position.make_move(piece_move);
+            }
+            // Comments before the first move have nothing to be attached to, so are dropped.
+            Token::Comment(text) => {
+                if let Some(last) = line.last_mut() {
+                    last.comment = Some(match last.comment.take() {
+                        Some(comment) => comment + " " + &text,
+                        None => text,
+                    });
+                }
+            }
+            Token::Nag(nag) => {
+                if let Some(last) = line.last_mut() {
+                    if !last.nags.contains(&nag) {
+                        last.nags.push(nag);
+                    }
+                }
+            }
+            Token::StartVariation => {
+                let variation = read_line(&previous, tokens, true)?;
+                let last = line.last_mut().ok_or(PgnError::InvalidVariation)?;
+
+                if !variation.is_empty() {
+                    last.variations.push(variation);
+                }
+            }
+            Token::EndVariation if in_variation => return Ok(line),
+            Token::EndVariation => return Err(PgnError::InvalidVariation),
+            // Results are only expected at the end of the game, but are sometimes given at the
+            // end of variations too.
+            Token::Result(_) | Token::Tag(..) => {}
+        }
+    }
+
+    if in_variation {
+        return Err(PgnError::InvalidVariation);
+    }
+
+    Ok(line)
+}
+
+// Splits PGN into tags, comments, variation brackets, annotations, moves and results. Move
+// numbers and escaped lines are skipped.
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = vec![];
+    let mut chars = pgn.char_indices().peekable();
+    let mut line_start = true;
+
+    while let Some((index, c)) = chars.next() {
+        let at_line_start = line_start;
+        line_start = c == '\n';
+
+        match c {
+            _ if c.is_whitespace() => {}
+            '%' if at_line_start => {
+                chars.find(|&(_, c)| c == '\n');
+                line_start = true;
+            }
+            ';' => {
+                let comment: String = chars
+                    .by_ref()
+                    .map(|(_, c)| c)
+                    .take_while(|&c| c != '\n')
+                    .collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+            }
+            '{' => {
+                let mut comment = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => comment.push(c),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+
+                tokens.push(Token::Comment(
+                    comment.split_whitespace().collect::<Vec<_>>().join(" "),
+                ));
+            }
+            '[' => {
+                let end = pgn[index..]
+                    .find(['\n', ']'])
+                    .map_or(pgn.len(), |end| index + end);
+
+                // Quotes and brackets inside the value are escaped, so the tag ends at the first
+                // unescaped closing quote.
+                let mut value = String::new();
+                let mut name = String::new();
+                let mut in_value = false;
+                let mut closed = false;
+
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        // An escape can't run on to the next line, which ends the tag.
+                        '\\' if in_value => {
+                            if let Some((_, escaped)) = chars.next_if(|&(_, c)| c != '\n') {
+                                value.push(escaped);
+                            }
+                        }
+                        '"' => {
+                            if in_value {
+                                closed = true;
+                                chars.find(|&(_, c)| c == ']' || c == '\n');
+                                break;
+                            }
+
+                            in_value = true;
+                        }
+                        '\n' => break,
+                        _ if in_value => value.push(c),
+                        _ if !c.is_whitespace() => name.push(c),
+                        _ => {}
+                    }
+                }
+
+                if !closed || name.is_empty() {
+                    return Err(PgnError::InvalidTag(pgn[index..end].to_string()));
+                }
+
+                tokens.push(Token::Tag(name, value));
+                line_start = false;
+            }
+            '(' => tokens.push(Token::StartVariation),
+            ')' => tokens.push(Token::EndVariation),
+            _ => {
+                let mut end = index + c.len_utf8();
+
+                while let Some(&(next, c)) = chars.peek() {
+                    if c.is_whitespace() || "{};()[".contains(c) {
+                        break;
+                    }
+
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+
+                let word = &pgn[index..end];
+
+                if let Some(nag) = word.strip_prefix('$') {
+                    let nag = nag
+                        .parse()
+                        .map_err(|_| PgnError::InvalidMove(word.to_string()))?;
+                    tokens.push(Token::Nag(nag));
+                    continue;
+                }
+
+                let result = match word {
+                    "1-0" => Some(GameResult::WhiteWins),
+                    "0-1" => Some(GameResult::BlackWins),
+                    "1/2-1/2" => Some(GameResult::DrawAdjudicated),
+                    "*" => Some(GameResult::Ongoing),
+                    _ => None,
+                };
+
+                if let Some(result) = result {
+                    tokens.push(Token::Result(result));
+                    continue;
+                }
+
+                // Move numbers, such as `12.` or `12...`, may be written against the move.
+                let san = match word.find(|c: char| !c.is_ascii_digit()) {
+                    Some(digits) if digits > 0 && word[digits..].starts_with('.') => {
+                        word[digits..].trim_start_matches('.')
+                    }
+                    _ => word,
+                };
+
+                if !san.is_empty() {
+                    tokens.push(Token::Move(san.to_string()));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
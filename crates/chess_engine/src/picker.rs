@@ -0,0 +1,309 @@
+use crate::{
+    mvv_lva, Move, MoveList, MoveOrdering, PieceType, Position, MAX_MOVES, MVV_LVA_VALUES,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    GenerateEvasions,
+    Evasions,
+    Done,
+}
+
+// In check, captures and promotions are tried before other evasions, which are ordered by their
+// history scores. This is above the highest history score.
+const EVASION_CAPTURE_SCORE: i32 = 100_000;
+
+/// Yields the legal moves of a position one at a time, roughly best first, generating them in
+/// stages: the hash move, captures which don't appear to lose material, killer moves, other quiet
+/// moves by history score, and finally the remaining captures. Each stage is only generated once
+/// the ones before it have been tried, so a search which cuts off early doesn't pay for
+/// generating the rest. When the side to move is in check, only moves which may evade it are
+/// generated after the hash move, captures first.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, MoveOrdering, MovePicker};
+///
+/// let mut engine = Engine::default();
+/// engine.set_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+///
+/// let ordering = MoveOrdering::new();
+/// let mut picker = MovePicker::new(None, [None; 2]);
+///
+/// // The capture comes first, and every legal move is picked exactly once.
+/// let first = picker.next(&engine, &ordering).unwrap();
+/// assert_eq!(first.to_string(), "e4d5");
+///
+/// let mut count = 1;
+/// while picker.next(&engine, &ordering).is_some() {
+///     count += 1;
+/// }
+/// assert_eq!(count, engine.legal_moves().len());
+/// ```
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    killer_index: usize,
+
+    // The moves generated so far, with their ordering scores. Those before `index` have already
+    // been picked.
+    moves: MoveList,
+    scores: [i32; MAX_MOVES],
+    index: usize,
+
+    // Captures put aside during the good captures stage are kept at the start of `moves`, in the
+    // order they were found.
+    bad_capture_count: usize,
+    bad_capture_index: usize,
+
+    // Only captures and promotions are picked, as in quiescence search.
+    captures_only: bool,
+}
+
+impl MovePicker {
+    /// Creates a picker for all legal moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_move` - The move to try first, such as the best move found by an earlier search of
+    ///   the position. It is skipped if it isn't legal.
+    /// * `killers` - Quiet moves which caused cutoffs at the same ply, tried before other quiet
+    ///   moves if they are legal.
+    pub fn new(hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        Self {
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            killer_index: 0,
+            moves: MoveList::new(),
+            scores: [0; MAX_MOVES],
+            index: 0,
+            bad_capture_count: 0,
+            bad_capture_index: 0,
+            captures_only: false,
+        }
+    }
+
+    /// Creates a picker for only the legal captures and queen promotions, best first.
+    pub fn captures() -> Self {
+        Self {
+            stage: Stage::GenerateCaptures,
+            captures_only: true,
+            ..Self::new(None, [None; 2])
+        }
+    }
+
+    /// Returns the next legal move, or `None` once every move has been picked. The same position
+    /// and ordering must be given each time.
+    pub fn next(&mut self, position: &Position, ordering: &MoveOrdering) -> Option<Move> {
+        let side = position.side_to_move();
+        let board = position.board();
+        let is_legal = |piece_move: &Move| !board.leaves_king_in_check(piece_move, side);
+
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = if !self.captures_only && position.in_check() {
+                        Stage::GenerateEvasions
+                    } else {
+                        Stage::GenerateCaptures
+                    };
+
+                    if let Some(hash_move) = self.hash_move {
+                        if position.is_pseudo_legal(hash_move) && is_legal(&hash_move) {
+                            return Some(hash_move);
+                        }
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    position.generate_captures(&mut self.moves);
+
+                    for (i, piece_move) in self.moves.iter().enumerate() {
+                        self.scores[i] = capture_score(piece_move, position);
+                    }
+
+                    self.index = 0;
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    let Some(piece_move) = self.pick_best() else {
+                        self.stage = if self.captures_only {
+                            Stage::BadCaptures
+                        } else {
+                            Stage::Killers
+                        };
+                        continue;
+                    };
+
+                    if Some(piece_move) == self.hash_move {
+                        continue;
+                    }
+
+                    // Promoting to anything but a queen is almost never better, and only worth
+                    // trying once everything else has been.
+                    if self.captures_only && is_underpromotion(&piece_move) {
+                        continue;
+                    }
+
+                    if is_bad_capture(&piece_move, position) {
+                        self.moves.swap(self.bad_capture_count, self.index - 1);
+                        self.bad_capture_count += 1;
+                    } else if is_legal(&piece_move) {
+                        return Some(piece_move);
+                    }
+                }
+                Stage::Killers => {
+                    let Some(&killer) = self.killers.get(self.killer_index) else {
+                        self.stage = Stage::GenerateQuiets;
+                        continue;
+                    };
+
+                    self.killer_index += 1;
+
+                    if let Some(killer) = killer {
+                        if Some(killer) != self.hash_move
+                            && killer.is_quiet()
+                            && position.is_pseudo_legal(killer)
+                            && is_legal(&killer)
+                        {
+                            return Some(killer);
+                        }
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    self.moves.truncate(self.bad_capture_count);
+                    position.generate_quiets(&mut self.moves);
+
+                    for i in self.bad_capture_count..self.moves.len() {
+                        self.scores[i] = ordering.history_score(&self.moves[i], side);
+                    }
+
+                    self.index = self.bad_capture_count;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    let Some(piece_move) = self.pick_best() else {
+                        self.stage = Stage::BadCaptures;
+                        continue;
+                    };
+
+                    if Some(piece_move) != self.hash_move
+                        && !self.killers.contains(&Some(piece_move))
+                        && is_legal(&piece_move)
+                    {
+                        return Some(piece_move);
+                    }
+                }
+                Stage::BadCaptures => {
+                    if self.bad_capture_index >= self.bad_capture_count {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+
+                    let piece_move = self.moves[self.bad_capture_index];
+
+                    self.bad_capture_index += 1;
+
+                    if is_legal(&piece_move) {
+                        return Some(piece_move);
+                    }
+                }
+                Stage::GenerateEvasions => {
+                    position.generate_evasions(&mut self.moves);
+
+                    for (i, piece_move) in self.moves.iter().enumerate() {
+                        self.scores[i] = if piece_move.is_capture() || piece_move.is_promotion() {
+                            EVASION_CAPTURE_SCORE + capture_score(piece_move, position)
+                        } else {
+                            ordering.history_score(piece_move, side)
+                        };
+                    }
+
+                    self.index = 0;
+                    self.stage = Stage::Evasions;
+                }
+                Stage::Evasions => {
+                    let Some(piece_move) = self.pick_best() else {
+                        self.stage = Stage::Done;
+                        continue;
+                    };
+
+                    if Some(piece_move) != self.hash_move && is_legal(&piece_move) {
+                        return Some(piece_move);
+                    }
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    // Takes the highest scoring move not yet picked from the current stage, by moving it to the
+    // front of the remaining moves. This is cheaper than sorting when only a few moves are used.
+    fn pick_best(&mut self) -> Option<Move> {
+        if self.index >= self.moves.len() {
+            return None;
+        }
+
+        let best = (self.index..self.moves.len())
+            .max_by_key(|&i| self.scores[i])
+            .unwrap_or(self.index);
+
+        self.moves.swap(self.index, best);
+        self.scores.swap(self.index, best);
+        self.index += 1;
+
+        Some(self.moves[self.index - 1])
+    }
+}
+
+// Orders captures by MVV-LVA, with promotions ranked by the piece promoted to.
+fn capture_score(piece_move: &Move, position: &Position) -> i32 {
+    let attacker = position
+        .board()
+        .piece_type_at(piece_move.from.index())
+        .unwrap_or(PieceType::Pawn);
+
+    piece_move
+        .captured
+        .map_or(0, |victim| mvv_lva(victim, attacker))
+        + piece_move
+            .promote
+            .map_or(0, |promote| MVV_LVA_VALUES[promote.val()] * 100)
+}
+
+// Whether a capture looks like it loses material: a piece taking a less valuable one on a square
+// the opponent defends. This is a cheap stand-in for a full static exchange evaluation. Moves
+// which promote to anything but a queen are also counted, so that they are tried last.
+fn is_bad_capture(piece_move: &Move, position: &Position) -> bool {
+    let board = position.board();
+
+    if is_underpromotion(piece_move) {
+        return true;
+    }
+
+    let (Some(victim), None) = (piece_move.captured, piece_move.promote) else {
+        return false;
+    };
+
+    let attacker = board
+        .piece_type_at(piece_move.from.index())
+        .unwrap_or(PieceType::Pawn);
+
+    MVV_LVA_VALUES[attacker.val()] > MVV_LVA_VALUES[victim.val()]
+        && board.is_square_attacked(piece_move.to.index(), position.side_to_move().flip())
+}
+
+fn is_underpromotion(piece_move: &Move) -> bool {
+    piece_move
+        .promote
+        .is_some_and(|promote| promote != PieceType::Queen)
+}
@@ -0,0 +1,1658 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::board::*;
+use crate::eval::piece_scores;
+use crate::{
+    zobrist, BoardRenderer, FenError, File, Move, MoveError, MoveKind, MoveList, PieceType, Side,
+    Square,
+};
+
+/// A square whose contents differ between two positions, as found by [`Position::diff`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SquareChange {
+    pub square: Square,
+    // The piece on the square in the first position, and in the second.
+    pub before: Option<(PieceType, Side)>,
+    pub after: Option<(PieceType, Side)>,
+}
+
+/// The state of the board at one point in a game: where the pieces are, who is to move, and the
+/// castling rights, en passant square and move clocks. A position doesn't know how it was
+/// reached, so cloning one allocates nothing; [`Engine`] tracks the game leading up to it.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Move, Position};
+///
+/// let mut start = Position::default();
+/// start.set_initial_position();
+///
+/// let play = |moves: [&str; 4]| {
+///     let mut position = start.clone();
+///
+///     for notation in moves {
+///         position.make_move(Move::from_uci_str(notation).unwrap());
+///     }
+///
+///     position
+/// };
+///
+/// // The same position, reached by a different move order.
+/// assert!(play(["g1f3", "g8f6", "b1c3", "b8c6"]) == play(["b1c3", "b8c6", "g1f3", "g8f6"]));
+/// assert!(play(["g1f3", "g8f6", "b1c3", "b8c6"]) != start);
+/// ```
+///
+/// [`Engine`]: crate::Engine
+#[derive(Clone, PartialEq, Eq)]
+pub struct Position {
+    board: Board,
+
+    side_to_move: Side,
+    castling: Castling,
+
+    // The square behind a pawn which has just made a double push, but only if an enemy pawn is in
+    // a position to capture it.
+    en_passant: Option<Square>,
+
+    // Number of half moves since the last capture or pawn move, for the fifty-move rule.
+    halfmove_clock: u32,
+    fullmove_number: u32,
+
+    hash: u64,
+
+    // The material and piece-square scores of each side, as (middlegame, endgame) pairs, kept up
+    // to date as pieces are placed and removed so that evaluation needn't add them up.
+    material: [(i32, i32); 2],
+    piece_squares: [(i32, i32); 2],
+}
+
+impl Position {
+    pub fn side_to_move(&self) -> Side {
+        self.side_to_move
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the castling rights still held and the rooks they castle with.
+    pub fn castling(&self) -> &Castling {
+        &self.castling
+    }
+
+    /// Returns the square a pawn may capture onto en passant, if there is one.
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Returns the Zobrist hash of the current position, which is its key in Polyglot opening
+    /// books.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    /// assert_eq!(position.hash(), 0x463b96181691fc9c);
+    ///
+    /// // The en passant square only counts when a pawn can capture onto it.
+    /// position
+    ///     .set_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+    ///     .unwrap();
+    /// assert_eq!(position.hash(), 0x22a48b5a8e47ff78);
+    ///
+    /// position
+    ///     .set_fen("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4")
+    ///     .unwrap();
+    /// assert_eq!(position.hash(), 0x5c3f9b829b279560);
+    /// ```
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the type and side of the piece on the given square, if there is one.
+    pub fn piece_at(&self, square: Square) -> Option<(PieceType, Side)> {
+        self.board.piece_at(square.index())
+    }
+
+    /// Returns an iterator over the pieces on the board, from a1 to h8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{PieceType, Position, Side, Square};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// let pieces: Vec<_> = position.pieces().collect();
+    /// assert_eq!(
+    ///     pieces,
+    ///     [
+    ///         (Square::new(4), PieceType::King, Side::White),
+    ///         (Square::new(7), PieceType::Rook, Side::White),
+    ///         (Square::new(60), PieceType::King, Side::Black),
+    ///     ]
+    /// );
+    ///
+    /// let e1 = "e1".parse().unwrap();
+    /// assert_eq!(position.piece_at(e1), Some((PieceType::King, Side::White)));
+    /// ```
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, PieceType, Side)> + '_ {
+        (0..64).filter_map(|index| {
+            self.board
+                .piece_at(index)
+                .map(|(piece_type, side)| (Square::new(index), piece_type, side))
+        })
+    }
+
+    /// Returns the squares whose contents differ between this position and another, from a1 to
+    /// h8, such as for a GUI to animate a move or a test to find where two positions diverge.
+    /// Only the pieces are compared, not the side to move or other state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Move, PieceType, Position, Side, SquareChange};
+    ///
+    /// let mut before = Position::default();
+    /// before.set_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// let mut after = before.clone();
+    /// after.make_move(Move::from_uci_str("e1g1").unwrap());
+    ///
+    /// let king = Some((PieceType::King, Side::White));
+    /// let rook = Some((PieceType::Rook, Side::White));
+    /// let square = |name: &str| name.parse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     before.diff(&after),
+    ///     [
+    ///         SquareChange { square: square("e1"), before: king, after: None },
+    ///         SquareChange { square: square("f1"), before: None, after: rook },
+    ///         SquareChange { square: square("g1"), before: None, after: king },
+    ///         SquareChange { square: square("h1"), before: rook, after: None },
+    ///     ]
+    /// );
+    /// assert!(before.diff(&before).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Position) -> Vec<SquareChange> {
+        (0..64)
+            .filter_map(|index| {
+                let (before, after) = (self.board.piece_at(index), other.board.piece_at(index));
+
+                (before != after).then(|| SquareChange {
+                    square: Square::new(index),
+                    before,
+                    after,
+                })
+            })
+            .collect()
+    }
+
+    /// The material of each side, indexed by side, as (middlegame, endgame) pairs in centipawns.
+    /// This is kept up to date as moves are made rather than counted when asked for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move, PieceType, Position, Side, ENDGAME_VALUES, MIDDLEGAME_VALUES};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("r3k3/1P6/8/3pP3/8/8/8/R3K2R w KQq d6 0 1").unwrap();
+    ///
+    /// // Castling, capturing en passant, and promoting with a capture.
+    /// for notation in ["e1g1", "e8f7", "e5d6", "f7e6", "b7a8q"] {
+    ///     engine.make_move(Move::from_uci_str(notation).unwrap());
+    /// }
+    ///
+    /// let mut recounted = Position::default();
+    /// recounted.set_fen(&engine.fen()).unwrap();
+    /// assert_eq!(engine.material_scores(), recounted.material_scores());
+    /// assert_eq!(engine.piece_square_scores(), recounted.piece_square_scores());
+    ///
+    /// // Black has only its king left.
+    /// let queen = PieceType::Queen.val();
+    /// let (pawn, rook) = (PieceType::Pawn.val(), PieceType::Rook.val());
+    /// let white = (
+    ///     MIDDLEGAME_VALUES[queen] + MIDDLEGAME_VALUES[pawn] + 2 * MIDDLEGAME_VALUES[rook],
+    ///     ENDGAME_VALUES[queen] + ENDGAME_VALUES[pawn] + 2 * ENDGAME_VALUES[rook],
+    /// );
+    /// assert_eq!(engine.material_scores(), [white, (0, 0)]);
+    /// ```
+    pub fn material_scores(&self) -> [(i32, i32); 2] {
+        self.material
+    }
+
+    /// The piece-square table scores of each side, indexed by side, as (middlegame, endgame)
+    /// pairs in centipawns. Like `material_scores`, this is kept up to date as moves are made.
+    pub fn piece_square_scores(&self) -> [(i32, i32); 2] {
+        self.piece_squares
+    }
+
+    /// The number of the current move, starting at 1 and increasing after each move by Black.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// The number of half moves since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn set_initial_position(&mut self) {
+        self.clear();
+
+        for file in 1..=8 {
+            self.set_square(7 + file, Side::White, Some(PieceType::Pawn));
+            self.set_square(47 + file, Side::Black, Some(PieceType::Pawn));
+        }
+
+        self.set_square(1, Side::White, Some(PieceType::Knight));
+        self.set_square(6, Side::White, Some(PieceType::Knight));
+        self.set_square(57, Side::Black, Some(PieceType::Knight));
+        self.set_square(62, Side::Black, Some(PieceType::Knight));
+
+        self.set_square(2, Side::White, Some(PieceType::Bishop));
+        self.set_square(5, Side::White, Some(PieceType::Bishop));
+        self.set_square(58, Side::Black, Some(PieceType::Bishop));
+        self.set_square(61, Side::Black, Some(PieceType::Bishop));
+
+        self.set_square(0, Side::White, Some(PieceType::Rook));
+        self.set_square(7, Side::White, Some(PieceType::Rook));
+        self.set_square(56, Side::Black, Some(PieceType::Rook));
+        self.set_square(63, Side::Black, Some(PieceType::Rook));
+
+        self.set_square(3, Side::White, Some(PieceType::Queen));
+        self.set_square(59, Side::Black, Some(PieceType::Queen));
+
+        self.set_square(4, Side::White, Some(PieceType::King));
+        self.set_square(60, Side::Black, Some(PieceType::King));
+
+        self.castling.rights = CASTLE_ALL;
+        self.hash = self.compute_hash();
+    }
+
+    /// Sets up one of the 960 starting positions of Chess960, numbered from 0 to 959 as in
+    /// Scharnagl's scheme, in which the standard starting position is number 518. Castling moves
+    /// are written as Chess960 requires from then on, as [`Position::set_chess960`] describes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number is 960 or more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    ///
+    /// position.set_chess960_position(518);
+    /// assert_eq!(
+    ///     position.fen(),
+    ///     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    /// );
+    ///
+    /// position.set_chess960_position(0);
+    /// assert_eq!(
+    ///     position.fen(),
+    ///     "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    /// );
+    /// ```
+    pub fn set_chess960_position(&mut self, number: u32) {
+        assert!(
+            number < 960,
+            "Chess960 positions are numbered from 0 to 959"
+        );
+
+        // The files of the two knights among the five squares left once the bishops and queen
+        // have been placed, for each knight number.
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+
+        let mut back_rank = [None; 8];
+        let number = number as usize;
+
+        // The bishops go on opposite colours, then the queen and knights on the squares left.
+        back_rank[number % 4 * 2 + 1] = Some(PieceType::Bishop);
+        back_rank[number / 4 % 4 * 2] = Some(PieceType::Bishop);
+
+        fn empty_files(back_rank: &[Option<PieceType>; 8]) -> impl Iterator<Item = usize> + '_ {
+            (0..8).filter(|&file| back_rank[file].is_none())
+        }
+
+        let queen = empty_files(&back_rank).nth(number / 16 % 6).unwrap();
+        back_rank[queen] = Some(PieceType::Queen);
+
+        let (first_knight, second_knight) = KNIGHTS[number / 96];
+        let knights: Vec<usize> = empty_files(&back_rank)
+            .enumerate()
+            .filter(|&(i, _)| i == first_knight || i == second_knight)
+            .map(|(_, file)| file)
+            .collect();
+
+        for file in knights {
+            back_rank[file] = Some(PieceType::Knight);
+        }
+
+        // The king goes between the rooks on the three squares left.
+        let rest: Vec<usize> = empty_files(&back_rank).collect();
+        back_rank[rest[0]] = Some(PieceType::Rook);
+        back_rank[rest[1]] = Some(PieceType::King);
+        back_rank[rest[2]] = Some(PieceType::Rook);
+
+        self.clear();
+
+        for (file, piece_type) in back_rank.into_iter().enumerate() {
+            self.set_square(file, Side::White, piece_type);
+            self.set_square(8 + file, Side::White, Some(PieceType::Pawn));
+            self.set_square(48 + file, Side::Black, Some(PieceType::Pawn));
+            self.set_square(56 + file, Side::Black, piece_type);
+        }
+
+        self.castling = Castling {
+            rights: CASTLE_ALL,
+            rooks: [
+                Square::new(rest[2]),
+                Square::new(rest[0]),
+                Square::new(56 + rest[2]),
+                Square::new(56 + rest[0]),
+            ],
+            chess960: true,
+        };
+        self.hash = self.compute_hash();
+    }
+
+    /// Whether castling moves are written as in Chess960, which [`Position::set_chess960`]
+    /// describes.
+    pub fn is_chess960(&self) -> bool {
+        self.castling.chess960
+    }
+
+    /// Sets whether castling moves are written as in Chess960, where the king captures its own
+    /// rook, as UCI expects when the `UCI_Chess960` option is set. Otherwise they are written as
+    /// the king moving two squares. Castling itself works the same either way, including from
+    /// Chess960 starting positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// let castle = |position: &Position| {
+    ///     position
+    ///         .legal_moves()
+    ///         .into_iter()
+    ///         .find(|piece_move| piece_move.is_castle())
+    ///         .map(|castle| castle.to_string())
+    /// };
+    ///
+    /// assert_eq!(castle(&position).as_deref(), Some("e1g1"));
+    ///
+    /// position.set_chess960(true);
+    /// assert_eq!(castle(&position).as_deref(), Some("e1h1"));
+    /// ```
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.castling.chess960 = chess960;
+    }
+
+    /// Sets up the position described by a FEN string. The halfmove clock and fullmove number may
+    /// be left out, as they are in EPD. If the string is invalid, the position is left unchanged.
+    /// An en passant square is only kept if a pawn can capture onto it, and one which no pawn
+    /// could have passed over with a double push makes the string invalid.
+    ///
+    /// Castling rights may be given as the files of the rooks, as in Shredder-FEN, as well as with
+    /// `KQkq`, which mean the outermost rook on each side of the king as in X-FEN, so Chess960
+    /// positions can be read. Rights without a rook on the back rank to castle with are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Position, Side};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let mut from_fen = Position::default();
+    /// from_fen
+    ///     .set_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+    ///     .unwrap();
+    /// assert_eq!(from_fen.hash(), position.hash());
+    ///
+    /// from_fen.set_fen("4k3/8/8/8/8/8/4P3/4K3 b - -").unwrap();
+    /// assert_eq!(from_fen.side_to_move(), Side::Black);
+    ///
+    /// assert!(from_fen.set_fen("not a fen").is_err());
+    ///
+    /// // An en passant square must be behind a pawn which has just made a double push.
+    /// assert!(from_fen.set_fen("4k3/8/8/8/8/3PN3/8/4K3 w - e4 0 1").is_err());
+    ///
+    /// // Nor can a pawn stand on a back rank.
+    /// assert!(from_fen.set_fen("4k3/8/8/8/8/8/8/1p2K3 w - - 0 1").is_err());
+    ///
+    /// // Black may be in check with Black to move, but not with White to move.
+    /// assert!(from_fen.set_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1").is_ok());
+    /// assert!(from_fen.set_fen("4k3/8/8/8/8/8/8/4RK2 w - - 0 1").is_err());
+    ///
+    /// // White may castle with the rook on the g-file, which isn't the outermost, so its file is
+    /// // written; `A` means the outermost rook on the queenside, which is written as `Q`.
+    /// from_fen
+    ///     .set_fen("r3k1rr/8/8/8/8/8/8/R3K1RR w GAk - 0 1")
+    ///     .unwrap();
+    /// assert_eq!(from_fen.fen(), "r3k1rr/8/8/8/8/8/8/R3K1RR w GQk - 0 1");
+    /// ```
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        let mut fields = fen.split_whitespace();
+        let mut position = Position::default();
+
+        let placement = fields
+            .next()
+            .ok_or(FenError::MissingField("piece placement"))?;
+        let ranks: Vec<&str> = placement.split('/').collect();
+
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement(placement.to_string()));
+        }
+
+        // Ranks are given from the eighth down to the first.
+        for (rank, pieces) in ranks.iter().rev().enumerate() {
+            let mut file = 0;
+
+            for c in pieces.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    file += empty as usize;
+                    continue;
+                }
+
+                let piece_type = piece_from_char(c)
+                    .ok_or_else(|| FenError::InvalidPlacement(placement.to_string()))?;
+                let side = if c.is_ascii_uppercase() {
+                    Side::White
+                } else {
+                    Side::Black
+                };
+
+                if file >= 8 {
+                    return Err(FenError::InvalidPlacement(placement.to_string()));
+                }
+
+                position.set_square(rank * 8 + file, side, Some(piece_type));
+                file += 1;
+            }
+
+            if file != 8 {
+                return Err(FenError::InvalidPlacement(placement.to_string()));
+            }
+        }
+
+        // Pawns can't stand on either back rank, where they would already have promoted, or
+        // still be to move from.
+        if position.board.bitboard_by_piece[PieceType::Pawn.val()] & (RANK_1 | RANK_8) != 0 {
+            return Err(FenError::InvalidPlacement(placement.to_string()));
+        }
+
+        // Each side needs exactly one king for the position to be playable.
+        for side in [Side::White, Side::Black] {
+            let kings = position.board.bitboard_by_piece[PieceType::King.val()]
+                & position.board.bitboard_by_side[side.val()];
+
+            if kings.count_ones() != 1 {
+                return Err(FenError::InvalidPlacement(placement.to_string()));
+            }
+        }
+
+        position.side_to_move = match fields.next() {
+            Some("w") => Side::White,
+            Some("b") => Side::Black,
+            Some(side) => return Err(FenError::InvalidSideToMove(side.to_string())),
+            None => return Err(FenError::MissingField("side to move")),
+        };
+
+        // The side not to move can't be in check, or its king could be captured.
+        let side_to_move = position.side_to_move;
+        let enemy_king = position.board.bitboard_by_piece[PieceType::King.val()]
+            & position.board.bitboard_by_side[side_to_move.flip().val()];
+
+        if position
+            .board
+            .attackers_to(enemy_king.trailing_zeros() as usize, side_to_move)
+            != 0
+        {
+            let side = if side_to_move == Side::White {
+                "w"
+            } else {
+                "b"
+            };
+            return Err(FenError::InvalidSideToMove(side.to_string()));
+        }
+
+        let castling = fields.next().ok_or(FenError::MissingField("castling"))?;
+        position.castling.chess960 = self.castling.chess960;
+
+        if castling != "-" {
+            for c in castling.chars() {
+                let side = if c.is_ascii_uppercase() {
+                    Side::White
+                } else {
+                    Side::Black
+                };
+
+                let rook_file = match c.to_ascii_lowercase() {
+                    'k' => position.outermost_rook_file(side, true),
+                    'q' => position.outermost_rook_file(side, false),
+                    c => File::from_char(c).map(File::index),
+                }
+                .ok_or_else(|| FenError::InvalidCastling(castling.to_string()))?;
+
+                position.add_castling_right(side, rook_file);
+            }
+        }
+
+        let en_passant = fields.next().ok_or(FenError::MissingField("en passant"))?;
+
+        if en_passant != "-" {
+            let square: Square = en_passant
+                .parse()
+                .map_err(|_| FenError::InvalidEnPassant(en_passant.to_string()))?;
+            let pushed_side = position.side_to_move.flip();
+
+            // The square must be the one passed over by a pawn which could just have made a
+            // double push, or capturing onto it would take the wrong piece.
+            let (rank, pawn_index, start_index) = match pushed_side {
+                Side::White => (2, square.index() + 8, square.index().wrapping_sub(8)),
+                _ => (5, square.index().wrapping_sub(8), square.index() + 8),
+            };
+
+            if square.rank().index() != rank
+                || position.board.piece_at(square.index()).is_some()
+                || position.board.piece_at(start_index).is_some()
+                || position.board.piece_at(pawn_index) != Some((PieceType::Pawn, pushed_side))
+            {
+                return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+            }
+
+            // As in `make_move`, only keep the square if a pawn could actually capture onto it.
+            let capturing_pawns = position.board.bitboard_by_piece[PieceType::Pawn.val()]
+                & position.board.bitboard_by_side[position.side_to_move.val()];
+
+            if pawn_east_attacks(square.bitboard(), capturing_pawns, pushed_side)
+                | pawn_west_attacks(square.bitboard(), capturing_pawns, pushed_side)
+                != 0
+            {
+                position.en_passant = Some(square);
+            }
+        }
+
+        if let Some(halfmove_clock) = fields.next() {
+            position.halfmove_clock = halfmove_clock
+                .parse()
+                .map_err(|_| FenError::InvalidClock(halfmove_clock.to_string()))?;
+        }
+
+        if let Some(fullmove_number) = fields.next() {
+            position.fullmove_number = fullmove_number
+                .parse()
+                .map_err(|_| FenError::InvalidClock(fullmove_number.to_string()))?;
+        }
+
+        position.hash = position.compute_hash();
+        *self = position;
+
+        Ok(())
+    }
+
+    /// Returns the FEN string describing the current position. The en passant square is only
+    /// given when a pawn can capture onto it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    /// assert_eq!(
+    ///     position.fen(),
+    ///     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    /// );
+    ///
+    /// let fen = "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 0 12";
+    /// position.set_fen(fen).unwrap();
+    /// assert_eq!(position.fen(), fen);
+    /// ```
+    pub fn fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                let square_idx = rank * 8 + file;
+
+                match self.board.piece_type_at(square_idx) {
+                    Some(piece_type) => {
+                        if empty > 0 {
+                            fen += &empty.to_string();
+                            empty = 0;
+                        }
+
+                        fen.push(char_from_piece(piece_type, self.side_on_square(square_idx)));
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen += &empty.to_string();
+            }
+
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen += match self.side_to_move {
+            Side::White => " w ",
+            _ => " b ",
+        };
+
+        if self.castling.rights == 0 {
+            fen.push('-');
+        }
+
+        // As in X-FEN, rights are given by the rook's file only when it isn't the outermost rook.
+        for (i, (side, kingside, c)) in [
+            (Side::White, true, 'K'),
+            (Side::White, false, 'Q'),
+            (Side::Black, true, 'k'),
+            (Side::Black, false, 'q'),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if self.castling.rights & (1 << i) == 0 {
+                continue;
+            }
+
+            let rook_file = self.castling.rooks[i].file();
+
+            if self.outermost_rook_file(side, kingside) == Some(rook_file.index()) {
+                fen.push(c);
+            } else if side == Side::White {
+                fen.push(rook_file.to_char().to_ascii_uppercase());
+            } else {
+                fen.push(rook_file.to_char());
+            }
+        }
+
+        match self.en_passant {
+            Some(en_passant) => fen += &format!(" {}", en_passant),
+            None => fen += " -",
+        }
+
+        fen + &format!(" {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+
+    /// Returns the same position with the colours swapped: the board is flipped top to bottom,
+    /// every piece changes side, and the other side is to move, with the castling rights and en
+    /// passant square to match. The game is the same from the point of view of the side to move,
+    /// so any evaluation from that point of view should give both positions the same score.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position
+    ///     .set_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 0 12")
+    ///     .unwrap();
+    ///
+    /// let mirrored = position.mirrored();
+    /// assert_eq!(mirrored.fen(), "r3k2r/8/8/8/3Pp3/8/8/R3K2R b Qk d3 0 12");
+    /// assert_eq!(mirrored.evaluate(), position.evaluate());
+    /// assert_eq!(mirrored.mirrored().hash(), position.hash());
+    /// ```
+    pub fn mirrored(&self) -> Position {
+        let mut mirrored = Position {
+            castling: Castling {
+                chess960: self.castling.chess960,
+                ..Castling::new()
+            },
+            ..Position::default()
+        };
+
+        for square_idx in 0..64 {
+            if let Some(piece_type) = self.board.piece_type_at(square_idx) {
+                let side = self.side_on_square(square_idx).flip();
+                mirrored.set_square(square_idx ^ 56, side, Some(piece_type));
+            }
+        }
+
+        // White's rights are the low two bits and Black's the high two, so swap the pairs along
+        // with their rooks.
+        let [white_kingside, white_queenside, black_kingside, black_queenside] =
+            self.castling.rooks;
+        mirrored.castling.rights = (self.castling.rights & 0b11) << 2 | self.castling.rights >> 2;
+        mirrored.castling.rooks = [
+            black_kingside,
+            black_queenside,
+            white_kingside,
+            white_queenside,
+        ]
+        .map(|rook| Square::new(rook.index() ^ 56));
+
+        mirrored.side_to_move = self.side_to_move.flip();
+        mirrored.en_passant = self
+            .en_passant
+            .map(|en_passant| Square::new(en_passant.index() ^ 56));
+        mirrored.halfmove_clock = self.halfmove_clock;
+        mirrored.fullmove_number = self.fullmove_number;
+        mirrored.hash = mirrored.compute_hash();
+
+        mirrored
+    }
+
+    // The file of the rook furthest from the side's king on its back rank, towards the h-file if
+    // `kingside` is set and the a-file otherwise. `None` if there is no such rook, or the king
+    // isn't on its back rank.
+    fn outermost_rook_file(&self, side: Side, kingside: bool) -> Option<usize> {
+        let back_rank = match side {
+            Side::White => 0,
+            _ => 56,
+        };
+
+        let is_ours = |piece_type, file: usize| {
+            self.board.piece_type_at(back_rank + file) == Some(piece_type)
+                && self.side_on_square(back_rank + file) == side
+        };
+
+        let king_file = (0..8).find(|&file| is_ours(PieceType::King, file))?;
+
+        if kingside {
+            (king_file + 1..8)
+                .rev()
+                .find(|&file| is_ours(PieceType::Rook, file))
+        } else {
+            (0..king_file).find(|&file| is_ours(PieceType::Rook, file))
+        }
+    }
+
+    // Gives the side the right to castle with the rook on the given file of its back rank, on
+    // whichever side of the king the rook is. Does nothing if the king or rook isn't there.
+    fn add_castling_right(&mut self, side: Side, rook_file: usize) {
+        let (back_rank, kingside, queenside) = match side {
+            Side::White => (0, CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE),
+            _ => (56, CASTLE_BLACK_KINGSIDE, CASTLE_BLACK_QUEENSIDE),
+        };
+
+        let is_ours = |piece_type, square_idx: usize| {
+            self.board.piece_type_at(square_idx) == Some(piece_type)
+                && self.side_on_square(square_idx) == side
+        };
+
+        let Some(king_file) = (0..8).find(|&file| is_ours(PieceType::King, back_rank + file))
+        else {
+            return;
+        };
+
+        if !is_ours(PieceType::Rook, back_rank + rook_file) || rook_file == king_file {
+            return;
+        }
+
+        let right = if rook_file > king_file {
+            kingside
+        } else {
+            queenside
+        };
+
+        self.castling.rights |= right;
+        self.castling.rooks[right.trailing_zeros() as usize] = Square::new(back_rank + rook_file);
+    }
+
+    // The castling rights lost when a piece moves from, or is captured on, the given square: both
+    // of a side's rights if its king is there, or the right to castle with a rook there.
+    fn castling_rights_lost(&self, square_idx: usize) -> u8 {
+        let mut lost = 0;
+
+        for (i, rook) in self.castling.rooks.iter().enumerate() {
+            if rook.index() == square_idx {
+                lost |= 1 << i;
+            }
+        }
+
+        if self.board.piece_type_at(square_idx) == Some(PieceType::King) {
+            lost |= match self.side_on_square(square_idx) {
+                Side::White => CASTLE_WHITE_KINGSIDE | CASTLE_WHITE_QUEENSIDE,
+                _ => CASTLE_BLACK_KINGSIDE | CASTLE_BLACK_QUEENSIDE,
+            };
+        }
+
+        lost
+    }
+
+    // Which way the move castles, if it does. Moves from move generation say so themselves, but
+    // one parsed from coordinates is taken to castle if the king moves two squares along its rank
+    // or onto its own rook.
+    pub(crate) fn castling_kind(&self, piece_move: Move) -> Option<MoveKind> {
+        if piece_move.is_castle() {
+            return Some(piece_move.kind);
+        }
+
+        let (from, to) = (piece_move.from.index(), piece_move.to.index());
+
+        match (self.piece_at(piece_move.from), self.piece_at(piece_move.to)) {
+            (Some((PieceType::King, side)), target)
+                if target == Some((PieceType::Rook, side))
+                    || (target.is_none() && from / 8 == to / 8 && from.abs_diff(to) == 2) =>
+            {
+                Some(if to > from {
+                    MoveKind::KingsideCastle
+                } else {
+                    MoveKind::QueensideCastle
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Removes all pieces and resets the game state.
+    fn clear(&mut self) {
+        self.board = Board::new();
+        self.material = [(0, 0); 2];
+        self.piece_squares = [(0, 0); 2];
+
+        self.side_to_move = Side::White;
+        self.castling = Castling {
+            chess960: self.castling.chess960,
+            ..Castling::new()
+        };
+        self.en_passant = None;
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
+
+        self.hash = self.compute_hash();
+    }
+
+    // Computes the Zobrist hash of the current position from scratch.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut hash = zobrist::castling_key(self.castling.rights);
+
+        for (square, piece_type, side) in self.pieces() {
+            hash ^= zobrist::piece_key(side, piece_type, square.index());
+        }
+
+        if let Some(en_passant) = self.en_passant {
+            hash ^= zobrist::en_passant_key(en_passant);
+        }
+
+        if self.side_to_move == Side::White {
+            hash ^= zobrist::side_key();
+        }
+
+        hash
+    }
+
+    // The side owning the piece on the given square. Only meaningful if the square is occupied.
+    fn side_on_square(&self, square_idx: usize) -> Side {
+        self.board
+            .piece_at(square_idx)
+            .map_or(Side::Black, |(_, side)| side)
+    }
+
+    // Places a piece on the given square, replacing whatever was there. If `piece_type` is `None`,
+    // the square is cleared regardless of `side`.
+    fn set_square(&mut self, square_idx: usize, side: Side, piece_type: Option<PieceType>) {
+        let piece = piece_type.map(|piece_type| (piece_type, side));
+
+        if let Some((old_piece_type, old_side)) = self.board.set_square(square_idx, piece) {
+            self.hash ^= zobrist::piece_key(old_side, old_piece_type, square_idx);
+            self.update_piece_scores(old_piece_type, old_side, square_idx, -1);
+        }
+
+        if let Some(piece_type) = piece_type {
+            self.hash ^= zobrist::piece_key(side, piece_type, square_idx);
+            self.update_piece_scores(piece_type, side, square_idx, 1);
+        }
+    }
+
+    // Adds the scores of a piece on a square to its side's totals, or with a `sign` of -1,
+    // subtracts them.
+    fn update_piece_scores(
+        &mut self,
+        piece_type: PieceType,
+        side: Side,
+        square_idx: usize,
+        sign: i32,
+    ) {
+        let (material, piece_square) = piece_scores(piece_type, side, square_idx);
+        let (total_material, total_piece_square) = (
+            &mut self.material[side.val()],
+            &mut self.piece_squares[side.val()],
+        );
+
+        total_material.0 += sign * material.0;
+        total_material.1 += sign * material.1;
+        total_piece_square.0 += sign * piece_square.0;
+        total_piece_square.1 += sign * piece_square.1;
+    }
+
+    /// Makes a move without checking that it is legal, which is the fast path used by the search.
+    /// The move should come from move generation, or at least be legal; anything else can leave
+    /// the position in an invalid state. Use [`Position::try_make_move`] for moves from elsewhere.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no piece on the square moved from.
+    pub fn make_move(&mut self, piece_move: Move) {
+        let from_index = piece_move.from.index();
+        let mut to_index = piece_move.to.index();
+
+        // Ascertain which side is making the move.
+        let side =
+            if self.board.bitboard_by_side[Side::White.val()] & piece_move.from.bitboard() != 0 {
+                Side::White
+            } else {
+                Side::Black
+            };
+
+        // Ascertain the piece type.
+        let from_piece_type = self
+            .board
+            .piece_type_at(from_index)
+            .unwrap_or_else(|| panic!("no piece to move on {}", piece_move.from));
+        let castling_kind = match from_piece_type {
+            PieceType::King => self.castling_kind(piece_move),
+            _ => None,
+        };
+        let is_capture = self.board.piece_type_at(to_index).is_some() && castling_kind.is_none();
+
+        // Moving a king or rook, or capturing a rook, loses the associated castling rights.
+        let rights_lost =
+            self.castling_rights_lost(from_index) | self.castling_rights_lost(to_index);
+
+        // Remove the old en passant square and castling rights from the hash; they are added back
+        // once updated below.
+        if let Some(en_passant) = self.en_passant.take() {
+            self.hash ^= zobrist::en_passant_key(en_passant);
+        }
+        self.hash ^= zobrist::castling_key(self.castling.rights);
+
+        // Where the rook ends up, if castling.
+        let mut castled_rook = None;
+
+        match from_piece_type {
+            PieceType::Pawn => {
+                // A pawn moving diagonally onto an empty square is capturing en passant.
+                if !is_capture && from_index % 8 != to_index % 8 {
+                    let captured_index = match side {
+                        Side::White => to_index - 8,
+                        _ => to_index + 8,
+                    };
+
+                    self.set_square(captured_index, side.flip(), None);
+                }
+
+                // Only record the en passant square if an enemy pawn could capture onto it.
+                if from_index.abs_diff(to_index) == 16 {
+                    let en_passant = (from_index + to_index) / 2;
+                    let enemy_pawns = self.board.bitboard_by_piece[PieceType::Pawn.val()]
+                        & self.board.bitboard_by_side[side.flip().val()];
+
+                    if pawn_east_attacks(1 << en_passant, enemy_pawns, side)
+                        | pawn_west_attacks(1 << en_passant, enemy_pawns, side)
+                        != 0
+                    {
+                        self.en_passant = Some(Square::new(en_passant));
+                        self.hash ^= zobrist::en_passant_key(Square::new(en_passant));
+                    }
+                }
+            }
+            PieceType::King => {
+                // Castling, so the king goes to the g-file or c-file and the rook to the other side
+                // of it, wherever they started. The rook is lifted first, as in Chess960 either
+                // piece may land where the other started.
+                if let Some(kind) = castling_kind {
+                    let back_rank = from_index / 8 * 8;
+                    let (right, king_file, rook_file) = match kind {
+                        MoveKind::KingsideCastle => (0, 6, 5),
+                        _ => (1, 2, 3),
+                    };
+
+                    let rook = self.castling.rooks[side.val() * 2 + right];
+                    self.set_square(rook.index(), side, None);
+
+                    to_index = back_rank + king_file;
+                    castled_rook = Some(back_rank + rook_file);
+                }
+            }
+            _ => {}
+        }
+
+        let to_piece_type = piece_move.promote.unwrap_or(from_piece_type);
+
+        self.set_square(from_index, side, None);
+        self.set_square(to_index, side, Some(to_piece_type));
+
+        if let Some(rook_to) = castled_rook {
+            self.set_square(rook_to, side, Some(PieceType::Rook));
+        }
+
+        self.castling.rights &= !rights_lost;
+        self.hash ^= zobrist::castling_key(self.castling.rights);
+
+        if from_piece_type == PieceType::Pawn || is_capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if side == Side::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.side_to_move = side.flip();
+        self.hash ^= zobrist::side_key();
+    }
+
+    /// Makes a move after checking that it is legal, leaving the position unchanged if it isn't.
+    /// Only the move's squares and promotion are checked, so a move which doesn't know what it
+    /// captures, such as one parsed from coordinate notation, is made as the matching legal move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Move, MoveError, Position};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let e5 = Move::from_uci_str("e7e5").unwrap();
+    /// assert_eq!(position.try_make_move(e5), Err(MoveError::WrongSide(e5.from)));
+    ///
+    /// let e3 = Move::from_uci_str("e3e4").unwrap();
+    /// assert_eq!(position.try_make_move(e3), Err(MoveError::EmptySquare(e3.from)));
+    ///
+    /// let e4 = Move::from_uci_str("e2e4").unwrap();
+    /// assert!(position.try_make_move(e4).is_ok());
+    /// assert_eq!(position.try_make_move(e4), Err(MoveError::EmptySquare(e4.from)));
+    ///
+    /// let kd7 = Move::from_uci_str("e8d7").unwrap();
+    /// assert_eq!(position.try_make_move(kd7), Err(MoveError::IllegalMove(kd7)));
+    /// ```
+    pub fn try_make_move(&mut self, piece_move: Move) -> Result<(), MoveError> {
+        match self.piece_at(piece_move.from) {
+            None => return Err(MoveError::EmptySquare(piece_move.from)),
+            Some((_, side)) if side != self.side_to_move => {
+                return Err(MoveError::WrongSide(piece_move.from))
+            }
+            _ => {}
+        }
+
+        let legal_move = self
+            .legal_moves()
+            .into_iter()
+            .find(|legal_move| {
+                legal_move.from == piece_move.from
+                    && legal_move.to == piece_move.to
+                    && legal_move.promote == piece_move.promote
+            })
+            .ok_or(MoveError::IllegalMove(piece_move))?;
+
+        self.make_move(legal_move);
+
+        Ok(())
+    }
+
+    /// Returns a diagram of the board like the one given by [`Display`](std::fmt::Display), but
+    /// with the pieces drawn as Unicode chess figurines rather than letters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let diagram = position.to_unicode_string();
+    /// assert!(diagram.contains("| ♜ | ♞ | ♝ | ♛ | ♚ | ♝ | ♞ | ♜ |"));
+    /// assert!(diagram.contains("| ♖ | ♘ | ♗ | ♕ | ♔ | ♗ | ♘ | ♖ |"));
+    /// ```
+    pub fn to_unicode_string(&self) -> String {
+        BoardRenderer {
+            unicode: true,
+            ..Default::default()
+        }
+        .render(self)
+    }
+
+    /// Generates all pseudo-legal moves for the given side, adding them to `moves`. Some of these
+    /// moves may leave the side's own king in check; use [`Position::legal_moves`] to exclude them.
+    pub fn generate_moves(&self, side: Side, moves: &mut MoveList) {
+        self.board.generate_pawn_moves(side, moves);
+
+        if side == self.side_to_move {
+            self.board
+                .generate_en_passant_moves(side, self.en_passant, moves);
+        }
+
+        self.board.generate_knight_moves(side, moves);
+        self.board.generate_bishop_moves(side, moves);
+        self.board.generate_rook_moves(side, moves);
+        self.board.generate_queen_moves(side, moves);
+        self.board.generate_king_moves(side, moves);
+        self.board
+            .generate_castling_moves(side, &self.castling, moves);
+    }
+
+    /// Generates the pseudo-legal captures and promotions for the side to move, adding them to
+    /// `moves`.
+    pub fn generate_captures(&self, moves: &mut MoveList) {
+        self.board
+            .generate_captures(self.side_to_move, self.en_passant, moves);
+    }
+
+    /// Generates the pseudo-legal moves for the side to move which neither capture nor promote,
+    /// adding them to `moves`.
+    pub fn generate_quiets(&self, moves: &mut MoveList) {
+        self.board
+            .generate_quiets(self.side_to_move, &self.castling, moves);
+    }
+
+    /// Generates the pseudo-legal moves for the side to move which give check without capturing
+    /// or promoting, adding them to `moves`.
+    pub fn generate_quiet_checks(&self, moves: &mut MoveList) {
+        self.board.generate_quiet_checks(self.side_to_move, moves);
+    }
+
+    /// Generates the pseudo-legal moves for the side to move which may get its king out of check,
+    /// adding them to `moves`. Only valid when the side to move is in check, when it replaces the
+    /// other generators.
+    pub fn generate_evasions(&self, moves: &mut MoveList) {
+        self.board
+            .generate_evasions(self.side_to_move, self.en_passant, moves);
+    }
+
+    /// Returns true if the side to move could make the move in the current position, ignoring
+    /// whether it leaves their king in check. Used to check moves which were found in other
+    /// positions, such as from the transposition table, before making them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Position, Move};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let e4 = position
+    ///     .legal_moves()
+    ///     .into_iter()
+    ///     .find(|piece_move| piece_move.to_string() == "e2e4")
+    ///     .unwrap();
+    /// assert!(position.is_pseudo_legal(e4));
+    ///
+    /// position.make_move(e4);
+    /// assert!(!position.is_pseudo_legal(e4));
+    /// assert!(!position.is_pseudo_legal(Move::from_uci_str("e7e4").unwrap()));
+    /// ```
+    pub fn is_pseudo_legal(&self, piece_move: Move) -> bool {
+        let side = self.side_to_move;
+        let mut moves = MoveList::new();
+
+        match self.piece_at(piece_move.from) {
+            Some((_, piece_side)) if piece_side != side => return false,
+            Some((PieceType::Pawn, _)) => {
+                self.board.generate_pawn_moves(side, &mut moves);
+                self.board
+                    .generate_en_passant_moves(side, self.en_passant, &mut moves);
+            }
+            Some((piece_type, _)) => {
+                self.board.generate_piece_moves(
+                    side,
+                    piece_type,
+                    piece_move.to.bitboard(),
+                    &mut moves,
+                );
+
+                if piece_type == PieceType::King {
+                    self.board
+                        .generate_castling_moves(side, &self.castling, &mut moves);
+                }
+            }
+            None => return false,
+        }
+
+        moves.contains(&piece_move)
+    }
+
+    /// Generates all legal moves for the side to move.
+    pub fn legal_moves(&self) -> MoveList {
+        let side = self.side_to_move;
+        let mut moves = MoveList::new();
+
+        let in_check = self.in_check();
+        if in_check {
+            self.generate_evasions(&mut moves);
+        } else {
+            self.generate_moves(side, &mut moves);
+        }
+
+        // Out of check, only moves of the king, of pinned pieces, or capturing en passant (which
+        // takes two pieces off one rank) can expose the king, so the rest need no testing.
+        let pinned = self.board.pinned_pieces(side);
+        let king = self.board.bitboard_by_piece[PieceType::King.val()];
+
+        moves.retain(|piece_move| {
+            let from = piece_move.from.bitboard();
+            let safe = !in_check && (pinned | king) & from == 0 && !piece_move.is_en_passant();
+
+            safe || !self.board.leaves_king_in_check(piece_move, side)
+        });
+
+        moves
+    }
+
+    /// Returns the legal moves of the piece on the given square, such as for a GUI to show when
+    /// the piece is clicked. There are none if the square is empty or the piece can't move, or if
+    /// it belongs to the side not to move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let moves: Vec<String> = engine
+    ///     .legal_moves_from("g1".parse().unwrap())
+    ///     .iter()
+    ///     .map(Move::to_string)
+    ///     .collect();
+    /// assert_eq!(moves, ["g1f3", "g1h3"]);
+    ///
+    /// assert!(engine.legal_moves_from("g8".parse().unwrap()).is_empty());
+    /// ```
+    pub fn legal_moves_from(&self, square: Square) -> Vec<Move> {
+        self.legal_moves()
+            .iter()
+            .filter(|piece_move| piece_move.from == square)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the squares the piece on the given square can legally move to, as a bitboard. A
+    /// pawn which can promote on a square has one move there for each promotion, but the square
+    /// is only set once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Square};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    ///
+    /// let b7: Square = "b7".parse().unwrap();
+    /// let b8: Square = "b8".parse().unwrap();
+    /// assert_eq!(engine.legal_moves_from(b7).len(), 4);
+    /// assert_eq!(engine.legal_destinations(b7), b8.bitboard());
+    /// ```
+    pub fn legal_destinations(&self, square: Square) -> Bitboard {
+        self.legal_moves_from(square)
+            .iter()
+            .fold(0, |destinations, piece_move| {
+                destinations | piece_move.to.bitboard()
+            })
+    }
+
+    /// Finds the legal move a player means by moving a piece from one square to another, such as
+    /// by dragging it in a GUI, so that frontends never need to build moves themselves. Castling
+    /// may be given as the king moving to its destination or onto its own rook, whichever way
+    /// castling moves are written. Returns `None` if there is no such legal move, including when
+    /// a promotion is missing or not needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The square the piece moves from.
+    /// * `to` - The square the piece moves to.
+    /// * `promotion` - The piece a pawn promotes to, if the move is a promotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, PieceType, Square};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// let square = |name: &str| name.parse::<Square>().unwrap();
+    ///
+    /// // The king may be dropped on its destination or on the rook to castle.
+    /// let castle = engine.find_move(square("e1"), square("g1"), None).unwrap();
+    /// assert!(castle.is_castle());
+    /// assert_eq!(engine.find_move(square("e1"), square("h1"), None), Some(castle));
+    ///
+    /// let promotion = engine.find_move(square("b7"), square("b8"), Some(PieceType::Knight));
+    /// assert_eq!(promotion.unwrap().to_string(), "b7b8n");
+    /// assert_eq!(engine.find_move(square("b7"), square("b8"), None), None);
+    /// ```
+    pub fn find_move(
+        &self,
+        from: Square,
+        to: Square,
+        promotion: Option<PieceType>,
+    ) -> Option<Move> {
+        let moves: Vec<Move> = self
+            .legal_moves_from(from)
+            .into_iter()
+            .filter(|legal_move| legal_move.promote == promotion)
+            .collect();
+
+        // A normal move to the square is preferred, as in Chess960 the king may be able to move
+        // to the square it would castle to without castling.
+        moves
+            .iter()
+            .find(|legal_move| legal_move.to == to)
+            .or_else(|| {
+                moves.iter().find(|legal_move| {
+                    legal_move.is_castle() && self.castle_drop_squares(legal_move).contains(&to)
+                })
+            })
+            .copied()
+    }
+
+    // The squares a castling move may be given by: the king's destination and its rook.
+    fn castle_drop_squares(&self, castle: &Move) -> [Square; 2] {
+        let back_rank = castle.from.index() / 8 * 8;
+        let (right, king_file) = match castle.kind {
+            MoveKind::KingsideCastle => (0, 6),
+            _ => (1, 2),
+        };
+
+        let side = self.side_to_move.val();
+        [
+            Square::new(back_rank + king_file),
+            self.castling.rooks[side * 2 + right],
+        ]
+    }
+
+    /// Counts the leaf nodes of the tree of legal moves to the given depth. Comparing the counts
+    /// with known values is the standard way to check that move generation is correct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// assert_eq!(position.perft(1), 20);
+    /// assert_eq!(position.perft(3), 8902);
+    ///
+    /// // A Chess960 position, in which both sides may castle either way.
+    /// position.set_chess960(true);
+    /// position
+    ///     .set_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+    ///     .unwrap();
+    /// assert_eq!(position.perft(3), 12189);
+    /// ```
+    pub fn perft(&self, depth: u32) -> u64 {
+        let moves = self.legal_moves();
+
+        if depth <= 1 {
+            return if depth == 0 { 1 } else { moves.len() as u64 };
+        }
+
+        moves
+            .into_iter()
+            .map(|piece_move| {
+                let mut child = self.clone();
+                child.make_move(piece_move);
+                child.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// Counts the leaf nodes to the given depth as [`Position::perft`] does, but splits the moves
+    /// from this position between the given number of threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// // A position with promotions, castling and discovered checks available.
+    /// let mut position = Position::default();
+    /// position
+    ///     .set_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(position.perft_parallel(3, 4), 62379);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn perft_parallel(&self, depth: u32, threads: usize) -> u64 {
+        let moves = self.legal_moves();
+
+        // Threads can't be spawned in the browser.
+        let threads = if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            1
+        } else {
+            threads.clamp(1, moves.len().max(1))
+        };
+
+        if depth <= 1 || threads == 1 {
+            return self.perft(depth);
+        }
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread| {
+                    let moves = &moves;
+
+                    scope.spawn(move || {
+                        moves
+                            .iter()
+                            .skip(thread)
+                            .step_by(threads)
+                            .map(|&piece_move| {
+                                let mut child = self.clone();
+                                child.make_move(piece_move);
+                                child.perft(depth - 1)
+                            })
+                            .sum::<u64>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum()
+        })
+    }
+
+    /// Returns true if the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        let king = self.board.bitboard_by_piece[PieceType::King.val()]
+            & self.board.bitboard_by_side[self.side_to_move.val()];
+
+        king != 0
+            && self
+                .board
+                .is_square_attacked(king.trailing_zeros() as usize, self.side_to_move.flip())
+    }
+
+    /// Returns true if neither side has enough material left to checkmate the other.
+    pub fn is_insufficient_material(&self) -> bool {
+        let pieces = &self.board.bitboard_by_piece;
+
+        if pieces[PieceType::Pawn.val()]
+            | pieces[PieceType::Rook.val()]
+            | pieces[PieceType::Queen.val()]
+            != 0
+        {
+            return false;
+        }
+
+        let knights = pieces[PieceType::Knight.val()];
+        let bishops = pieces[PieceType::Bishop.val()];
+
+        // A lone minor piece can't force mate, and neither can any number of bishops which are
+        // all on the same colour squares.
+        (knights | bishops).count_ones() <= 1
+            || (knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & DARK_SQUARES == 0))
+    }
+}
+
+// The FEN character for a piece, upper case for white and lower case for black.
+pub(crate) fn char_from_piece(piece_type: PieceType, side: Side) -> char {
+    let char = match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+        PieceType::Count => '?',
+    };
+
+    if side == Side::White {
+        char
+    } else {
+        char.to_ascii_lowercase()
+    }
+}
+
+// The Unicode chess figurine for a piece.
+pub(crate) fn figurine_from_piece(piece_type: PieceType, side: Side) -> char {
+    let (white, black) = match piece_type {
+        PieceType::Pawn => ('♙', '♟'),
+        PieceType::Knight => ('♘', '♞'),
+        PieceType::Bishop => ('♗', '♝'),
+        PieceType::Rook => ('♖', '♜'),
+        PieceType::Queen => ('♕', '♛'),
+        PieceType::King => ('♔', '♚'),
+        PieceType::Count => ('?', '?'),
+    };
+
+    if side == Side::White {
+        white
+    } else {
+        black
+    }
+}
+
+// The type of piece represented by the given FEN character, in either case.
+fn piece_from_char(c: char) -> Option<PieceType> {
+    match c.to_ascii_uppercase() {
+        'P' => Some(PieceType::Pawn),
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Position {
+    /// Writes a diagram of the board from white's side, with white pieces in upper case and black
+    /// pieces in lower case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Position;
+    ///
+    /// let mut position = Position::default();
+    /// position.set_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    ///
+    /// let diagram = position.to_string();
+    /// assert!(diagram.contains("8 |   |   |   |   | k |   |   |   | 8"));
+    /// assert!(diagram.contains("1 |   |   |   |   | K |   |   | R | 1"));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", BoardRenderer::default().render(self))
+    }
+}
+
+impl fmt::Debug for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Position").field(&self.fen()).finish()
+    }
+}
+
+/// Positions are written as FEN, which holds all of their state, including the castling rights
+/// and en passant square.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::Position;
+///
+/// let mut position = Position::default();
+/// position.set_initial_position();
+///
+/// let json = serde_json::to_string(&position).unwrap();
+/// assert_eq!(json, "\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"");
+/// assert_eq!(serde_json::from_str::<Position>(&json).unwrap(), position);
+/// assert!(serde_json::from_str::<Position>("\"8/8 w\"").is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Position {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.fen())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Position {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+
+        let mut position = Self::default();
+        position.set_fen(&fen).map_err(serde::de::Error::custom)?;
+
+        Ok(position)
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            board: Board::new(),
+            side_to_move: Side::White,
+            castling: Castling::new(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            material: [(0, 0); 2],
+            piece_squares: [(0, 0); 2],
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// Returns the next number from an xorshift64* generator, advancing its state, which must not be 0.
+// Only used where moves are picked at random, so it doesn't need to be strong.
+pub(crate) fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+
+    state.wrapping_mul(0x2545F4914F6CDD1D)
+}
@@ -0,0 +1,109 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::position::{char_from_piece, figurine_from_piece};
+use crate::{Move, PieceType, Position, Square};
+
+/// Draws positions as text diagrams with coordinates around the board, for showing in a terminal
+/// or log. Marked squares have their piece drawn in brackets: `[ ]` for the squares of the last
+/// move, and `( )` for a king in check.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{BoardRenderer, Engine, Move};
+///
+/// let mut engine = Engine::default();
+/// engine.set_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+///
+/// let last_move = Move::from_uci_str("h1h8").unwrap();
+/// engine.make_move(last_move);
+///
+/// let renderer = BoardRenderer {
+///     unicode: true,
+///     flipped: true,
+///     last_move: Some(last_move),
+///     highlight_check: true,
+/// };
+///
+/// let diagram = renderer.render(&engine);
+/// assert!(diagram.starts_with("    h   g   f   e   d   c   b   a"));
+/// assert!(diagram.contains("1 |[ ]|   |   | ♔ |   |   |   |   | 1"));
+/// assert!(diagram.contains("8 |[♖]|   |   |(♚)|   |   |   |   | 8"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BoardRenderer {
+    // Draw the pieces as Unicode chess figurines rather than as FEN letters.
+    pub unicode: bool,
+    // Draw the board from black's side, with the eighth rank at the bottom.
+    pub flipped: bool,
+    // The move whose squares are marked, usually the last one played.
+    pub last_move: Option<Move>,
+    // Mark the king of the side to move when it is in check.
+    pub highlight_check: bool,
+}
+
+impl BoardRenderer {
+    /// Returns the diagram of the given position.
+    pub fn render(&self, position: &Position) -> String {
+        let (ranks, files): (Vec<usize>, Vec<usize>) = if self.flipped {
+            ((0..8).collect(), (0..8).rev().collect())
+        } else {
+            ((0..8).rev().collect(), (0..8).collect())
+        };
+
+        let file_names: String = files
+            .iter()
+            .map(|&file| format!("   {}", (b'a' + file as u8) as char))
+            .collect();
+        let border = "  +---+---+---+---+---+---+---+---+\n";
+
+        let checked_king = (self.highlight_check && position.in_check())
+            .then(|| {
+                position.pieces().find(|&(_, piece_type, side)| {
+                    piece_type == PieceType::King && side == position.side_to_move()
+                })
+            })
+            .flatten()
+            .map(|(square, ..)| square);
+
+        let mut out = format!(" {}\n", file_names);
+        out += border;
+
+        for &rank in &ranks {
+            out += &format!("{} |", rank + 1);
+
+            for &file in &files {
+                let square = Square::new(rank * 8 + file);
+
+                let c = match position.piece_at(square) {
+                    Some((piece_type, side)) if self.unicode => {
+                        figurine_from_piece(piece_type, side)
+                    }
+                    Some((piece_type, side)) => char_from_piece(piece_type, side),
+                    None => ' ',
+                };
+
+                let is_last_move = self
+                    .last_move
+                    .is_some_and(|last_move| square == last_move.from || square == last_move.to);
+
+                if checked_king == Some(square) {
+                    out += &format!("({})|", c);
+                } else if is_last_move {
+                    out += &format!("[{}]|", c);
+                } else {
+                    out += &format!(" {} |", c);
+                }
+            }
+
+            out += &format!(" {}\n", rank + 1);
+            out += border;
+        }
+
+        out += &format!(" {}", file_names);
+
+        out
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Move, MoveKind, PieceType, Position, Square};
+
+impl Position {
+    /// Returns a legal move in standard algebraic notation, such as `Nf3`, `exd5`, `O-O` or
+    /// `e8=Q+`, for the current position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let knight_move = Move::from_uci_str("g1f3").unwrap();
+    /// assert_eq!(engine.move_to_san(knight_move), "Nf3");
+    ///
+    /// // Fool's mate.
+    /// for notation in ["f2f3", "e7e5", "g2g4"] {
+    ///     engine.make_move(Move::from_uci_str(notation).unwrap());
+    /// }
+    /// assert_eq!(engine.move_to_san(Move::from_uci_str("d8h4").unwrap()), "Qh4#");
+    /// ```
+    pub fn move_to_san(&self, piece_move: Move) -> String {
+        let (from, to) = (piece_move.from, piece_move.to);
+
+        let Some((piece_type, _)) = self.piece_at(from) else {
+            // Not a move in this position, so the best that can be done is coordinates.
+            return piece_move.to_string();
+        };
+
+        let mut san = String::new();
+
+        if let Some(kind) = self.castling_kind(piece_move) {
+            san += match kind {
+                MoveKind::KingsideCastle => "O-O",
+                _ => "O-O-O",
+            };
+        } else {
+            let is_capture = self.piece_at(to).is_some()
+                || (piece_type == PieceType::Pawn && from.file() != to.file());
+
+            if piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push(from.file().to_char());
+                }
+            } else {
+                san.push(piece_char(piece_type));
+                san += &self.disambiguation(piece_move, piece_type);
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san += &to.to_string();
+
+            if let Some(promote) = piece_move.promote {
+                san.push('=');
+                san.push(piece_char(promote));
+            }
+        }
+
+        let mut after = self.clone();
+        after.make_move(piece_move);
+
+        if after.in_check() {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Finds the legal move in the current position written in standard algebraic notation.
+    /// Check and annotation symbols are optional, and castling may be written with zeros.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// assert_eq!(engine.move_from_san("Nf3"), Move::from_uci_str("g1f3"));
+    /// assert_eq!(engine.move_from_san("e4!?").map(|e4| e4.to), "e4".parse().ok());
+    /// assert_eq!(engine.move_from_san("Nf6"), None);
+    /// ```
+    pub fn move_from_san(&self, san: &str) -> Option<Move> {
+        let san = san.replace('0', "O");
+        let wanted = strip_suffixes(&san);
+
+        self.legal_moves()
+            .into_iter()
+            .find(|legal_move| strip_suffixes(&self.move_to_san(*legal_move)) == wanted)
+    }
+
+    // Returns what is needed to tell a piece move apart from the same type of piece moving to the
+    // same square: the origin file if that is enough, otherwise the rank, otherwise both.
+    fn disambiguation(&self, piece_move: Move, piece_type: PieceType) -> String {
+        let from = piece_move.from;
+
+        let others: Vec<Square> = self
+            .legal_moves()
+            .iter()
+            .filter(|other| other.to == piece_move.to && other.from != from)
+            .map(|other| other.from)
+            .filter(|&other_from| {
+                self.piece_at(other_from)
+                    .is_some_and(|(other_type, _)| other_type == piece_type)
+            })
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.file() != from.file()) {
+            from.file().to_string()
+        } else if others.iter().all(|other| other.rank() != from.rank()) {
+            from.rank().to_string()
+        } else {
+            from.to_string()
+        }
+    }
+}
+
+// Removes any check, mate or annotation symbols from the end of a move.
+fn strip_suffixes(san: &str) -> &str {
+    san.trim_end_matches(['+', '#', '!', '?'])
+}
+
+fn piece_char(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+        _ => 'P',
+    }
+}
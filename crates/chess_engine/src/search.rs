@@ -0,0 +1,1988 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::random::next_random;
+use crate::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::{
+    Bound, Engine, EvalCache, EvalCacheStats, InfoCallback, LogCallback, Move, MoveList,
+    MoveOrdering, MovePicker, PawnTable, Position, SearchInfo, SearchTrace, Side, TimeLimits,
+    TimeManager, TraceOutcome, TranspositionTable, TtStats, MAX_PLY,
+};
+
+// Scores are in centipawns from the perspective of the side to move.
+pub const INFINITY: i32 = 32000;
+pub const MATE_SCORE: i32 = 31000;
+
+pub const MAX_DEPTH: u32 = 64;
+
+/// The highest skill level, at which the engine plays at full strength. Lower levels are set with
+/// `SearchConfig::skill_level`.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, Search, SearchLimits};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// let mut search = Search::new();
+/// search.config.skill_level = 0;
+///
+/// // The weakest level only looks one move ahead, but always plays the same move in the same
+/// // position.
+/// let result = search.best_move(&engine, &SearchLimits::default());
+/// assert_eq!(result.depth, 1);
+/// assert_eq!(search.best_move(&engine, &SearchLimits::default()).best_move, result.best_move);
+/// ```
+pub const MAX_SKILL_LEVEL: u32 = 20;
+
+// Below the highest skill level, the noise added to root move scores grows by this many
+// centipawns for each level.
+const SKILL_NOISE_PER_LEVEL: i32 = 15;
+
+// Late move reductions are applied to quiet moves from this depth, after this many moves have
+// been searched at full depth.
+const LMR_MIN_DEPTH: u32 = 3;
+const LMR_MIN_MOVES: usize = 3;
+
+// Late move pruning skips quiet moves up to this depth.
+const LMP_MAX_DEPTH: u32 = 3;
+
+// Aspiration windows are used from this depth, starting this many centipawns either side of the
+// previous iteration's score.
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 25;
+
+// In deterministic mode, time limits are converted to node limits assuming this search speed.
+const DETERMINISTIC_NODES_PER_MS: u64 = 1000;
+
+// Scores beyond this are mate scores. Mates are scored as `MATE_SCORE` less the number of plies
+// to mate, so that faster mates score higher.
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+/// A search score in the form reported over UCI.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Score {
+    Centipawns(i32),
+    // Mate in the given number of moves (not plies). Negative if the side to move is being mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// Converts a score from the search into centipawns or a distance to mate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Score, MATE_SCORE};
+    ///
+    /// assert_eq!(Score::from_search_score(35), Score::Centipawns(35));
+    ///
+    /// // Mating in 3 plies is mate in 2 moves.
+    /// assert_eq!(Score::from_search_score(MATE_SCORE - 3), Score::Mate(2));
+    /// assert_eq!(Score::from_search_score(-MATE_SCORE + 2), Score::Mate(-1));
+    /// ```
+    pub fn from_search_score(score: i32) -> Score {
+        if score >= MATE_BOUND {
+            Score::Mate((MATE_SCORE - score + 1) / 2)
+        } else if score <= -MATE_BOUND {
+            Score::Mate(-(MATE_SCORE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+// Mate scores are relative to the root when searching, but must be stored in the transposition
+// table relative to the position itself, since it may be reached at a different ply.
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Constraints on how far the search may go. With no limits at all, the search runs until it reaches
+/// `MAX_DEPTH` or is stopped.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, Move, MoveKind, Search, SearchLimits, Square};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// // Only consider h2h3, whatever else might be better.
+/// let h2h3 = Move {
+///     from: Square::new(15),
+///     to: Square::new(23),
+///     promote: None,
+///     captured: None,
+///     kind: MoveKind::Normal,
+/// };
+///
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     nodes: Some(100_000),
+///     search_moves: vec![h2h3],
+///     ..Default::default()
+/// };
+///
+/// let result = Search::new().best_move(&engine, &limits);
+/// assert_eq!(result.best_move, Some(h2h3));
+/// ```
+///
+/// Searching for a mate finds the shortest one within the limit, along with the mating line.
+///
+/// ```
+/// use chess_engine::{Engine, Score, Search, SearchLimits};
+///
+/// let mut engine = Engine::default();
+/// engine
+///     .set_fen("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 10")
+///     .unwrap();
+///
+/// let limits = SearchLimits {
+///     mate: Some(3),
+///     ..Default::default()
+/// };
+///
+/// let result = Search::new().best_move(&engine, &limits);
+/// assert_eq!(Score::from_search_score(result.score), Score::Mate(2));
+///
+/// let pv: Vec<String> = result.pv.iter().map(|piece_move| piece_move.to_string()).collect();
+/// assert_eq!(pv, ["d5f6", "g7f6", "c4f7"]);
+/// ```
+///
+/// A mate search which is stopped before it finds a mate still returns a move to play.
+///
+/// ```
+/// use std::sync::atomic::Ordering;
+/// use chess_engine::{Engine, Search, SearchLimits};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// let limits = SearchLimits {
+///     mate: Some(6),
+///     ..Default::default()
+/// };
+///
+/// let mut search = Search::new();
+/// search.stop_flag().store(true, Ordering::Relaxed);
+///
+/// let result = search.best_move(&engine, &limits);
+/// assert!(result.best_move.is_some());
+/// assert_eq!(result.depth, 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    // The maximum depth, in plies, to search to.
+    pub depth: Option<u32>,
+    // The maximum number of nodes to search.
+    pub nodes: Option<u64>,
+    // Look for a forced mate in at most this many moves. If there is none, the best move found
+    // by a normal search that deep is returned instead.
+    pub mate: Option<u32>,
+    pub time: TimeLimits,
+    // Ignore the time limits, and don't return until stopped.
+    pub infinite: bool,
+    // Only consider these moves at the root, if any are given.
+    pub search_moves: Vec<Move>,
+}
+
+impl SearchLimits {
+    /// Returns true if nothing would stop the search before `MAX_DEPTH`.
+    pub fn is_unlimited(&self) -> bool {
+        self.depth.is_none() && self.nodes.is_none() && self.mate.is_none() && self.time.is_empty()
+    }
+}
+
+/// Counts of what happened during a search, for diagnosing and tuning it.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Position, Search, SearchLimits};
+///
+/// let mut position = Position::default();
+/// position.set_initial_position();
+///
+/// let limits = SearchLimits {
+///     depth: Some(4),
+///     ..Default::default()
+/// };
+/// let result = Search::new().best_move(&position, &limits);
+///
+/// assert_eq!(result.stats.nodes, result.nodes);
+/// assert!(result.stats.qnodes < result.stats.nodes);
+/// assert!(result.stats.first_move_cutoffs <= result.stats.beta_cutoffs);
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct SearchStats {
+    // Every position searched, including those in the quiescence search.
+    pub nodes: u64,
+    // The positions searched by the quiescence search.
+    pub qnodes: u64,
+    // The greatest ply reached, including the quiescence search.
+    pub seldepth: u32,
+    // Positions whose score was taken from the transposition table rather than searched.
+    pub tt_cutoffs: u64,
+    // Positions where a move scored at least beta, and how many of those it was the first move
+    // tried. The higher the proportion, the better the move ordering.
+    pub beta_cutoffs: u64,
+    pub first_move_cutoffs: u64,
+}
+
+impl SearchStats {
+    /// The fraction of beta cutoffs caused by the first move searched, from 0 to 1.
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        self.first_move_cutoffs as f64 / self.beta_cutoffs.max(1) as f64
+    }
+}
+
+/// The outcome of a search.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    // The best move found, or `None` if the position has no legal moves.
+    pub best_move: Option<Move>,
+    // The expected reply to the best move, taken from the principal variation.
+    pub ponder_move: Option<Move>,
+    pub score: i32,
+    // The principal variation, starting with the best move.
+    pub pv: Vec<Move>,
+    // The depth of the last completed iteration.
+    pub depth: u32,
+    pub nodes: u64,
+    pub stats: SearchStats,
+}
+
+/// Settings controlling how the search plays: which extensions are used, how strongly it plays,
+/// and how it values draws.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, Search, SearchLimits};
+///
+/// // With only the kings left, every line is a draw.
+/// let mut engine = Engine::default();
+/// engine.set_fen("8/8/4k3/8/8/3K4/8/8 w - - 0 1").unwrap();
+///
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     ..Default::default()
+/// };
+///
+/// let mut search = Search::new();
+/// assert_eq!(search.best_move(&engine, &limits).score, 0);
+///
+/// // With contempt, the engine thinks a draw is worse for itself than an equal position.
+/// search.config.contempt = 20;
+/// assert_eq!(search.best_move(&engine, &limits).score, -20);
+/// ```
+///
+/// Early in the game, moves can be picked at random to vary the openings played, but only from
+/// those which score close to the best.
+///
+/// ```
+/// use chess_engine::{Engine, Search, SearchLimits};
+///
+/// // Taking the queen is far better than any other move.
+/// let mut engine = Engine::default();
+/// engine.set_fen("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1").unwrap();
+///
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     ..Default::default()
+/// };
+///
+/// let mut search = Search::new();
+/// search.config.random_plies = 10;
+/// search.config.random_margin = 50;
+///
+/// for _ in 0..5 {
+///     assert_eq!(search.best_move(&engine, &limits).best_move.unwrap().to_string(), "d1d5");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    // Extend the search by one ply when in check.
+    pub check_extensions: bool,
+
+    // Extend the search of the transposition table move when every alternative is much worse.
+    pub singular_extensions: bool,
+    // The minimum depth at which a move is tested for singularity.
+    pub singular_min_depth: u32,
+    // How far below the transposition table score, per ply of depth, the alternatives must all
+    // fail for the move to be considered singular.
+    pub singular_margin: i32,
+
+    // The maximum number of extensions along any one line, to prevent the search exploding.
+    pub max_extensions: u32,
+
+    // How many plies into the quiescence search quiet moves which give check are searched as
+    // well as captures. 0 searches captures only.
+    pub quiescence_checks: u32,
+
+    // How well to play, from 0 to `MAX_SKILL_LEVEL`. Lower levels search less deeply, and choose
+    // between the root moves with noise added to their scores.
+    pub skill_level: u32,
+
+    // How many centipawns the side to move at the root considers a draw to be worth less than
+    // an equal position. Positive values avoid draws, and negative values seek them.
+    pub contempt: i32,
+
+    // Make every search independent of earlier ones and of the clock, so that the same position
+    // and limits always give the same result. Time limits become a fixed budget of nodes.
+    pub deterministic: bool,
+
+    // For this many plies from the start of the game, play a random root move from those scoring
+    // within `random_margin` centipawns of the best, to vary the openings played. 0 disables it,
+    // as does deterministic mode.
+    pub random_plies: u32,
+    pub random_margin: i32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            check_extensions: true,
+            singular_extensions: true,
+            singular_min_depth: 6,
+            singular_margin: 2,
+            max_extensions: 16,
+            quiescence_checks: 0,
+            skill_level: MAX_SKILL_LEVEL,
+            contempt: 0,
+            deterministic: false,
+            random_plies: 0,
+            random_margin: 20,
+        }
+    }
+}
+
+pub struct Search {
+    pub config: SearchConfig,
+
+    stats: SearchStats,
+    ordering: MoveOrdering,
+    tt: TranspositionTable,
+    pawn_table: PawnTable,
+    eval_cache: EvalCache,
+
+    // Told about the progress of the search after every iteration.
+    info_callback: Option<Box<dyn InfoCallback + Send>>,
+    // Told why the search does what it does, for diagnosing problems.
+    debug_callback: Option<Box<dyn LogCallback + Send>>,
+    start_time: Instant,
+
+    time_manager: TimeManager,
+    // Shared with other threads, so the search can be stopped from outside.
+    stop_flag: Arc<AtomicBool>,
+    // Shared with other threads. Set while pondering, and cleared on a ponder hit.
+    ponder_flag: Arc<AtomicBool>,
+    // Whether the time limits are currently being ignored because the search is pondering.
+    pondering: bool,
+    // The node limit and root moves of the current search.
+    node_limit: Option<u64>,
+    search_moves: Vec<Move>,
+    // The depth of the current iteration.
+    root_depth: u32,
+    // Set once the search has run out of time, after which all results are discarded.
+    stopped: bool,
+    // The hashes of the positions from the start of the game down to the parent of the current
+    // one, used to detect repetitions.
+    path: Vec<u64>,
+    // The state of the generator used to pick between root moves in the opening.
+    random_state: u64,
+    // Records the tree explored, while tracing is on.
+    trace: Option<SearchTrace>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self {
+            config: SearchConfig::default(),
+            stats: SearchStats::default(),
+            ordering: MoveOrdering::new(),
+            tt: TranspositionTable::default(),
+            pawn_table: PawnTable::new(),
+            eval_cache: EvalCache::new(),
+            info_callback: None,
+            debug_callback: None,
+            start_time: Instant::now(),
+            time_manager: TimeManager::unlimited(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            ponder_flag: Arc::new(AtomicBool::new(false)),
+            pondering: false,
+            node_limit: None,
+            search_moves: vec![],
+            root_depth: 0,
+            stopped: false,
+            path: vec![],
+            // Only used to vary the moves played, so the time is a good enough seed.
+            random_state: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64)
+                | 1,
+            trace: None,
+        }
+    }
+
+    /// Forgets everything learnt from previous searches, so that nothing carries over from one
+    /// game to the next.
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+        self.pawn_table.clear();
+        self.eval_cache.clear();
+        self.ordering.clear();
+    }
+
+    /// Replaces the transposition table with an empty one of the given size, in megabytes.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.tt = TranspositionTable::new(size_mb);
+    }
+
+    /// Returns how full the transposition table is, and how well it served the last search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Position, Search, SearchLimits};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let mut search = Search::new();
+    /// search.best_move(&position, &SearchLimits { depth: Some(4), ..Default::default() });
+    ///
+    /// let stats = search.tt_stats();
+    /// assert!(stats.probes > 0 && stats.hits <= stats.probes);
+    /// assert!(stats.hashfull <= 1000);
+    /// ```
+    pub fn tt_stats(&self) -> TtStats {
+        self.tt.stats()
+    }
+
+    /// Returns how well the evaluation cache served the last search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Position, Search, SearchLimits};
+    ///
+    /// let mut position = Position::default();
+    /// position.set_initial_position();
+    ///
+    /// let mut search = Search::new();
+    /// search.best_move(&position, &SearchLimits { depth: Some(4), ..Default::default() });
+    ///
+    /// let stats = search.eval_cache_stats();
+    /// assert!(stats.hits > 0 && stats.hits < stats.probes);
+    /// ```
+    pub fn eval_cache_stats(&self) -> EvalCacheStats {
+        self.eval_cache.stats()
+    }
+
+    /// Sets a callback which is given the depth, score, principal variation and so on after every
+    /// completed iteration, so that progress can be reported while searching.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    /// use chess_engine::{Engine, Search, SearchInfo, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    ///
+    /// let mut search = Search::new();
+    /// search.set_info_callback(move |info: &SearchInfo| sender.send(info.depth).unwrap());
+    ///
+    /// let limits = SearchLimits {
+    ///     depth: Some(3),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// search.best_move(&engine, &limits);
+    /// assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    /// ```
+    pub fn set_info_callback(&mut self, callback: impl InfoCallback + Send + 'static) {
+        self.info_callback = Some(Box::new(callback));
+    }
+
+    /// Sets a callback which is told about the decisions the search makes: the time it allots
+    /// itself, aspiration window re-searches, transposition table hits, why it stops, and so on.
+    /// The messages are meant for people diagnosing problems, and their wording may change.
+    ///
+    /// With the `tracing` feature, the same messages are also emitted as `tracing` events at the
+    /// debug level, whether or not a callback is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    /// use chess_engine::{Engine, Search, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    ///
+    /// let mut search = Search::new();
+    /// search.set_debug_callback(move |message: &str| sender.send(message.to_string()).unwrap());
+    ///
+    /// let limits = SearchLimits {
+    ///     depth: Some(3),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// search.best_move(&engine, &limits);
+    /// assert!(receiver.try_iter().any(|message| message.starts_with("depth 3 done")));
+    /// ```
+    pub fn set_debug_callback(&mut self, callback: impl LogCallback + Send + 'static) {
+        self.debug_callback = Some(Box::new(callback));
+    }
+
+    /// Removes the debug callback, so that the search's decisions are no longer reported.
+    pub fn clear_debug_callback(&mut self) {
+        self.debug_callback = None;
+    }
+
+    /// Starts recording the tree explored by the searches which follow, keeping at most
+    /// `max_nodes` nodes of each iteration. See `SearchTrace`. Tracing slows the search, so it
+    /// is best kept for small searches.
+    pub fn start_trace(&mut self, max_nodes: usize) {
+        self.trace = Some(SearchTrace::new(max_nodes));
+    }
+
+    /// Stops tracing, and returns the tree explored by the last iteration searched since it was
+    /// started, if it was.
+    pub fn take_trace(&mut self) -> Option<SearchTrace> {
+        self.trace.take()
+    }
+
+    /// Returns a flag which stops the search once set, so a search running on another thread can
+    /// be interrupted. The search then returns the best move from the last complete iteration.
+    ///
+    /// The flag is not cleared automatically, so it must be reset before starting a new search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::Ordering;
+    /// use chess_engine::{Engine, Search, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let mut search = Search::new();
+    /// let stop_flag = search.stop_flag();
+    ///
+    /// let handle = std::thread::spawn(move || {
+    ///     search.best_move(&engine, &SearchLimits::default())
+    /// });
+    ///
+    /// std::thread::sleep(std::time::Duration::from_millis(50));
+    /// stop_flag.store(true, Ordering::Relaxed);
+    ///
+    /// assert!(handle.join().unwrap().best_move.is_some());
+    /// ```
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop_flag.clone()
+    }
+
+    /// Returns a flag which puts the search into ponder mode while set. It must be set before the
+    /// search starts, which then ignores its time limits until the flag is cleared on a ponder
+    /// hit, at which point the clock starts and the search continues as a normal timed search.
+    ///
+    /// UCI doesn't allow a best move to be sent while pondering, so a search which finishes
+    /// early waits for the flag to be cleared, or for the search to be stopped, before returning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::Ordering;
+    /// use std::time::Duration;
+    /// use chess_engine::{Engine, Search, SearchLimits, TimeLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let mut search = Search::new();
+    /// let ponder_flag = search.ponder_flag();
+    /// ponder_flag.store(true, Ordering::Relaxed);
+    ///
+    /// let handle = std::thread::spawn(move || {
+    ///     let limits = SearchLimits {
+    ///         time: TimeLimits {
+    ///             move_time: Some(Duration::from_millis(50)),
+    ///             ..Default::default()
+    ///         },
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     search.best_move(&engine, &limits)
+    /// });
+    ///
+    /// // The move time only starts counting once the predicted move is played.
+    /// std::thread::sleep(Duration::from_millis(100));
+    /// assert!(!handle.is_finished());
+    ///
+    /// ponder_flag.store(false, Ordering::Relaxed);
+    /// assert!(handle.join().unwrap().best_move.is_some());
+    /// ```
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        self.ponder_flag.clone()
+    }
+
+    /// Searches the given position using iterative deepening, returning the best move found along
+    /// with its score and principal variation. The moves which led to the position are not known,
+    /// so only repetitions within the search are seen. Use `best_move_in_game` to see those of
+    /// the game as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position to search, with the side to search for to move.
+    /// * `limits` - The constraints to search within.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Search, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let limits = SearchLimits {
+    ///     depth: Some(2),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let result = Search::new().best_move(&engine, &limits);
+    /// assert!(result.best_move.is_some());
+    /// assert_eq!(result.pv.len(), 2);
+    /// assert_eq!(result.ponder_move, Some(result.pv[1]));
+    /// ```
+    ///
+    /// In deterministic mode, a search with a time limit is repeatable, however fast the machine
+    /// and whatever was searched before.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use chess_engine::{Engine, Search, SearchLimits, TimeLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_initial_position();
+    ///
+    /// let limits = SearchLimits {
+    ///     time: TimeLimits {
+    ///         move_time: Some(Duration::from_millis(20)),
+    ///         ..Default::default()
+    ///     },
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut search = Search::new();
+    /// search.config.deterministic = true;
+    ///
+    /// let first = search.best_move(&engine, &limits);
+    /// let second = search.best_move(&engine, &limits);
+    /// assert_eq!(first.best_move, second.best_move);
+    /// assert_eq!(first.nodes, second.nodes);
+    /// ```
+    pub fn best_move(&mut self, position: &Position, limits: &SearchLimits) -> SearchResult {
+        self.search_root(position, &[], limits)
+    }
+
+    /// Searches the current position of a game, as `best_move` does, treating any return to a
+    /// position from earlier in the game as a draw.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - The game to search, with the side to search for to move.
+    /// * `limits` - The constraints to search within.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Engine, Move, Search, SearchLimits};
+    ///
+    /// let mut engine = Engine::default();
+    /// engine.set_fen("2q1k3/8/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+    ///
+    /// for notation in ["g1h1", "c8d8", "h1g1", "d8c8"] {
+    ///     let piece_move = Move::from_uci_str(notation).unwrap();
+    ///     engine.try_make_move(piece_move).unwrap();
+    /// }
+    ///
+    /// let limits = SearchLimits {
+    ///     depth: Some(3),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// // A queen down, White can only save the game by repeating the position.
+    /// assert!(Search::new().best_move(&engine, &limits).score < -500);
+    ///
+    /// let result = Search::new().best_move_in_game(&engine, &limits);
+    /// assert_eq!(result.best_move.unwrap().to_string(), "g1h1");
+    /// assert_eq!(result.score, 0);
+    /// ```
+    pub fn best_move_in_game(&mut self, engine: &Engine, limits: &SearchLimits) -> SearchResult {
+        self.search_root(engine.position(), engine.history(), limits)
+    }
+
+    // Searches a position reached through the positions with the given hashes, oldest first.
+    fn search_root(
+        &mut self,
+        position: &Position,
+        history: &[u64],
+        limits: &SearchLimits,
+    ) -> SearchResult {
+        self.stats = SearchStats::default();
+        self.start_time = Instant::now();
+
+        if self.config.deterministic {
+            self.new_game();
+        }
+
+        self.ordering.new_search();
+        self.tt.new_search();
+        self.eval_cache.new_search();
+
+        let time_manager = if limits.infinite {
+            TimeManager::unlimited()
+        } else {
+            TimeManager::new(&limits.time, position.side_to_move())
+        };
+
+        // The clock is never looked at in deterministic mode, where the time the search would
+        // have aimed to use is turned into a number of nodes instead.
+        if self.config.deterministic {
+            let time_nodes = time_manager
+                .soft_limit()
+                .map(|soft_limit| soft_limit.as_millis() as u64 * DETERMINISTIC_NODES_PER_MS);
+
+            self.node_limit = limits.nodes.into_iter().chain(time_nodes).min();
+            self.time_manager = TimeManager::unlimited();
+        } else {
+            self.node_limit = limits.nodes;
+            self.time_manager = time_manager;
+        }
+
+        self.search_moves = limits.search_moves.clone();
+        self.pondering = self.ponder_flag.load(Ordering::Relaxed);
+        self.stopped = false;
+        self.path.clear();
+        self.path.extend_from_slice(history);
+
+        let time_manager = &self.time_manager;
+        let (soft_limit, hard_limit) = (time_manager.soft_limit(), time_manager.hard_limit());
+        self.debug(|| match (soft_limit, hard_limit) {
+            (Some(soft), Some(hard)) => format!(
+                "time allotted: soft limit {}ms, hard limit {}ms",
+                soft.as_millis(),
+                hard.as_millis()
+            ),
+            _ => "time allotted: unlimited".to_string(),
+        });
+
+        let max_depth = limits
+            .depth
+            .or(limits.mate.map(|moves| (2 * moves).saturating_sub(1)))
+            .unwrap_or(MAX_DEPTH)
+            .min(self.skill_depth());
+
+        let mut result = match limits
+            .mate
+            .and_then(|moves| self.mate_search(position, moves))
+        {
+            Some(result) => result,
+            // A stopped mate search has no move to play, so search a single ply for one.
+            None if self.stopped => {
+                self.stopped = false;
+                self.iterative_deepening(position, 1)
+            }
+            None => self.iterative_deepening(position, max_depth),
+        };
+
+        if self.config.skill_level < MAX_SKILL_LEVEL {
+            result = self.weakened_result(position, result);
+        } else if self.in_random_opening(position) {
+            result = self.randomized_result(position, result);
+        }
+
+        // The result can't be used until the ponder move has been played or the search stopped,
+        // and an infinite search only ends when stopped.
+        while (limits.infinite || self.ponder_flag.load(Ordering::Relaxed))
+            && !self.stop_flag.load(Ordering::Relaxed)
+        {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        result
+    }
+
+    // Searches one ply deeper at a time, up to the given depth, until the search is stopped or
+    // runs out of time, and returns the result of the last complete iteration.
+    fn iterative_deepening(&mut self, position: &Position, max_depth: u32) -> SearchResult {
+        let mut result = SearchResult {
+            best_move: None,
+            ponder_move: None,
+            score: 0,
+            pv: vec![],
+            depth: 0,
+            nodes: 0,
+            stats: SearchStats::default(),
+        };
+
+        for depth in 1..=max_depth.clamp(1, MAX_DEPTH) {
+            self.root_depth = depth;
+
+            let (score, pv) = self.aspiration_search(position, depth, result.score, &result.pv);
+
+            // An interrupted iteration can't be trusted, so keep the last complete one.
+            if self.stopped {
+                let (nodes, elapsed) = (self.stats.nodes, self.start_time.elapsed());
+                self.debug(|| {
+                    format!(
+                        "depth {} abandoned after {}ms and {} nodes",
+                        depth,
+                        elapsed.as_millis(),
+                        nodes
+                    )
+                });
+                break;
+            }
+
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                ponder_move: pv.get(1).copied(),
+                score,
+                pv,
+                depth,
+                nodes: self.stats.nodes,
+                stats: self.stats,
+            };
+
+            self.report_info(&result);
+            self.check_ponder_hit();
+
+            let tt_stats = self.tt.stats();
+            let best_move = result.best_move;
+            self.debug(|| {
+                format!(
+                    "depth {} done: best move {}, score {}, tt hits {}/{} ({:.1}%)",
+                    depth,
+                    best_move.map_or("none".to_string(), |piece_move| piece_move.to_string()),
+                    score,
+                    tt_stats.hits,
+                    tt_stats.probes,
+                    tt_stats.hit_rate() * 100.0
+                )
+            });
+
+            let eval_cache_stats = self.eval_cache.stats();
+            self.debug(|| {
+                format!(
+                    "depth {} eval cache hits {}/{} ({:.1}%)",
+                    depth,
+                    eval_cache_stats.hits,
+                    eval_cache_stats.probes,
+                    eval_cache_stats.hit_rate() * 100.0
+                )
+            });
+
+            let stats = self.stats;
+            self.debug(|| {
+                format!(
+                    "depth {} stats: {} nodes, {} qnodes, seldepth {}, {} tt cutoffs, {} beta \
+                     cutoffs ({:.1}% on the first move)",
+                    depth,
+                    stats.nodes,
+                    stats.qnodes,
+                    stats.seldepth,
+                    stats.tt_cutoffs,
+                    stats.beta_cutoffs,
+                    stats.first_move_cutoff_rate() * 100.0
+                )
+            });
+
+            // There is nothing to search if the game is already over, and no point starting an
+            // iteration which is unlikely to finish in time.
+            if result.best_move.is_none() {
+                break;
+            }
+
+            if !self.pondering && self.time_manager.soft_limit_reached() {
+                let elapsed = self.time_manager.elapsed();
+                self.debug(|| {
+                    format!(
+                        "soft time limit reached after {}ms, not starting depth {}",
+                        elapsed.as_millis(),
+                        depth + 1
+                    )
+                });
+                break;
+            }
+        }
+
+        result
+    }
+
+    // Looks for a forced mate in at most the given number of moves, trying shorter mates first,
+    // and returns the mating line if there is one. Nothing is pruned, so no mate is missed unless
+    // the search is stopped first, but the cost grows quickly with the number of moves.
+    fn mate_search(&mut self, position: &Position, moves: u32) -> Option<SearchResult> {
+        for moves_left in 1..=moves.min(MAX_DEPTH.div_ceil(2)) {
+            self.root_depth = 2 * moves_left - 1;
+
+            let mut pv = vec![];
+            let found = self.attack_mate(position, moves_left, 0, &mut pv);
+
+            if self.stopped {
+                return None;
+            }
+
+            if found {
+                let result = SearchResult {
+                    best_move: pv.first().copied(),
+                    ponder_move: pv.get(1).copied(),
+                    score: MATE_SCORE - self.root_depth as i32,
+                    pv,
+                    depth: self.root_depth,
+                    nodes: self.stats.nodes,
+                    stats: self.stats,
+                };
+
+                self.report_info(&result);
+
+                return Some(result);
+            }
+
+            self.debug(|| format!("no mate in {}", moves_left));
+        }
+
+        None
+    }
+
+    // Whether the side to move can mate within `moves_left` moves however the other side
+    // defends, in which case the mating line is written to `pv`.
+    fn attack_mate(
+        &mut self,
+        position: &Position,
+        moves_left: u32,
+        ply: u32,
+        pv: &mut Vec<Move>,
+    ) -> bool {
+        if self.should_stop() {
+            return false;
+        }
+
+        self.stats.nodes += 1;
+        self.stats.seldepth = self.stats.seldepth.max(ply);
+
+        // Try checks first, since mates usually come from them. On the last move, nothing else
+        // can mate.
+        let mut children: Vec<(Move, Position)> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|piece_move| {
+                ply > 0 || self.search_moves.is_empty() || self.is_search_move(piece_move)
+            })
+            .map(|piece_move| {
+                let mut child = position.clone();
+                child.make_move(piece_move);
+                (piece_move, child)
+            })
+            .collect();
+
+        children.sort_by_key(|(_, child)| !child.in_check());
+
+        for (piece_move, child) in children {
+            if !child.in_check() && moves_left == 1 {
+                break;
+            }
+
+            let mut child_pv = vec![];
+
+            let mates = if child.legal_moves().is_empty() {
+                child.in_check()
+            } else {
+                moves_left > 1 && self.defend_mate(&child, moves_left - 1, ply + 1, &mut child_pv)
+            };
+
+            if mates {
+                pv.clear();
+                pv.push(piece_move);
+                pv.append(&mut child_pv);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Whether every defence against the other side's attack is mated within `moves_left` more of
+    // its moves, in which case the line resisting longest is written to `pv`.
+    fn defend_mate(
+        &mut self,
+        position: &Position,
+        moves_left: u32,
+        ply: u32,
+        pv: &mut Vec<Move>,
+    ) -> bool {
+        self.stats.nodes += 1;
+        self.stats.seldepth = self.stats.seldepth.max(ply);
+
+        let mut longest: Option<Vec<Move>> = None;
+
+        for piece_move in position.legal_moves() {
+            let mut child = position.clone();
+            child.make_move(piece_move);
+
+            let mut child_pv = vec![];
+
+            if !self.attack_mate(&child, moves_left, ply + 1, &mut child_pv) {
+                return false;
+            }
+
+            if longest
+                .as_ref()
+                .is_none_or(|line| child_pv.len() + 1 > line.len())
+            {
+                child_pv.insert(0, piece_move);
+                longest = Some(child_pv);
+            }
+        }
+
+        match longest {
+            Some(line) => {
+                *pv = line;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The deepest iteration searched at the current skill level.
+    fn skill_depth(&self) -> u32 {
+        if self.config.skill_level >= MAX_SKILL_LEVEL {
+            MAX_DEPTH
+        } else {
+            1 + self.config.skill_level / 2
+        }
+    }
+
+    // Searches each root move again, one ply shallower than the completed search, returning the
+    // score and principal variation of each. Returns `None` if the search is stopped before the
+    // moves are all scored.
+    fn score_root_moves(
+        &mut self,
+        position: &Position,
+        depth: u32,
+    ) -> Option<Vec<(i32, Vec<Move>)>> {
+        let mut scored_moves = vec![];
+
+        // As in the main search, a line returning to the root is a repetition.
+        self.path.push(position.hash());
+
+        for piece_move in position.legal_moves() {
+            if !self.search_moves.is_empty() && !self.is_search_move(&piece_move) {
+                continue;
+            }
+
+            let mut child = position.clone();
+            child.make_move(piece_move);
+
+            let mut child_pv = vec![];
+            let score = -self.negamax(
+                &child,
+                depth.saturating_sub(1),
+                1,
+                -INFINITY,
+                INFINITY,
+                &[],
+                &mut child_pv,
+                None,
+                0,
+            );
+
+            if self.stopped {
+                break;
+            }
+
+            let mut pv = vec![piece_move];
+            pv.append(&mut child_pv);
+            scored_moves.push((score, pv));
+        }
+
+        self.path.pop();
+
+        (!self.stopped).then_some(scored_moves)
+    }
+
+    // Rescores the root moves and plays the one whose score is best once noise has been added.
+    // The noise comes from the position and the move rather than a random number generator, so
+    // the same position always gets the same move. The search result is kept if the search is
+    // stopped before the moves are all scored.
+    fn weakened_result(&mut self, position: &Position, result: SearchResult) -> SearchResult {
+        let noise_range =
+            (MAX_SKILL_LEVEL - self.config.skill_level) as i32 * SKILL_NOISE_PER_LEVEL;
+
+        let Some(scored_moves) = self.score_root_moves(position, result.depth) else {
+            return result;
+        };
+
+        let best = scored_moves
+            .into_iter()
+            .map(|(score, pv)| {
+                let noisy_score = score + skill_noise(position.hash(), pv[0], noise_range);
+                (noisy_score, score, pv)
+            })
+            .reduce(|best, scored| if scored.0 > best.0 { scored } else { best });
+
+        if let Some((noisy_score, score, pv)) = &best {
+            let (skill_level, searched_move) = (self.config.skill_level, result.best_move);
+            let (chosen_move, score, noisy_score) = (pv[0], *score, *noisy_score);
+            self.debug(|| {
+                format!(
+                    "skill level {}: playing {} (score {}, {} with noise) instead of {}",
+                    skill_level,
+                    chosen_move,
+                    score,
+                    noisy_score,
+                    searched_move.map_or("none".to_string(), |piece_move| piece_move.to_string())
+                )
+            });
+        }
+
+        match best {
+            Some((_, score, pv)) => SearchResult {
+                best_move: pv.first().copied(),
+                ponder_move: pv.get(1).copied(),
+                score,
+                pv,
+                ..result
+            },
+            None => result,
+        }
+    }
+
+    // Whether the game is still early enough for the root move to be picked at random.
+    fn in_random_opening(&self, position: &Position) -> bool {
+        let game_ply = 2 * position.fullmove_number().saturating_sub(1)
+            + u32::from(position.side_to_move() == Side::Black);
+
+        !self.config.deterministic && game_ply < self.config.random_plies
+    }
+
+    // Rescores the root moves and plays one at random from those within the random margin of the
+    // best. The search result is kept if the search is stopped before the moves are all scored.
+    fn randomized_result(&mut self, position: &Position, result: SearchResult) -> SearchResult {
+        let Some(scored_moves) = self.score_root_moves(position, result.depth) else {
+            return result;
+        };
+
+        let Some(best_score) = scored_moves.iter().map(|(score, _)| *score).max() else {
+            return result;
+        };
+
+        let candidates: Vec<_> = scored_moves
+            .into_iter()
+            .filter(|(score, _)| *score >= best_score - self.config.random_margin)
+            .collect();
+
+        let index = (next_random(&mut self.random_state) % candidates.len() as u64) as usize;
+        let (score, pv) = candidates[index].clone();
+
+        let (candidate_count, chosen_move) = (candidates.len(), pv[0]);
+        let random_margin = self.config.random_margin;
+        self.debug(|| {
+            format!(
+                "random opening: playing {} (score {}) from {} moves within {} of {}",
+                chosen_move, score, candidate_count, random_margin, best_score
+            )
+        });
+
+        SearchResult {
+            best_move: pv.first().copied(),
+            ponder_move: pv.get(1).copied(),
+            score,
+            pv,
+            ..result
+        }
+    }
+
+    // Passes a message on to the debug callback, and to `tracing` if enabled. The message is
+    // only built if something will receive it.
+    fn debug(&mut self, message: impl FnOnce() -> String) {
+        #[cfg(feature = "tracing")]
+        let tracing_enabled = tracing::enabled!(tracing::Level::DEBUG);
+        #[cfg(not(feature = "tracing"))]
+        let tracing_enabled = false;
+
+        if self.debug_callback.is_none() && !tracing_enabled {
+            return;
+        }
+
+        let message = message();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("{}", message);
+
+        if let Some(callback) = self.debug_callback.as_mut() {
+            callback.on_log(&message);
+        }
+    }
+
+    fn report_info(&mut self, result: &SearchResult) {
+        let Some(callback) = self.info_callback.as_mut() else {
+            return;
+        };
+
+        // There is no line to report if the game is already over, and `score mate 0` without one
+        // would only confuse a GUI.
+        if result.pv.is_empty() {
+            return;
+        }
+
+        let time = self.start_time.elapsed();
+        let nps = (self.stats.nodes as u128 * 1000 / time.as_millis().max(1)) as u64;
+
+        callback.on_info(&SearchInfo {
+            depth: result.depth,
+            seldepth: self.stats.seldepth,
+            score: result.score,
+            nodes: self.stats.nodes,
+            nps,
+            time,
+            hashfull: self.tt.hashfull(),
+            pv: result.pv.clone(),
+        });
+    }
+
+    // Whether the move is one of those the root is restricted to. Moves are compared by their
+    // squares and promotion only, since moves from elsewhere may not know what they capture or
+    // what kind of move they are.
+    fn is_search_move(&self, piece_move: &Move) -> bool {
+        self.search_moves.iter().any(|search_move| {
+            search_move.from == piece_move.from
+                && search_move.to == piece_move.to
+                && search_move.promote == piece_move.promote
+        })
+    }
+
+    // Switches from pondering to a normal timed search once the ponder flag has been cleared.
+    fn check_ponder_hit(&mut self) {
+        if self.pondering && !self.ponder_flag.load(Ordering::Relaxed) {
+            self.pondering = false;
+            self.time_manager.restart();
+            self.debug(|| "ponder hit, starting the clock".to_string());
+        }
+    }
+
+    // Checks whether the search has run out of time or been told to stop. The clock is only read
+    // every so often, since doing so is relatively expensive.
+    fn should_stop(&mut self) -> bool {
+        // Always finish the first iteration, so there is a move to return.
+        if self.root_depth <= 1 {
+            return false;
+        }
+
+        if self
+            .node_limit
+            .is_some_and(|node_limit| self.stats.nodes >= node_limit)
+        {
+            self.stopped = true;
+        }
+
+        if !self.stopped && self.stats.nodes.is_multiple_of(1024) {
+            self.check_ponder_hit();
+
+            if self.stop_flag.load(Ordering::Relaxed)
+                || (!self.pondering && self.time_manager.hard_limit_reached())
+            {
+                self.stopped = true;
+            }
+        }
+
+        self.stopped
+    }
+
+    // Searches the root with a narrow window around the previous iteration's score, widening the
+    // window and searching again whenever the score falls outside of it.
+    fn aspiration_search(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        previous_score: i32,
+        previous_pv: &[Move],
+    ) -> (i32, Vec<Move>) {
+        let mut delta = ASPIRATION_WINDOW;
+
+        let (mut alpha, mut beta) =
+            if depth >= ASPIRATION_MIN_DEPTH && previous_score.abs() < MATE_BOUND {
+                (previous_score - delta, previous_score + delta)
+            } else {
+                (-INFINITY, INFINITY)
+            };
+
+        loop {
+            let mut pv = vec![];
+            let score = self.negamax(
+                position,
+                depth,
+                0,
+                alpha,
+                beta,
+                previous_pv,
+                &mut pv,
+                None,
+                0,
+            );
+
+            // A stopped search returns a score outside any window, so widening would only search
+            // again to no purpose. The caller keeps its last complete iteration instead.
+            if self.stopped {
+                return (score, pv);
+            }
+
+            if score <= alpha && alpha > -INFINITY {
+                alpha = (score - delta).max(-INFINITY);
+                self.debug(|| {
+                    format!(
+                        "depth {} failed low at {}, searching again in ({}, {})",
+                        depth, score, alpha, beta
+                    )
+                });
+            } else if score >= beta && beta < INFINITY {
+                beta = (score + delta).min(INFINITY);
+                self.debug(|| {
+                    format!(
+                        "depth {} failed high at {}, searching again in ({}, {})",
+                        depth, score, alpha, beta
+                    )
+                });
+            } else {
+                return (score, pv);
+            }
+
+            delta *= 2;
+        }
+    }
+
+    // Searches a node with `negamax_node`, recording it if the search is being traced. Nodes at the
+    // horizon go straight into the quiescence search, which records them itself.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+        previous_pv: &[Move],
+        pv: &mut Vec<Move>,
+        excluded_move: Option<Move>,
+        extensions: u32,
+    ) -> i32 {
+        let traced = match self.trace.as_mut() {
+            Some(trace) if depth > 0 || position.in_check() => {
+                trace.enter(ply, depth, alpha, beta, false);
+                true
+            }
+            _ => false,
+        };
+
+        let score = self.negamax_node(
+            position,
+            depth,
+            ply,
+            alpha,
+            beta,
+            previous_pv,
+            pv,
+            excluded_move,
+            extensions,
+        );
+
+        if let Some(trace) = self.trace.as_mut().filter(|_| traced) {
+            trace.exit(score, self.stopped);
+        }
+
+        score
+    }
+
+    // Negamax alpha-beta search. `previous_pv` is the principal variation from the last iteration,
+    // which is searched first so the best line found so far sets the bounds early. If
+    // `excluded_move` is given the move is skipped, which is used to test whether it is singular.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax_node(
+        &mut self,
+        position: &Position,
+        mut depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        mut beta: i32,
+        previous_pv: &[Move],
+        pv: &mut Vec<Move>,
+        excluded_move: Option<Move>,
+        extensions: u32,
+    ) -> i32 {
+        let in_check = position.in_check();
+        let mut extensions = extensions;
+
+        // Check extension: never drop into quiescence while in check, and look one ply further so
+        // that forcing sequences of checks are resolved.
+        if in_check && self.config.check_extensions && extensions < self.config.max_extensions {
+            depth += 1;
+            extensions += 1;
+        }
+
+        // A single repetition is enough to score a draw, as a line which repeats once can always
+        // be repeated again.
+        if ply > 0
+            && (position.halfmove_clock() >= 100
+                || self.is_repetition(position)
+                || position.is_insufficient_material())
+        {
+            self.trace_settle(TraceOutcome::Draw);
+            return self.draw_score(ply);
+        }
+
+        if depth == 0 {
+            return self.quiescence(position, ply, alpha, beta, self.config.quiescence_checks);
+        }
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        self.stats.nodes += 1;
+        self.stats.seldepth = self.stats.seldepth.max(ply);
+
+        // Mate distance pruning: no line from here can score better than mating on the next
+        // move, or worse than being mated now, so narrow the window accordingly.
+        if ply > 0 {
+            alpha = alpha.max(-MATE_SCORE + ply as i32);
+            beta = beta.min(MATE_SCORE - ply as i32 - 1);
+
+            if alpha >= beta {
+                self.trace_settle(TraceOutcome::MateDistance);
+                return alpha;
+            }
+        }
+
+        let is_pv_node = beta - alpha > 1;
+        let original_alpha = alpha;
+
+        let tt_entry = if excluded_move.is_none() {
+            self.tt.probe(position.hash()).map(|mut entry| {
+                entry.score = score_from_tt(entry.score, ply);
+                entry
+            })
+        } else {
+            None
+        };
+
+        // Use the stored score if it was searched deeply enough. This is not done in principal
+        // variation nodes, so that the full principal variation is still collected.
+        if let Some(entry) = tt_entry {
+            if ply > 0 && !is_pv_node && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+
+                if cutoff {
+                    self.stats.tt_cutoffs += 1;
+                    self.trace_settle(TraceOutcome::TtCutoff);
+                    return entry.score;
+                }
+            }
+        }
+
+        // Only the root moves asked for are searched, but if none of them are legal the
+        // restriction is ignored rather than leaving nothing to play.
+        let restrict_to_search_moves = ply == 0
+            && position
+                .legal_moves()
+                .iter()
+                .any(|piece_move| self.is_search_move(piece_move));
+
+        let tt_move = tt_entry.and_then(|entry| entry.best_move);
+
+        // Singular extension: if searching every move other than the transposition table move
+        // to a reduced depth fails well below its stored score, the move is the only good one and
+        // deserves a deeper search.
+        let mut singular_extension = 0;
+
+        if let (Some(entry), Some(tt_move)) = (tt_entry, tt_move) {
+            if self.config.singular_extensions
+                && ply > 0
+                && depth >= self.config.singular_min_depth
+                && entry.bound != Bound::Upper
+                && entry.depth + 3 >= depth
+                && entry.score.abs() < MATE_BOUND
+                && extensions < self.config.max_extensions
+            {
+                let singular_beta = entry.score - self.config.singular_margin * depth as i32;
+
+                let score = self.negamax(
+                    position,
+                    (depth - 1) / 2,
+                    ply,
+                    singular_beta - 1,
+                    singular_beta,
+                    &[],
+                    &mut vec![],
+                    Some(tt_move),
+                    extensions,
+                );
+
+                if self.stopped {
+                    return 0;
+                }
+
+                if score < singular_beta {
+                    singular_extension = 1;
+                }
+            }
+        }
+
+        // The previous principal variation move is tried first, followed by the transposition
+        // table move.
+        let mut picker = MovePicker::new(
+            previous_pv.first().copied().or(tt_move),
+            self.ordering.killers(ply as usize),
+        );
+
+        let mut best_move = None;
+        let mut has_legal_move = false;
+        let mut moves_searched = 0;
+        let mut move_count = 0;
+
+        while let Some(piece_move) = picker.next(position, &self.ordering) {
+            has_legal_move = true;
+
+            if restrict_to_search_moves && !self.is_search_move(&piece_move) {
+                continue;
+            }
+
+            // How many moves were ordered before this one.
+            let i = move_count;
+            move_count += 1;
+
+            if excluded_move == Some(piece_move) {
+                continue;
+            }
+
+            let is_quiet = piece_move.is_quiet();
+
+            // Late move pruning: at low depth, quiet moves ordered this late are very unlikely to
+            // raise alpha, so skip them entirely. Never prune at the root or when in check, and
+            // only once a move has been found which avoids being mated.
+            if ply > 0
+                && !in_check
+                && is_quiet
+                && depth <= LMP_MAX_DEPTH
+                && i >= late_move_pruning_threshold(depth)
+                && alpha > -MATE_BOUND
+            {
+                if let Some(trace) = self.trace.as_mut() {
+                    trace.prune(piece_move, ply, depth - 1);
+                }
+
+                continue;
+            }
+
+            let mut child = position.clone();
+            child.make_move(piece_move);
+            self.tt.prefetch(child.hash());
+
+            let extension = if tt_move == Some(piece_move) {
+                singular_extension
+            } else {
+                0
+            };
+            let child_depth = depth - 1 + extension;
+            let child_extensions = extensions + extension;
+
+            let child_previous_pv = if previous_pv.first() == Some(&piece_move) {
+                &previous_pv[1..]
+            } else {
+                &[]
+            };
+
+            let mut child_pv = vec![];
+            let mut score;
+
+            self.path.push(position.hash());
+
+            if let Some(trace) = self.trace.as_mut() {
+                trace.push_move(piece_move);
+            }
+
+            if moves_searched == 0 {
+                score = -self.negamax(
+                    &child,
+                    child_depth,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    child_previous_pv,
+                    &mut child_pv,
+                    None,
+                    child_extensions,
+                );
+            } else {
+                // Late move reductions: search late quiet moves to a reduced depth first, and only
+                // search them fully if they turn out to beat alpha.
+                let reduction = if depth >= LMR_MIN_DEPTH
+                    && i >= LMR_MIN_MOVES
+                    && is_quiet
+                    && !in_check
+                    && !child.in_check()
+                {
+                    late_move_reduction(depth, i)
+                } else {
+                    0
+                };
+
+                // Principal variation search: the first move is assumed to be the best, so the
+                // others are searched with a zero window just to prove that they are worse.
+                score = -self.negamax(
+                    &child,
+                    child_depth - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    &[],
+                    &mut child_pv,
+                    None,
+                    child_extensions,
+                );
+
+                if score > alpha && reduction > 0 {
+                    child_pv.clear();
+                    score = -self.negamax(
+                        &child,
+                        child_depth,
+                        ply + 1,
+                        -alpha - 1,
+                        -alpha,
+                        &[],
+                        &mut child_pv,
+                        None,
+                        child_extensions,
+                    );
+                }
+
+                // The move may be better than the first after all, so search it with the full
+                // window to find its exact score.
+                if score > alpha && score < beta {
+                    child_pv.clear();
+                    score = -self.negamax(
+                        &child,
+                        child_depth,
+                        ply + 1,
+                        -beta,
+                        -alpha,
+                        &[],
+                        &mut child_pv,
+                        None,
+                        child_extensions,
+                    );
+                }
+            }
+
+            self.path.pop();
+
+            if let Some(trace) = self.trace.as_mut() {
+                trace.pop_move();
+            }
+
+            if self.stopped {
+                return 0;
+            }
+
+            moves_searched += 1;
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(piece_move);
+
+                pv.clear();
+                pv.push(piece_move);
+                pv.append(&mut child_pv);
+
+                if alpha >= beta {
+                    self.stats.beta_cutoffs += 1;
+                    self.stats.first_move_cutoffs += (moves_searched == 1) as u64;
+                    self.ordering.record_cutoff(
+                        piece_move,
+                        position.side_to_move(),
+                        ply as usize,
+                        depth,
+                    );
+                    break;
+                }
+            }
+        }
+
+        if !has_legal_move {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                self.draw_score(ply)
+            };
+        }
+
+        if excluded_move.is_none() {
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+
+            self.tt.store(
+                position.hash(),
+                depth,
+                score_to_tt(alpha, ply),
+                bound,
+                best_move.or(tt_move),
+            );
+        }
+
+        alpha
+    }
+
+    // Notes how the node being searched was settled, if the search is being traced.
+    fn trace_settle(&mut self, outcome: TraceOutcome) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.settle(outcome);
+        }
+    }
+
+    // Whether the position has already occurred in the game or the line being searched, with the
+    // same side to move and no irreversible move since.
+    fn is_repetition(&self, position: &Position) -> bool {
+        self.path
+            .iter()
+            .rev()
+            .take(position.halfmove_clock() as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|&hash| hash == position.hash())
+    }
+
+    // The score of a drawn position at the given ply, for the side to move there. With contempt,
+    // the side to move at the root sees a draw as worse than equal, and its opponent as better.
+    fn draw_score(&self, ply: u32) -> i32 {
+        if ply.is_multiple_of(2) {
+            -self.config.contempt
+        } else {
+            self.config.contempt
+        }
+    }
+
+    // Searches a node with `quiescence_node`, recording it if the search is being traced.
+    fn quiescence(
+        &mut self,
+        position: &Position,
+        ply: u32,
+        alpha: i32,
+        beta: i32,
+        check_plies: u32,
+    ) -> i32 {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.enter(ply, 0, alpha, beta, true);
+        }
+
+        let score = self.quiescence_node(position, ply, alpha, beta, check_plies);
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.exit(score, self.stopped);
+        }
+
+        score
+    }
+
+    // Searches captures only until the position is quiet, so the static evaluation is not taken in
+    // the middle of an exchange. Quiet checks are also searched for the first `check_plies` plies.
+    // In check there is no standing pat, so every evasion is searched instead.
+    fn quiescence_node(
+        &mut self,
+        position: &Position,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+        check_plies: u32,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+
+        self.stats.nodes += 1;
+        self.stats.qnodes += 1;
+        self.stats.seldepth = self.stats.seldepth.max(ply);
+
+        let in_check = position.in_check();
+
+        if !in_check {
+            let stand_pat = self.eval_cache.evaluate(position, &mut self.pawn_table);
+
+            if stand_pat >= beta {
+                self.trace_settle(TraceOutcome::StandPat);
+                return stand_pat;
+            }
+
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut picker = if in_check {
+            MovePicker::new(None, [None; 2])
+        } else {
+            MovePicker::captures()
+        };
+        let mut has_legal_move = false;
+
+        while let Some(piece_move) = picker.next(position, &self.ordering) {
+            has_legal_move = true;
+
+            if self.quiescence_move(position, piece_move, ply, &mut alpha, beta, check_plies) {
+                break;
+            }
+        }
+
+        if self.stopped {
+            return 0;
+        }
+
+        if in_check && !has_legal_move {
+            return -MATE_SCORE + ply as i32;
+        }
+
+        if !in_check && check_plies > 0 && alpha < beta {
+            let side = position.side_to_move();
+            let mut checks = MoveList::new();
+            position.generate_quiet_checks(&mut checks);
+
+            for &piece_move in checks.iter() {
+                if !position.board().leaves_king_in_check(&piece_move, side)
+                    && self.quiescence_move(
+                        position,
+                        piece_move,
+                        ply,
+                        &mut alpha,
+                        beta,
+                        check_plies,
+                    )
+                {
+                    break;
+                }
+            }
+        }
+
+        if self.stopped {
+            return 0;
+        }
+
+        alpha
+    }
+
+    // Searches one move of the quiescence search, raising `alpha` if it does better. Returns true
+    // if the search should stop looking at moves, either on a beta cutoff or because it was
+    // stopped.
+    fn quiescence_move(
+        &mut self,
+        position: &Position,
+        piece_move: Move,
+        ply: u32,
+        alpha: &mut i32,
+        beta: i32,
+        check_plies: u32,
+    ) -> bool {
+        let mut child = position.clone();
+        child.make_move(piece_move);
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push_move(piece_move);
+        }
+
+        let score = -self.quiescence(
+            &child,
+            ply + 1,
+            -beta,
+            -*alpha,
+            check_plies.saturating_sub(1),
+        );
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.pop_move();
+        }
+
+        if self.stopped {
+            return true;
+        }
+
+        *alpha = (*alpha).max(score);
+        *alpha >= beta
+    }
+}
+
+// A number from 0 up to `range`, derived from the position and move by mixing their bits as the
+// SplitMix64 generator does.
+fn skill_noise(hash: u64, piece_move: Move, range: i32) -> i32 {
+    if range <= 0 {
+        return 0;
+    }
+
+    let mut z = hash ^ ((piece_move.from.index() as u64) << 6 | piece_move.to.index() as u64);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+
+    (z % range as u64) as i32
+}
+
+// The number of moves after which quiet moves are pruned at the given depth.
+fn late_move_pruning_threshold(depth: u32) -> usize {
+    (3 + depth * depth) as usize
+}
+
+// How many plies to reduce the search of a late quiet move by. Reductions grow with both depth and
+// move number, leaving at least one ply to search.
+fn late_move_reduction(depth: u32, move_index: usize) -> u32 {
+    let reduction = 0.75 + (depth as f64).ln() * (move_index as f64).ln() / 2.25;
+
+    (reduction as u32).clamp(1, depth - 2)
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::{
+    Board, Engine, Position, Search, SearchLimits, SearchResult, TranspositionTable, TtEntry,
+};
+
+// Positions, games and the tables the search fills in are plain data, so they can be shared
+// between threads. A search holds callbacks which may only be moved to another thread, so it is
+// sent to one but not shared.
+const _: () = {
+    const fn send_sync<T: Send + Sync>() {}
+    const fn send<T: Send>() {}
+
+    send_sync::<Board>();
+    send_sync::<Position>();
+    send_sync::<Engine>();
+    send_sync::<TranspositionTable>();
+    send_sync::<TtEntry>();
+    send_sync::<SearchHandle>();
+    send::<Search>();
+};
+
+/// A search running on a thread of its own, started by `Engine::search_async` or
+/// `Search::search_async`. It can be waited for with `join`, or cut short with `abort`, either of
+/// which gives the result. Dropping the handle stops the search without waiting for it.
+///
+/// # Examples
+///
+/// Several games can be analysed at once, each on its own thread.
+///
+/// ```
+/// use chess_engine::{Engine, SearchLimits};
+///
+/// let handles: Vec<_> = [
+///     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+///     "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+///     "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+/// ]
+/// .iter()
+/// .map(|fen| {
+///     let mut engine = Engine::default();
+///     engine.set_fen(fen).unwrap();
+///
+///     let limits = SearchLimits {
+///         depth: Some(4),
+///         ..Default::default()
+///     };
+///
+///     engine.search_async(limits)
+/// })
+/// .collect();
+///
+/// let results: Vec<_> = handles.into_iter().map(|handle| handle.join()).collect();
+/// assert!(results.iter().all(|result| result.best_move.is_some()));
+/// assert_eq!(results[1].best_move.unwrap().to_string(), "a1a8");
+/// ```
+///
+/// A search with no limits runs until it is aborted, and then returns the best move from its
+/// last complete iteration.
+///
+/// ```
+/// use std::time::Duration;
+/// use chess_engine::{Engine, SearchLimits};
+///
+/// let mut engine = Engine::default();
+/// engine.set_initial_position();
+///
+/// let handle = engine.search_async(SearchLimits {
+///     infinite: true,
+///     ..Default::default()
+/// });
+///
+/// std::thread::sleep(Duration::from_millis(50));
+/// assert!(!handle.is_finished());
+///
+/// let result = handle.abort();
+/// assert!(result.best_move.is_some());
+/// ```
+pub struct SearchHandle {
+    stop_flag: Arc<AtomicBool>,
+    // Only taken when the search is joined, which consumes the handle.
+    thread: Option<JoinHandle<SearchResult>>,
+}
+
+impl SearchHandle {
+    /// Returns true once the search has finished, so that `join` won't wait.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Tells the search to stop as soon as it can, without waiting for it.
+    pub fn stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Waits for the search to finish within its limits and returns its result. If the search
+    /// panicked, the panic is passed on to the caller.
+    pub fn join(mut self) -> SearchResult {
+        let thread = self.thread.take().expect("the search is only joined once");
+
+        thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    /// Stops the search and returns the best move found so far, from its last complete
+    /// iteration.
+    pub fn abort(self) -> SearchResult {
+        self.stop();
+        self.join()
+    }
+}
+
+impl Drop for SearchHandle {
+    fn drop(&mut self) {
+        // Nothing could collect the result, so the search is left to finish quickly on its own.
+        if self.thread.is_some() {
+            self.stop();
+        }
+    }
+}
+
+impl Search {
+    /// Starts searching the current position of a game on a new thread, as `best_move_in_game`
+    /// would, and returns a handle to wait for or stop it with. The search and a copy of the game
+    /// are moved to the thread, so the game can still be played on while it runs.
+    pub fn search_async(self, engine: &Engine, limits: SearchLimits) -> SearchHandle {
+        let mut search = self;
+        let stop_flag = search.stop_flag();
+        stop_flag.store(false, Ordering::Relaxed);
+
+        let engine = engine.clone();
+        let thread = std::thread::spawn(move || search.best_move_in_game(&engine, &limits));
+
+        SearchHandle {
+            stop_flag,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Engine {
+    /// Starts searching the current position on a new thread, with a search of its own, and
+    /// returns a handle to wait for or stop it with. See `SearchHandle`.
+    pub fn search_async(&self, limits: SearchLimits) -> SearchHandle {
+        Search::new().search_async(self, limits)
+    }
+}
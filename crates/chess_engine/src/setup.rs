@@ -0,0 +1,316 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::position::char_from_piece;
+use crate::{
+    Board, PieceType, Position, Side, Square, CASTLE_BLACK_KINGSIDE, CASTLE_BLACK_QUEENSIDE,
+    CASTLE_WHITE_KINGSIDE, CASTLE_WHITE_QUEENSIDE,
+};
+
+/// The reason a position set up with a [`PositionBuilder`] can't be played.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SetupError {
+    // The side doesn't have exactly one king.
+    KingCount(Side),
+    // A pawn is on the first or eighth rank, which pawns can never stand on.
+    PawnOnBackRank(Square),
+    // The `CASTLE_*` flag of a right given without the king and a rook on the back rank to
+    // castle with.
+    InvalidCastling(u8),
+    // The en passant square isn't behind a pawn which could just have made a double push.
+    InvalidEnPassant(Square),
+    // The side not to move is in check, so its king could be captured.
+    OpponentInCheck,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::KingCount(side) => {
+                write!(f, "{} must have exactly one king", side_name(*side))
+            }
+            SetupError::PawnOnBackRank(square) => write!(f, "pawn on back rank square {}", square),
+            SetupError::InvalidCastling(right) => {
+                write!(f, "no king and rook to castle with for right {}", right)
+            }
+            SetupError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square {}", square)
+            }
+            SetupError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl core::error::Error for SetupError {}
+
+/// Sets up a position piece by piece, such as in a GUI's board editor, checking that it can be
+/// played before producing a [`Position`].
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{PieceType, PositionBuilder, Side, Square, CASTLE_WHITE_KINGSIDE};
+///
+/// let square = |name: &str| name.parse::<Square>().unwrap();
+///
+/// let mut builder = PositionBuilder::new();
+/// builder
+///     .set_piece(square("e1"), Some((PieceType::King, Side::White)))
+///     .set_piece(square("h1"), Some((PieceType::Rook, Side::White)))
+///     .set_piece(square("e8"), Some((PieceType::King, Side::Black)))
+///     .set_side_to_move(Side::Black)
+///     .set_castling_rights(CASTLE_WHITE_KINGSIDE);
+///
+/// let position = builder.build().unwrap();
+/// assert_eq!(position.fen(), "4k3/8/8/8/8/8/8/4K2R b K - 0 1");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PositionBuilder {
+    squares: [Option<(PieceType, Side)>; 64],
+    side_to_move: Side,
+    // The `CASTLE_*` flags of the rights to give.
+    castling_rights: u8,
+    en_passant: Option<Square>,
+}
+
+impl PositionBuilder {
+    /// Starts from an empty board with white to move and no castling rights.
+    pub fn new() -> Self {
+        Self {
+            squares: [None; 64],
+            side_to_move: Side::White,
+            castling_rights: 0,
+            en_passant: None,
+        }
+    }
+
+    /// Puts a piece on a square, replacing any already there, or empties the square if `None`.
+    pub fn set_piece(&mut self, square: Square, piece: Option<(PieceType, Side)>) -> &mut Self {
+        self.squares[square.index()] = piece;
+        self
+    }
+
+    pub fn set_side_to_move(&mut self, side: Side) -> &mut Self {
+        self.side_to_move = side;
+        self
+    }
+
+    /// Sets the castling rights from `CASTLE_*` flags. Each side castles with the rook furthest
+    /// from its king on the given side, as the `KQkq` castling rights in FEN do.
+    pub fn set_castling_rights(&mut self, rights: u8) -> &mut Self {
+        self.castling_rights = rights;
+        self
+    }
+
+    /// Sets the square a pawn may capture en passant onto: the one behind a pawn of the side not
+    /// to move which has just made a double push.
+    pub fn set_en_passant(&mut self, square: Option<Square>) -> &mut Self {
+        self.en_passant = square;
+        self
+    }
+
+    /// Checks that the position can be played: each side has exactly one king, no pawn is on the
+    /// first or eighth rank, the castling rights and en passant square fit the pieces, and the
+    /// side not to move isn't in check. The first problem found is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{PieceType, PositionBuilder, SetupError, Side, Square};
+    ///
+    /// let square = |name: &str| name.parse::<Square>().unwrap();
+    ///
+    /// let mut builder = PositionBuilder::new();
+    /// builder.set_piece(square("e1"), Some((PieceType::King, Side::White)));
+    /// assert_eq!(builder.validate(), Err(SetupError::KingCount(Side::Black)));
+    ///
+    /// builder.set_piece(square("e8"), Some((PieceType::King, Side::Black)));
+    /// builder.set_piece(square("a8"), Some((PieceType::Pawn, Side::White)));
+    /// assert_eq!(builder.validate(), Err(SetupError::PawnOnBackRank(square("a8"))));
+    ///
+    /// // The rook checks the black king with white to move.
+    /// builder.set_piece(square("a8"), Some((PieceType::Rook, Side::White)));
+    /// assert_eq!(builder.validate(), Err(SetupError::OpponentInCheck));
+    ///
+    /// builder.set_side_to_move(Side::Black);
+    /// assert_eq!(builder.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), SetupError> {
+        for side in [Side::White, Side::Black] {
+            if self.find_pieces(PieceType::King, side).count() != 1 {
+                return Err(SetupError::KingCount(side));
+            }
+        }
+
+        for side in [Side::White, Side::Black] {
+            if let Some(square) = self
+                .find_pieces(PieceType::Pawn, side)
+                .find(|square| matches!(square.rank().index(), 0 | 7))
+            {
+                return Err(SetupError::PawnOnBackRank(square));
+            }
+        }
+
+        for (right, side, kingside) in [
+            (CASTLE_WHITE_KINGSIDE, Side::White, true),
+            (CASTLE_WHITE_QUEENSIDE, Side::White, false),
+            (CASTLE_BLACK_KINGSIDE, Side::Black, true),
+            (CASTLE_BLACK_QUEENSIDE, Side::Black, false),
+        ] {
+            if self.castling_rights & right != 0 && !self.can_have_castling_right(side, kingside) {
+                return Err(SetupError::InvalidCastling(right));
+            }
+        }
+
+        if let Some(square) = self.en_passant {
+            if !self.is_valid_en_passant(square) {
+                return Err(SetupError::InvalidEnPassant(square));
+            }
+        }
+
+        // The board is set up on its own, as a position can't be read from FEN with the side not
+        // to move in check.
+        let mut board = Board::new();
+
+        for (index, &piece) in self.squares.iter().enumerate() {
+            board.set_square(index, piece);
+        }
+
+        let opponent = self.side_to_move.flip();
+
+        if let Some(king) = self.find_pieces(PieceType::King, opponent).next() {
+            if board.attackers_to(king.index(), self.side_to_move) != 0 {
+                return Err(SetupError::OpponentInCheck);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Produces the position if it passes `validate`, with both clocks at their start.
+    pub fn build(&self) -> Result<Position, SetupError> {
+        self.validate()?;
+
+        Ok(self.position())
+    }
+
+    // The squares of the given side's pieces of the given type.
+    fn find_pieces(&self, piece_type: PieceType, side: Side) -> impl Iterator<Item = Square> + '_ {
+        (0..64)
+            .filter(move |&index| self.squares[index] == Some((piece_type, side)))
+            .map(Square::new)
+    }
+
+    // Whether the side's king is on its back rank with a rook of its own beyond it, towards the
+    // h-file if `kingside` is set and the a-file otherwise.
+    fn can_have_castling_right(&self, side: Side, kingside: bool) -> bool {
+        let back_rank = match side {
+            Side::White => 0,
+            _ => 56,
+        };
+
+        let is_ours =
+            |piece_type, file: usize| self.squares[back_rank + file] == Some((piece_type, side));
+
+        let Some(king_file) = (0..8).find(|&file| is_ours(PieceType::King, file)) else {
+            return false;
+        };
+
+        if kingside {
+            (king_file + 1..8).any(|file| is_ours(PieceType::Rook, file))
+        } else {
+            (0..king_file).any(|file| is_ours(PieceType::Rook, file))
+        }
+    }
+
+    // Whether the square is empty, with an enemy pawn in front of it which could have just pushed
+    // two squares over it from its starting square.
+    fn is_valid_en_passant(&self, square: Square) -> bool {
+        let pushed_side = self.side_to_move.flip();
+        let (rank, pawn_index, start_index) = match pushed_side {
+            Side::White => (2, square.index() + 8, square.index().wrapping_sub(8)),
+            _ => (5, square.index().wrapping_sub(8), square.index() + 8),
+        };
+
+        square.rank().index() == rank
+            && self.squares[square.index()].is_none()
+            && self.squares[start_index].is_none()
+            && self.squares[pawn_index] == Some((PieceType::Pawn, pushed_side))
+    }
+
+    // Sets up the position through its FEN, which is only valid once the position has passed all
+    // of `validate`'s checks.
+    fn position(&self) -> Position {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                match self.squares[rank * 8 + file] {
+                    Some((piece_type, side)) => {
+                        if empty > 0 {
+                            fen += &empty.to_string();
+                            empty = 0;
+                        }
+
+                        fen.push(char_from_piece(piece_type, side));
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen += &empty.to_string();
+            }
+
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen += match self.side_to_move {
+            Side::White => " w ",
+            _ => " b ",
+        };
+
+        let castling: String = [
+            (CASTLE_WHITE_KINGSIDE, 'K'),
+            (CASTLE_WHITE_QUEENSIDE, 'Q'),
+            (CASTLE_BLACK_KINGSIDE, 'k'),
+            (CASTLE_BLACK_QUEENSIDE, 'q'),
+        ]
+        .into_iter()
+        .filter(|(right, _)| self.castling_rights & right != 0)
+        .map(|(_, c)| c)
+        .collect();
+
+        fen += if castling.is_empty() { "-" } else { &castling };
+
+        match self.en_passant {
+            Some(square) => fen += &format!(" {} 0 1", square),
+            None => fen += " - 0 1",
+        }
+
+        let mut position = Position::default();
+        position
+            .set_fen(&fen)
+            .expect("the builder only writes FEN for validated positions");
+
+        position
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::White => "white",
+        _ => "black",
+    }
+}
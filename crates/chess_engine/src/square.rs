@@ -0,0 +1,201 @@
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::Bitboard;
+
+/// A file of the board, from the a-file to the h-file.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct File(u8);
+
+/// A rank of the board, from the first rank to the eighth.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Rank(u8);
+
+/// A square of the board. Squares are numbered from 0 for a1 to 63 for h8, rank by rank, which
+/// is the bit used for the square in a bitboard.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Square(u8);
+
+impl File {
+    /// Returns the file with the given index, from 0 for the a-file to 7 for the h-file.
+    pub const fn from_index(index: usize) -> Option<File> {
+        if index < 8 {
+            Some(File(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+
+    /// Returns the file named by a letter from `a` to `h`.
+    pub fn from_char(c: char) -> Option<File> {
+        ('a'..='h').contains(&c).then(|| File(c as u8 - b'a'))
+    }
+}
+
+impl Rank {
+    /// Returns the rank with the given index, from 0 for the first rank to 7 for the eighth.
+    pub const fn from_index(index: usize) -> Option<Rank> {
+        if index < 8 {
+            Some(Rank(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+
+    /// Returns the rank named by a digit from `1` to `8`.
+    pub fn from_char(c: char) -> Option<Rank> {
+        ('1'..='8').contains(&c).then(|| Rank(c as u8 - b'1'))
+    }
+}
+
+impl Square {
+    /// Returns the square with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not less than 64.
+    pub const fn new(index: usize) -> Square {
+        assert!(index < 64, "square index out of range");
+
+        Square(index as u8)
+    }
+
+    /// Returns the square with the given index, or `None` if it is not less than 64.
+    pub const fn from_index(index: usize) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub const fn from_coords(file: File, rank: Rank) -> Square {
+        Square(rank.0 * 8 + file.0)
+    }
+
+    /// Returns the lowest square set in a bitboard, or `None` if the bitboard is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Square, RANK_4};
+    ///
+    /// assert_eq!(Square::from_bitboard(RANK_4), "a4".parse().ok());
+    /// assert_eq!(Square::from_bitboard(0), None);
+    /// ```
+    pub const fn from_bitboard(bitboard: Bitboard) -> Option<Square> {
+        Square::from_index(bitboard.trailing_zeros() as usize)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub const fn file(self) -> File {
+        File(self.0 % 8)
+    }
+
+    pub const fn rank(self) -> Rank {
+        Rank(self.0 / 8)
+    }
+
+    /// Returns a bitboard with only this square set.
+    pub const fn bitboard(self) -> Bitboard {
+        1 << self.0
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    /// Formats the square by name, such as `e4`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+/// The error returned when a string doesn't name a square.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseSquareError(pub String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid square '{}'", self.0)
+    }
+}
+
+impl core::error::Error for ParseSquareError {}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    /// Parses a square by name, such as `e4`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::Square;
+    ///
+    /// let e4: Square = "e4".parse().unwrap();
+    /// assert_eq!(e4.index(), 28);
+    /// assert_eq!((e4.file().index(), e4.rank().index()), (4, 3));
+    /// assert_eq!(e4.to_string(), "e4");
+    ///
+    /// assert!("e9".parse::<Square>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+
+        match (
+            chars.next().and_then(File::from_char),
+            chars.next().and_then(Rank::from_char),
+            chars.next(),
+        ) {
+            (Some(file), Some(rank), None) => Ok(Square::from_coords(file, rank)),
+            _ => Err(ParseSquareError(s.to_string())),
+        }
+    }
+}
+
+// Squares are written in algebraic notation, such as "e4".
+#[cfg(feature = "serde")]
+impl serde::Serialize for Square {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Square {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        notation.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,8 @@
+// The standard library's clock panics on `wasm32-unknown-unknown`, which has no clock of its own,
+// so there the time is taken from the browser instead.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
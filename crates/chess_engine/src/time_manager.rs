@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use crate::time::Instant;
+use crate::Side;
+
+// The number of moves assumed to remain in the game when the time control doesn't say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// Time always left on the clock, to allow for communication delays.
+const SAFETY_MARGIN: Duration = Duration::from_millis(50);
+
+// The hard limit may exceed the soft limit by at most this factor.
+const HARD_LIMIT_FACTOR: u32 = 3;
+
+/// The time control information given with UCI `go`.
+#[derive(Clone, Debug, Default)]
+pub struct TimeLimits {
+    pub white_time: Option<Duration>,
+    pub black_time: Option<Duration>,
+    pub white_increment: Option<Duration>,
+    pub black_increment: Option<Duration>,
+    // The number of moves until the next time control.
+    pub moves_to_go: Option<u32>,
+    // Search for exactly this long.
+    pub move_time: Option<Duration>,
+    // Time lost on every move to communication delays, which is taken off the time available.
+    pub move_overhead: Duration,
+}
+
+impl TimeLimits {
+    pub fn is_empty(&self) -> bool {
+        self.white_time.is_none() && self.black_time.is_none() && self.move_time.is_none()
+    }
+}
+
+/// Converts a time control into a budget for the current move, and tracks the time used.
+///
+/// The soft limit is the time the search aims to use, and is checked between iterations of
+/// iterative deepening. The hard limit is checked during the search, which is aborted once it is
+/// reached.
+pub struct TimeManager {
+    start: Instant,
+    soft_limit: Option<Duration>,
+    hard_limit: Option<Duration>,
+}
+
+impl TimeManager {
+    /// Creates a time manager for the side to move, starting the clock immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The time control information for the search.
+    /// * `side` - The side to move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use chess_engine::{Side, TimeLimits, TimeManager};
+    ///
+    /// let limits = TimeLimits {
+    ///     white_time: Some(Duration::from_secs(60)),
+    ///     black_time: Some(Duration::from_secs(60)),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let time_manager = TimeManager::new(&limits, Side::White);
+    /// assert!(time_manager.soft_limit().unwrap() < time_manager.hard_limit().unwrap());
+    /// assert!(time_manager.hard_limit().unwrap() < Duration::from_secs(60));
+    ///
+    /// // The move overhead is kept back from the time given for the move.
+    /// let limits = TimeLimits {
+    ///     move_time: Some(Duration::from_millis(1000)),
+    ///     move_overhead: Duration::from_millis(100),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let time_manager = TimeManager::new(&limits, Side::White);
+    /// assert_eq!(time_manager.hard_limit(), Some(Duration::from_millis(900)));
+    /// ```
+    pub fn new(limits: &TimeLimits, side: Side) -> Self {
+        let (time, increment) = match side {
+            Side::White => (limits.white_time, limits.white_increment),
+            _ => (limits.black_time, limits.black_increment),
+        };
+
+        let overhead = limits.move_overhead;
+
+        let (soft_limit, hard_limit) = if let Some(move_time) = limits.move_time {
+            let move_time = move_time.saturating_sub(overhead);
+            (Some(move_time), Some(move_time))
+        } else if let Some(time) = time {
+            let available = time.saturating_sub(SAFETY_MARGIN + overhead);
+            let increment = increment.unwrap_or_default();
+            let moves_to_go = limits.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+            let soft = (time / moves_to_go + increment * 3 / 4).saturating_sub(overhead);
+            let hard = (soft * HARD_LIMIT_FACTOR).min(available * 3 / 4);
+
+            (Some(soft.min(hard)), Some(hard))
+        } else {
+            (None, None)
+        };
+
+        Self {
+            start: Instant::now(),
+            soft_limit,
+            hard_limit,
+        }
+    }
+
+    /// Creates a time manager which never runs out of time.
+    pub fn unlimited() -> Self {
+        Self {
+            start: Instant::now(),
+            soft_limit: None,
+            hard_limit: None,
+        }
+    }
+
+    /// Restarts the clock, keeping the same limits. Used when a search started while pondering
+    /// becomes a normal search, since the engine's clock only starts running then.
+    pub fn restart(&mut self) {
+        self.start = Instant::now();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn soft_limit(&self) -> Option<Duration> {
+        self.soft_limit
+    }
+
+    pub fn hard_limit(&self) -> Option<Duration> {
+        self.hard_limit
+    }
+
+    /// Returns true if there is not enough time left to start another iteration.
+    pub fn soft_limit_reached(&self) -> bool {
+        self.soft_limit
+            .is_some_and(|soft_limit| self.elapsed() >= soft_limit)
+    }
+
+    /// Returns true if the search must stop immediately.
+    pub fn hard_limit_reached(&self) -> bool {
+        self.hard_limit
+            .is_some_and(|hard_limit| self.elapsed() >= hard_limit)
+    }
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
@@ -0,0 +1,328 @@
+use std::fmt::Write;
+
+use crate::Move;
+
+/// How a node of a traced search was settled.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TraceOutcome {
+    // The score lies within the node's window, so it is exact.
+    Exact,
+    // The score reached beta, so the rest of the node's moves were cut off.
+    FailHigh,
+    // No move raised alpha, so the score is only an upper bound.
+    FailLow,
+    // A stored score from the transposition table was used instead of searching.
+    TtCutoff,
+    // The position was scored as a draw by repetition, the fifty-move rule or material.
+    Draw,
+    // Mate distance pruning closed the window before any move was searched.
+    MateDistance,
+    // The static evaluation was good enough to stop the quiescence search here.
+    StandPat,
+    // The move was skipped by late move pruning without being searched.
+    Pruned,
+    // The search was stopped before the node was finished.
+    Stopped,
+}
+
+impl TraceOutcome {
+    fn name(self) -> &'static str {
+        match self {
+            TraceOutcome::Exact => "exact",
+            TraceOutcome::FailHigh => "fail-high",
+            TraceOutcome::FailLow => "fail-low",
+            TraceOutcome::TtCutoff => "tt-cutoff",
+            TraceOutcome::Draw => "draw",
+            TraceOutcome::MateDistance => "mate-distance",
+            TraceOutcome::StandPat => "stand-pat",
+            TraceOutcome::Pruned => "pruned",
+            TraceOutcome::Stopped => "stopped",
+        }
+    }
+}
+
+/// A node of a traced search: a position searched with a window, and what became of it.
+#[derive(Clone, Debug)]
+pub struct TraceNode {
+    // The index of the node this one was searched from, or `None` for the root.
+    pub parent: Option<usize>,
+    // The move leading here from the parent, or `None` for the root, and for a search of the
+    // parent's own position, as when testing whether a move is singular.
+    pub piece_move: Option<Move>,
+    pub ply: u32,
+    // The depth left to search, which is 0 in the quiescence search.
+    pub depth: u32,
+    pub alpha: i32,
+    pub beta: i32,
+    // The score returned, from the point of view of the side to move at this node.
+    pub score: i32,
+    pub quiescence: bool,
+    pub outcome: TraceOutcome,
+}
+
+/// The tree explored by the last iteration of a search, recorded when tracing is turned on with
+/// `Search::start_trace`, so that it can be looked at to see why the search chose its move.
+///
+/// Only the first nodes of each iteration, up to the limit the trace was started with, are
+/// recorded, so tracing suits small searches, such as ones limited to a low depth. Every
+/// re-search is recorded as a node of its own, so a move reduced by late move reductions, or
+/// searched with a zero window, can appear more than once under its parent.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::{Engine, Search, SearchLimits, TraceOutcome};
+///
+/// let mut engine = Engine::default();
+/// engine.set_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+///
+/// let mut search = Search::new();
+/// search.start_trace(1000);
+///
+/// let limits = SearchLimits {
+///     depth: Some(3),
+///     ..Default::default()
+/// };
+///
+/// let result = search.best_move(&engine, &limits);
+/// let trace = search.take_trace().unwrap();
+///
+/// // The root is the first node, searched with the best move as one of its children.
+/// let root = &trace.nodes()[0];
+/// assert_eq!((root.parent, root.ply, root.score), (None, 0, result.score));
+///
+/// assert!(trace.nodes().len() <= 1000);
+/// assert!(trace.nodes().iter().any(|node| {
+///     node.parent == Some(0) && node.piece_move == result.best_move
+/// }));
+/// assert!(trace.nodes().iter().all(|node| node.outcome != TraceOutcome::Stopped));
+///
+/// assert!(trace.to_dot().starts_with("digraph search {"));
+/// assert!(trace.to_json().starts_with("{\"omitted\":"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SearchTrace {
+    max_nodes: usize,
+    nodes: Vec<TraceNode>,
+    // The nodes of the current iteration left out once the limit was reached.
+    omitted: u64,
+    // The nodes being searched, innermost last, or `None` for those left out.
+    open: Vec<Option<usize>>,
+    // The moves leading to the innermost node being searched.
+    line: Vec<Move>,
+    // How the innermost node was settled, if it was by something other than its score.
+    outcome: Option<TraceOutcome>,
+}
+
+impl SearchTrace {
+    pub(crate) fn new(max_nodes: usize) -> Self {
+        Self {
+            max_nodes,
+            nodes: vec![],
+            omitted: 0,
+            open: vec![],
+            line: vec![],
+            outcome: None,
+        }
+    }
+
+    /// The nodes recorded, in the order they were first searched, so that each node comes after
+    /// its parent.
+    pub fn nodes(&self) -> &[TraceNode] {
+        &self.nodes
+    }
+
+    /// The number of nodes searched in the iteration which were left out of the trace.
+    pub fn omitted(&self) -> u64 {
+        self.omitted
+    }
+
+    pub(crate) fn enter(&mut self, ply: u32, depth: u32, alpha: i32, beta: i32, quiescence: bool) {
+        // A new iteration, or a re-search of the root, replaces the tree recorded so far.
+        if ply == 0 && self.open.is_empty() {
+            self.nodes.clear();
+            self.omitted = 0;
+            self.line.clear();
+        }
+
+        if self.nodes.len() >= self.max_nodes {
+            self.omitted += 1;
+            self.open.push(None);
+            return;
+        }
+
+        // A node at the same ply as its parent searches the parent's position again.
+        let parent = self.open.last().copied().flatten();
+        let piece_move = match parent {
+            Some(parent) if self.nodes[parent].ply == ply => None,
+            _ => ply
+                .checked_sub(1)
+                .and_then(|index| self.line.get(index as usize))
+                .copied(),
+        };
+
+        self.open.push(Some(self.nodes.len()));
+        self.outcome = None;
+        self.nodes.push(TraceNode {
+            parent,
+            piece_move,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: 0,
+            quiescence,
+            outcome: TraceOutcome::Stopped,
+        });
+    }
+
+    pub(crate) fn exit(&mut self, score: i32, stopped: bool) {
+        let outcome = self.outcome.take();
+
+        let Some(Some(index)) = self.open.pop() else {
+            return;
+        };
+
+        let node = &mut self.nodes[index];
+        node.score = score;
+        node.outcome = match outcome {
+            _ if stopped => TraceOutcome::Stopped,
+            Some(outcome) => outcome,
+            None if score >= node.beta => TraceOutcome::FailHigh,
+            None if score <= node.alpha => TraceOutcome::FailLow,
+            None => TraceOutcome::Exact,
+        };
+    }
+
+    // Notes how the innermost node is being settled, when its score alone doesn't say.
+    pub(crate) fn settle(&mut self, outcome: TraceOutcome) {
+        self.outcome = Some(outcome);
+    }
+
+    pub(crate) fn push_move(&mut self, piece_move: Move) {
+        self.line.push(piece_move);
+    }
+
+    pub(crate) fn pop_move(&mut self) {
+        self.line.pop();
+    }
+
+    // Records a move of the innermost node which was skipped without being searched.
+    pub(crate) fn prune(&mut self, piece_move: Move, ply: u32, depth: u32) {
+        let Some(&Some(parent)) = self.open.last() else {
+            return;
+        };
+
+        if self.nodes.len() >= self.max_nodes {
+            self.omitted += 1;
+            return;
+        }
+
+        self.nodes.push(TraceNode {
+            parent: Some(parent),
+            piece_move: Some(piece_move),
+            ply: ply + 1,
+            depth,
+            alpha: 0,
+            beta: 0,
+            score: 0,
+            quiescence: false,
+            outcome: TraceOutcome::Pruned,
+        });
+    }
+
+    /// Writes the tree in the DOT language, for drawing with Graphviz, such as with
+    /// `dot -Tsvg trace.dot -o trace.svg`. Each node is labelled with its move, depth, window
+    /// and score, and coloured by how it was settled.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        dot += "  node [shape=box, fontname=monospace, style=filled];\n";
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = match node.outcome {
+                TraceOutcome::Pruned => format!("{}\\npruned", move_name(node)),
+                _ => format!(
+                    "{}\\n{}{} [{}, {}]\\n{} {}",
+                    move_name(node),
+                    if node.quiescence { "q" } else { "d" },
+                    node.depth,
+                    node.alpha,
+                    node.beta,
+                    node.score,
+                    node.outcome.name()
+                ),
+            };
+
+            let colour = match node.outcome {
+                TraceOutcome::Exact => "palegreen",
+                TraceOutcome::FailHigh => "lightsalmon",
+                TraceOutcome::FailLow => "lightblue",
+                TraceOutcome::TtCutoff | TraceOutcome::MateDistance => "khaki",
+                TraceOutcome::Pruned | TraceOutcome::Stopped => "lightgrey",
+                TraceOutcome::Draw | TraceOutcome::StandPat => "white",
+            };
+
+            writeln!(
+                dot,
+                "  n{} [label=\"{}\", fillcolor={}];",
+                index, label, colour
+            )
+            .unwrap();
+
+            if let Some(parent) = node.parent {
+                writeln!(dot, "  n{} -> n{};", parent, index).unwrap();
+            }
+        }
+
+        dot += "}\n";
+        dot
+    }
+
+    /// Writes the tree as JSON: `{"omitted": n, "nodes": [...]}`, with a node object for each
+    /// node, giving its `parent` index, `move`, `ply`, `depth`, `alpha`, `beta`, `score`,
+    /// `quiescence` and `outcome`.
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"omitted\":{},\"nodes\":[", self.omitted);
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+
+            let parent = node
+                .parent
+                .map_or("null".to_string(), |parent| parent.to_string());
+            let piece_move = node.piece_move.map_or("null".to_string(), |piece_move| {
+                format!("\"{}\"", piece_move)
+            });
+
+            write!(
+                json,
+                "{{\"parent\":{},\"move\":{},\"ply\":{},\"depth\":{},\"alpha\":{},\"beta\":{},\
+                 \"score\":{},\"quiescence\":{},\"outcome\":\"{}\"}}",
+                parent,
+                piece_move,
+                node.ply,
+                node.depth,
+                node.alpha,
+                node.beta,
+                node.score,
+                node.quiescence,
+                node.outcome.name()
+            )
+            .unwrap();
+        }
+
+        json += "]}";
+        json
+    }
+}
+
+// The move leading to a node as it is labelled in a drawing.
+fn move_name(node: &TraceNode) -> String {
+    match (node.parent, node.piece_move) {
+        (None, _) => "root".to_string(),
+        (_, Some(piece_move)) => piece_move.to_string(),
+        (_, None) => "(same position)".to_string(),
+    }
+}
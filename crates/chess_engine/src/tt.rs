@@ -0,0 +1,247 @@
+use crate::Move;
+
+// The default size of the transposition table, in megabytes.
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+/// How a stored score relates to the true score of a position.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bound {
+    // The score is exact.
+    Exact,
+    // The search failed high, so the true score is at least the stored score.
+    Lower,
+    // The search failed low, so the true score is at most the stored score.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TtEntry {
+    pub hash: u64,
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub bound: Bound,
+    // The generation of the table when the entry was stored.
+    pub generation: u8,
+}
+
+/// How full the transposition table is, and how useful it has been to the current search. Used
+/// to choose a size for the table.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct TtStats {
+    // The number of entries the table can hold.
+    pub capacity: usize,
+    // How many of a sample of entries were stored by the current search, per thousand, as
+    // reported by UCI's `info hashfull`.
+    pub hashfull: u32,
+    pub probes: u64,
+    // Probes which found an entry for the position.
+    pub hits: u64,
+    // Probes which found an entry for a different position in the position's slot.
+    pub collisions: u64,
+}
+
+impl TtStats {
+    /// The fraction of the table filled by the current search, from 0 to 1.
+    pub fn fill_rate(&self) -> f64 {
+        self.hashfull as f64 / 1000.0
+    }
+
+    /// The fraction of probes which found an entry for the position, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.probes.max(1) as f64
+    }
+}
+
+/// A hash table of previously searched positions, indexed by Zobrist hash. Each search is a new
+/// generation, and entries left over from earlier generations are replaced in preference to those
+/// stored by the current search.
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    generation: u8,
+
+    // Counts of probes since the start of the current search.
+    probes: u64,
+    hits: u64,
+    collisions: u64,
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most the given number of megabytes. The number of entries is
+    /// rounded down to a power of two so that indexing is a simple mask.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Bound, TranspositionTable};
+    ///
+    /// let mut tt = TranspositionTable::new(1);
+    /// tt.store(0x1234, 3, 50, Bound::Exact, None);
+    ///
+    /// assert_eq!(tt.probe(0x1234).unwrap().score, 50);
+    /// assert!(tt.probe(0x5678).is_none());
+    /// ```
+    pub fn new(size_mb: usize) -> Self {
+        let max_entries = (size_mb.max(1) * 1024 * 1024) / std::mem::size_of::<Option<TtEntry>>();
+        let entry_count = 1 << max_entries.ilog2();
+
+        Self {
+            entries: vec![None; entry_count],
+            generation: 0,
+            probes: 0,
+            hits: 0,
+            collisions: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.generation = 0;
+    }
+
+    /// Starts a new generation, so that the entries stored so far are treated as old and replaced
+    /// before any stored from now on. Called at the start of each search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Bound, TranspositionTable};
+    ///
+    /// let mut tt = TranspositionTable::new(1);
+    /// tt.store(0x1234, 8, 50, Bound::Exact, None);
+    ///
+    /// // A shallower search of the same position doesn't replace the deeper one...
+    /// tt.store(0x1234, 2, 10, Bound::Exact, None);
+    /// assert_eq!(tt.probe(0x1234).unwrap().score, 50);
+    ///
+    /// // ...unless the deeper one is from an earlier search.
+    /// tt.new_search();
+    /// tt.store(0x1234, 2, 10, Bound::Exact, None);
+    /// assert_eq!(tt.probe(0x1234).unwrap().score, 10);
+    /// ```
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.probes = 0;
+        self.hits = 0;
+        self.collisions = 0;
+    }
+
+    /// Returns how full the table is, and how often it has been probed, hit and had collisions
+    /// since the current search began.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::{Bound, TranspositionTable};
+    ///
+    /// let mut tt = TranspositionTable::new(1);
+    /// tt.store(0x1234, 3, 50, Bound::Exact, None);
+    ///
+    /// tt.probe(0x1234);
+    /// tt.probe(0x5678);
+    ///
+    /// let stats = tt.stats();
+    /// assert_eq!((stats.probes, stats.hits), (2, 1));
+    /// assert_eq!(stats.hit_rate(), 0.5);
+    /// ```
+    pub fn stats(&self) -> TtStats {
+        TtStats {
+            capacity: self.entries.len(),
+            hashfull: self.hashfull(),
+            probes: self.probes,
+            hits: self.hits,
+            collisions: self.collisions,
+        }
+    }
+
+    /// Returns how many of the first thousand entries were stored by the current search, as an
+    /// estimate of how full the table is in permille.
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+
+        let filled = sample
+            .iter()
+            .flatten()
+            .filter(|entry| entry.generation == self.generation)
+            .count();
+
+        (filled * 1000 / sample.len()) as u32
+    }
+
+    /// Hints to the CPU that the entry for the given position will be probed soon, so that it can
+    /// be fetched into the cache while other work is done. This has no effect on the contents of
+    /// the table.
+    #[inline]
+    pub fn prefetch(&self, hash: u64) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+            let entry: *const Option<TtEntry> = &self.entries[self.index(hash)];
+            // SAFETY: Every x86-64 CPU supports SSE, and prefetching never faults.
+            unsafe { _mm_prefetch::<_MM_HINT_T0>(entry.cast()) };
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = hash;
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash as usize) & (self.entries.len() - 1)
+    }
+
+    /// Returns the entry stored for the given position, if any.
+    pub fn probe(&mut self, hash: u64) -> Option<TtEntry> {
+        let slot = self.entries[self.index(hash)];
+        let entry = slot.filter(|entry| entry.hash == hash);
+
+        self.probes += 1;
+        self.hits += entry.is_some() as u64;
+        self.collisions += (slot.is_some() && entry.is_none()) as u64;
+
+        entry
+    }
+
+    /// Stores the result of searching a position. An entry stored by the current search is only
+    /// replaced by a search of at least the same depth, while entries from earlier searches are
+    /// always replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The Zobrist hash of the position.
+    /// * `depth` - The depth the position was searched to.
+    /// * `score` - The score found by the search.
+    /// * `bound` - How `score` relates to the true score.
+    /// * `best_move` - The best move found, if any.
+    pub fn store(
+        &mut self,
+        hash: u64,
+        depth: u32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(hash);
+
+        if let Some(existing) = self.entries[index] {
+            if existing.generation == self.generation && existing.depth > depth {
+                return;
+            }
+        }
+
+        self.entries[index] = Some(TtEntry {
+            hash,
+            best_move,
+            score,
+            depth,
+            bound,
+            generation: self.generation,
+        });
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_TT_SIZE_MB)
+    }
+}
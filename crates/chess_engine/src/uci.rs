@@ -0,0 +1,694 @@
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+use vampirc_uci::{
+    parse_with_unknown, Duration, UciInfoAttribute, UciMessage, UciMove, UciOptionConfig,
+    UciSearchControl, UciSquare, UciTimeControl,
+};
+
+use crate::{
+    run_bench, BitboardOps, Engine, EngineOptions, Error, GameResult, Move, OpeningBook,
+    OptionDescription, OptionKind, PieceType, Score, Search, SearchInfo, SearchLimits,
+    SearchResult, Square, TimeLimits, DEFAULT_BENCH_DEPTH, ENGINE_OPTIONS,
+};
+
+// The depth searched to when `go` is given without any limits.
+const DEFAULT_DEPTH: u32 = 5;
+
+// The output is written to both by the handler and by the search thread.
+type SharedOutput<W> = Arc<Mutex<W>>;
+
+// Writes a message as a single line. There is nobody to report a failed write to, so errors are
+// ignored.
+fn send<W: Write>(output: &SharedOutput<W>, message: impl Display) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{}", message);
+    let _ = output.flush();
+}
+
+// Runs searches on a background thread, so that commands such as `stop` can still be handled.
+struct SearchThread<W: Write + Send + 'static> {
+    output: SharedOutput<W>,
+    // The search is moved to the thread while it runs, and handed back when it finishes.
+    search: Option<Search>,
+    handle: Option<JoinHandle<Search>>,
+    stop_flag: Arc<AtomicBool>,
+    ponder_flag: Arc<AtomicBool>,
+}
+
+impl<W: Write + Send + 'static> SearchThread<W> {
+    // The search's decisions are sent as `info string` while `debug` is set.
+    fn new(output: SharedOutput<W>, debug: Arc<AtomicBool>) -> Self {
+        let mut search = Search::new();
+
+        let info_output = output.clone();
+        search.set_info_callback(move |info: &SearchInfo| send(&info_output, info_message(info)));
+
+        let debug_output = output.clone();
+        search.set_debug_callback(move |message: &str| {
+            if debug.load(Ordering::Relaxed) {
+                send(&debug_output, UciMessage::info_string(message.to_string()));
+            }
+        });
+
+        let stop_flag = search.stop_flag();
+        let ponder_flag = search.ponder_flag();
+
+        Self {
+            output,
+            search: Some(search),
+            handle: None,
+            stop_flag,
+            ponder_flag,
+        }
+    }
+
+    // Starts searching the current position of the game, reporting the best move once done. When
+    // pondering, the time limits only apply once the predicted move has been played.
+    fn start(&mut self, engine: Engine, limits: SearchLimits, ponder: bool) {
+        self.stop();
+
+        let mut search = self.search.take().unwrap();
+        self.stop_flag.store(false, Ordering::Relaxed);
+        self.ponder_flag.store(ponder, Ordering::Relaxed);
+
+        let output = self.output.clone();
+
+        self.handle = Some(std::thread::spawn(move || {
+            let result = search.best_move_in_game(&engine, &limits);
+
+            send(&output, best_move_message(&result));
+
+            search
+        }));
+    }
+
+    // The opponent played the predicted move, so carry on with a normal timed search.
+    fn ponder_hit(&mut self) {
+        self.ponder_flag.store(false, Ordering::Relaxed);
+    }
+
+    // Stops any running search, returning the search so that it can be configured.
+    fn search(&mut self) -> &mut Search {
+        self.stop();
+        self.search.as_mut().unwrap()
+    }
+
+    // Stops any running search, waiting for it to report its best move.
+    fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stop_flag.store(true, Ordering::Relaxed);
+            self.search = Some(handle.join().unwrap());
+        }
+
+        self.ponder_flag.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Plays as a UCI engine, reading commands a line at a time and writing the responses to the
+/// given output. Searches run on a background thread, so that `stop` and other commands are
+/// still handled while thinking.
+///
+/// # Examples
+///
+/// ```
+/// use chess_engine::uci::UciHandler;
+///
+/// let mut uci = UciHandler::new(Vec::new());
+///
+/// for line in ["uci", "isready", "position startpos moves e2e4", "go depth 3", "quit"] {
+///     uci.handle_line(line);
+/// }
+///
+/// let output = String::from_utf8(uci.output().clone()).unwrap();
+/// assert!(output.contains("uciok\n"));
+/// assert!(output.contains("readyok\n"));
+/// assert!(output.contains("bestmove "));
+/// ```
+pub struct UciHandler<W: Write + Send + 'static> {
+    output: SharedOutput<W>,
+    engine: Engine,
+    options: EngineOptions,
+    search_thread: SearchThread<W>,
+    // The opening book, loaded while the `OwnBook` option is set.
+    book: Option<OpeningBook>,
+    // Set once the `uci` command has been received, before which other commands are ignored.
+    uci_mode: bool,
+    // Set by `debug on`, after which the engine explains itself in `info string` messages.
+    debug: Arc<AtomicBool>,
+}
+
+impl<W: Write + Send + 'static> UciHandler<W> {
+    pub fn new(output: W) -> Self {
+        let output = Arc::new(Mutex::new(output));
+        let debug = Arc::new(AtomicBool::new(false));
+
+        Self {
+            output: output.clone(),
+            engine: Engine::default(),
+            options: EngineOptions::default(),
+            search_thread: SearchThread::new(output, debug.clone()),
+            book: None,
+            uci_mode: false,
+            debug,
+        }
+    }
+
+    /// Locks and returns the output, which is also written to by any running search.
+    pub fn output(&self) -> MutexGuard<'_, W> {
+        self.output.lock().unwrap()
+    }
+
+    /// Handles commands from the input until `quit` is received or the input ends.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if !self.handle_line(&line) {
+                return;
+            }
+        }
+
+        // The input was closed, so there will be no more commands.
+        self.search_thread.stop();
+    }
+
+    /// Handles a single line of input. Returns false once `quit` has been received, after which
+    /// any search has been stopped and no more commands should be given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chess_engine::uci::UciHandler;
+    ///
+    /// let mut uci = UciHandler::new(Vec::new());
+    /// uci.handle_line("uci");
+    ///
+    /// // The moves are played from the FEN with Black to move, and only Black's king can reply.
+    /// uci.handle_line("position fen 4k3/8/8/8/8/8/4P3/4K3 b - - 0 1 moves e8d7 e2e4");
+    /// uci.handle_line("go depth 1");
+    /// uci.handle_line("quit");
+    ///
+    /// let output = String::from_utf8(uci.output().clone()).unwrap();
+    /// assert!(output.contains("bestmove d7"));
+    ///
+    /// // With `UCI_Chess960` set, castling rights may be given as files and castling is written as
+    /// // the king capturing its rook. Once White has castled, Black's king has one move.
+    /// let mut uci = UciHandler::new(Vec::new());
+    /// uci.handle_line("uci");
+    /// uci.handle_line("setoption name UCI_Chess960 value true");
+    /// uci.handle_line("position fen 7k/7p/7P/8/8/8/8/1R4K1 w B - 0 1 moves g1b1");
+    /// uci.handle_line("go depth 1");
+    /// uci.handle_line("quit");
+    ///
+    /// let output = String::from_utf8(uci.output().clone()).unwrap();
+    /// assert!(output.contains("bestmove h8g8"));
+    /// ```
+    ///
+    /// Every `go` gets a `bestmove` reply, even when the game is over and there is no move to
+    /// play.
+    ///
+    /// ```
+    /// use chess_engine::uci::UciHandler;
+    ///
+    /// let mut uci = UciHandler::new(Vec::new());
+    /// uci.handle_line("uci");
+    /// uci.handle_line("position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+    /// uci.handle_line("go depth 3");
+    /// uci.handle_line("quit");
+    ///
+    /// let output = String::from_utf8(uci.output().clone()).unwrap();
+    /// assert!(output.contains("info string game over: WhiteWins\n"));
+    /// assert!(output.ends_with("bestmove 0000\n"));
+    /// ```
+    ///
+    /// `go perft <depth>` counts the leaf nodes of the tree of legal moves below each move, and in
+    /// total, for checking move generation.
+    ///
+    /// ```
+    /// use chess_engine::uci::UciHandler;
+    ///
+    /// let mut uci = UciHandler::new(Vec::new());
+    /// uci.handle_line("uci");
+    /// uci.handle_line("position startpos moves e2e4");
+    /// uci.handle_line("go perft 3");
+    ///
+    /// let output = String::from_utf8(uci.output().clone()).unwrap();
+    /// assert!(output.contains("\ne7e5: 835\n"));
+    /// assert!(output.contains("\nNodes searched: 13160\n"));
+    /// ```
+    ///
+    /// For debugging by hand, `d` shows the board with its FEN, hash key and any pieces giving
+    /// check, `eval` breaks down the evaluation, and `flip` swaps the colours of the position.
+    ///
+    /// ```
+    /// use chess_engine::uci::UciHandler;
+    ///
+    /// let mut uci = UciHandler::new(Vec::new());
+    /// uci.handle_line("uci");
+    /// uci.handle_line("position fen 4k3/8/8/8/8/8/4P3/4K2r w - - 0 1");
+    /// uci.handle_line("d");
+    /// uci.handle_line("flip");
+    /// uci.handle_line("d");
+    ///
+    /// let output = String::from_utf8(uci.output().clone()).unwrap();
+    /// assert!(output.contains("Fen: 4k3/8/8/8/8/8/4P3/4K2r w - - 0 1\n"));
+    /// assert!(output.contains("Checkers: h1\n"));
+    /// assert!(output.contains("Fen: 4k2R/4p3/8/8/8/8/8/4K3 b - - 0 1\n"));
+    /// assert!(output.contains("Checkers: h8\n"));
+    /// ```
+    pub fn handle_line(&mut self, line: &str) -> bool {
+        // The parser panics on some commands with trailing whitespace, such as `go `.
+        let line = line.trim();
+
+        for message in parse_with_unknown(line) {
+            match message {
+                UciMessage::Uci => self.uci(),
+                UciMessage::Quit => {
+                    self.search_thread.stop();
+                    return false;
+                }
+                UciMessage::Unknown(..) if line.trim() == "eval" => {
+                    // Not part of UCI, but useful for checking the evaluation by hand.
+                    send(&self.output, self.engine.evaluate_verbose());
+                }
+                UciMessage::Unknown(..) if line.trim() == "d" => {
+                    // Nor are this and `flip`, which Stockfish also understands.
+                    send(&self.output, describe_position(&self.engine));
+                }
+                UciMessage::Unknown(..) if line.trim() == "flip" => {
+                    let mirrored = self.engine.mirrored();
+                    self.engine.set_position(mirrored);
+                }
+                UciMessage::Unknown(..) if line.trim() == "bench" => {
+                    // Also not part of UCI. The search thread isn't used, so nothing it has learnt
+                    // affects the node count.
+                    send(&self.output, run_bench(DEFAULT_BENCH_DEPTH));
+                }
+                _ if !self.uci_mode => {}
+                UciMessage::Debug(debug) => self.debug.store(debug, Ordering::Relaxed),
+                UciMessage::IsReady => {
+                    // Immediately send a readyok message back, no reason not to at the moment.
+                    send(&self.output, UciMessage::ReadyOk);
+                }
+                UciMessage::SetOption { name, value } => {
+                    self.set_option(&name, value.as_deref().unwrap_or_default())
+                }
+                UciMessage::UciNewGame => {
+                    // Don't let anything from the last game affect the next one.
+                    self.search_thread.search().new_game();
+                    self.engine.new_game();
+                }
+                UciMessage::Position {
+                    startpos,
+                    fen,
+                    moves,
+                } => {
+                    let fen = fen.filter(|_| !startpos);
+                    let moves: Vec<String> = moves.iter().map(UciMove::to_string).collect();
+
+                    self.set_position(fen.as_ref().map(|fen| fen.as_str()), &moves);
+                }
+                UciMessage::Unknown(..) if line.split_whitespace().next() == Some("position") => {
+                    // The parser rejects FENs with castling rights given as files, as Chess960
+                    // positions are, so read those here.
+                    let mut tokens = line.split_whitespace().skip(1);
+                    let fen: Vec<&str> = match tokens.next() {
+                        Some("fen") => tokens
+                            .by_ref()
+                            .take_while(|&token| token != "moves")
+                            .collect(),
+                        _ => {
+                            tokens.next();
+                            vec![]
+                        }
+                    };
+                    let moves: Vec<String> = tokens.map(str::to_string).collect();
+
+                    let fen = fen.join(" ");
+
+                    self.set_position(Some(fen.as_str()).filter(|fen| !fen.is_empty()), &moves);
+                }
+                UciMessage::Go { .. } | UciMessage::Unknown(..)
+                    if line.split_whitespace().take(2).eq(["go", "perft"]) =>
+                {
+                    // Not part of UCI, but Stockfish supports it for checking move generation.
+                    self.perft(line.split_whitespace().nth(2));
+                }
+                UciMessage::Go {
+                    time_control,
+                    search_control,
+                } => {
+                    // The parser drops `ponder` when it is given along with the clock times, so
+                    // look for it in the command itself.
+                    let ponder = line
+                        .split_whitespace()
+                        .skip(1)
+                        .any(|token| token == "ponder");
+
+                    self.go(time_control, search_control, ponder);
+                }
+                UciMessage::PonderHit => self.search_thread.ponder_hit(),
+                UciMessage::Stop => {
+                    // Stop thinking, but keep the current best move. If pondering, the GUI
+                    // discards this move since the prediction was wrong.
+                    self.search_thread.stop();
+                }
+                _ => {}
+            }
+        }
+
+        true
+    }
+
+    fn uci(&mut self) {
+        // The engine is now running in UCI mode.
+        self.uci_mode = true;
+
+        // Send identification message, and report as ready.
+        send(&self.output, UciMessage::id_name(self.engine.name()));
+        send(&self.output, UciMessage::id_author(self.engine.author()));
+
+        for description in &ENGINE_OPTIONS {
+            send(
+                &self.output,
+                UciMessage::Option(option_to_uci_option(description)),
+            );
+        }
+
+        send(&self.output, UciMessage::UciOk);
+    }
+
+    // Sets up the given position, or the initial position if no FEN is given. The moves are played
+    // from it with whichever side it has to move, and are meaningless if it couldn't be set up.
+    fn set_position(&mut self, fen: Option<&str>, moves: &[String]) {
+        if let Some(fen) = fen {
+            if let Err(error) = self.engine.set_fen(fen) {
+                send(
+                    &self.output,
+                    UciMessage::info_string(Error::from(error).to_string()),
+                );
+                return;
+            }
+        } else {
+            self.engine.set_initial_position();
+        }
+
+        // Stop at the first move which can't be played, rather than corrupting the position.
+        for notation in moves {
+            let result = Move::from_uci_str(notation)
+                .ok_or_else(|| Error::InvalidMove(notation.clone()))
+                .and_then(|piece_move| self.engine.try_make_move(piece_move).map_err(Error::from));
+
+            if let Err(error) = result {
+                send(&self.output, UciMessage::info_string(error.to_string()));
+                break;
+            }
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) {
+        if let Err(error) = self.options.set(name, value) {
+            send(&self.output, UciMessage::info_string(error.to_string()));
+            return;
+        }
+
+        if name.eq_ignore_ascii_case("Hash") {
+            self.search_thread
+                .search()
+                .set_hash_size(self.options.hash_size_mb);
+        }
+
+        if name.eq_ignore_ascii_case("Skill Level") {
+            self.search_thread.search().config.skill_level = self.options.skill_level;
+        }
+
+        if name.eq_ignore_ascii_case("Contempt") {
+            self.search_thread.search().config.contempt = self.options.contempt;
+        }
+
+        if name.eq_ignore_ascii_case("Deterministic") {
+            self.search_thread.search().config.deterministic = self.options.deterministic;
+        }
+
+        if name.eq_ignore_ascii_case("Random Plies") {
+            self.search_thread.search().config.random_plies = self.options.random_plies;
+        }
+
+        if name.eq_ignore_ascii_case("Random Margin") {
+            self.search_thread.search().config.random_margin = self.options.random_margin;
+        }
+
+        if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.engine.set_chess960(self.options.chess960);
+        }
+
+        if name.eq_ignore_ascii_case("OwnBook") || name.eq_ignore_ascii_case("BookFile") {
+            self.load_book();
+        }
+    }
+
+    fn load_book(&mut self) {
+        self.book = None;
+
+        if !self.options.own_book {
+            return;
+        }
+
+        match OpeningBook::load(&self.options.book_file) {
+            Ok(book) => self.book = Some(book),
+            Err(error) => send(
+                &self.output,
+                UciMessage::info_string(format!(
+                    "failed to load book '{}': {}",
+                    self.options.book_file, error
+                )),
+            ),
+        }
+    }
+
+    // Sends a message explaining what the engine is doing, if `debug` is on.
+    fn send_debug(&self, message: String) {
+        if self.debug.load(Ordering::Relaxed) {
+            send(&self.output, UciMessage::info_string(message));
+        }
+    }
+
+    // Counts the leaf nodes below each legal move to the given depth, then the total, as Stockfish
+    // does for `go perft`.
+    fn perft(&mut self, depth: Option<&str>) {
+        let Some(depth) = depth.and_then(|depth| depth.parse::<u32>().ok()) else {
+            send(
+                &self.output,
+                UciMessage::info_string("go perft needs a depth".to_string()),
+            );
+            return;
+        };
+
+        let mut total = if depth == 0 { 1 } else { 0 };
+
+        for piece_move in self.engine.legal_moves().iter().filter(|_| depth > 0) {
+            let mut child = self.engine.position().clone();
+            child.make_move(*piece_move);
+
+            let nodes = child.perft(depth - 1);
+            total += nodes;
+
+            send(&self.output, format!("{}: {}", piece_move, nodes));
+        }
+
+        send(&self.output, format!("\nNodes searched: {}", total));
+    }
+
+    fn go(
+        &mut self,
+        time_control: Option<UciTimeControl>,
+        search_control: Option<UciSearchControl>,
+        ponder: bool,
+    ) {
+        let result = self.engine.game_result();
+        if result != GameResult::Ongoing {
+            send(
+                &self.output,
+                UciMessage::info_string(format!("game over: {:?}", result)),
+            );
+        }
+
+        // Search for and return the next move.
+        let mut limits = SearchLimits::default();
+
+        match time_control {
+            Some(UciTimeControl::Infinite) => limits.infinite = true,
+            Some(time_control) => limits.time = uci_time_control_to_time_limits(time_control),
+            None => {}
+        }
+
+        limits.time.move_overhead = self.options.move_overhead;
+
+        if let Some(search_control) = search_control {
+            limits.depth = search_control.depth.map(u32::from);
+            limits.nodes = search_control.nodes;
+            limits.mate = search_control.mate.map(u32::from);
+            limits.search_moves = search_control
+                .search_moves
+                .iter()
+                .filter_map(uci_move_to_move)
+                .collect();
+        }
+
+        // Play straight from the book if possible, unless the GUI wants the engine to think.
+        if let Some(book) = &mut self.book {
+            if !ponder && !limits.infinite && limits.search_moves.is_empty() {
+                if let Some(book_move) = book.pick_move(&self.engine) {
+                    self.send_debug(format!("playing {} from the book", book_move));
+                    send(
+                        &self.output,
+                        UciMessage::BestMove {
+                            best_move: move_to_uci_move(&book_move),
+                            ponder: None,
+                        },
+                    );
+                    return;
+                }
+            }
+        }
+
+        // A bare `go` searches to a fixed depth rather than forever. When pondering, keep
+        // thinking until the GUI says whether the predicted move was played.
+        if limits.is_unlimited() && !limits.infinite && !ponder {
+            limits.depth = Some(DEFAULT_DEPTH);
+        }
+
+        self.search_thread
+            .start(self.engine.clone(), limits, ponder);
+    }
+}
+
+// Describes the position for the `d` command: a diagram of the board, then its FEN, its hash key
+// and the squares of any pieces giving check.
+fn describe_position(engine: &Engine) -> String {
+    let board = engine.board();
+    let side = engine.side_to_move();
+    let king = board.bitboard_by_piece[PieceType::King.val()] & board.bitboard_by_side[side.val()];
+
+    let checkers: Vec<String> = match king {
+        0 => vec![],
+        king => board
+            .attackers_to(king.trailing_zeros() as usize, side.flip())
+            .iter()
+            .map(|checker| Square::new(checker.trailing_zeros() as usize).to_string())
+            .collect(),
+    };
+
+    format!(
+        "{}\nFen: {}\nKey: {:016X}\nCheckers: {}",
+        engine.position(),
+        engine.fen(),
+        engine.hash(),
+        checkers.join(" ")
+    )
+}
+
+fn info_message(info: &SearchInfo) -> UciMessage {
+    let score = match Score::from_search_score(info.score) {
+        Score::Centipawns(cp) => UciInfoAttribute::from_centipawns(cp),
+        Score::Mate(moves) => UciInfoAttribute::from_mate(moves as i8),
+    };
+
+    UciMessage::Info(vec![
+        UciInfoAttribute::Depth(info.depth as u8),
+        UciInfoAttribute::SelDepth(info.seldepth as u8),
+        score,
+        UciInfoAttribute::Nodes(info.nodes),
+        UciInfoAttribute::Nps(info.nps),
+        UciInfoAttribute::HashFull(info.hashfull as u16),
+        UciInfoAttribute::Time(Duration::milliseconds(info.time.as_millis() as i64)),
+        UciInfoAttribute::Pv(info.pv.iter().map(move_to_uci_move).collect()),
+    ])
+}
+
+// UCI expects a `bestmove` reply to every `go`, so when the game is over the null move is sent, as
+// other engines do.
+fn best_move_message(result: &SearchResult) -> String {
+    match result.best_move {
+        Some(best_move) => UciMessage::BestMove {
+            best_move: move_to_uci_move(&best_move),
+            ponder: result.ponder_move.as_ref().map(move_to_uci_move),
+        }
+        .to_string(),
+        None => "bestmove 0000".to_string(),
+    }
+}
+
+fn uci_time_control_to_time_limits(time_control: UciTimeControl) -> TimeLimits {
+    // UCI times may be negative if the GUI is lagging, so treat these as no time left.
+    let to_std = |duration: Duration| duration.to_std().unwrap_or_default();
+
+    match time_control {
+        UciTimeControl::TimeLeft {
+            white_time,
+            black_time,
+            white_increment,
+            black_increment,
+            moves_to_go,
+        } => TimeLimits {
+            white_time: white_time.map(to_std),
+            black_time: black_time.map(to_std),
+            white_increment: white_increment.map(to_std),
+            black_increment: black_increment.map(to_std),
+            moves_to_go: moves_to_go.map(u32::from),
+            ..Default::default()
+        },
+        UciTimeControl::MoveTime(move_time) => TimeLimits {
+            move_time: Some(to_std(move_time)),
+            ..Default::default()
+        },
+        _ => TimeLimits::default(),
+    }
+}
+
+fn option_to_uci_option(description: &OptionDescription) -> UciOptionConfig {
+    let name = description.name.to_string();
+
+    match description.kind {
+        OptionKind::Check { default } => UciOptionConfig::Check {
+            name,
+            default: Some(default),
+        },
+        OptionKind::Spin { default, min, max } => UciOptionConfig::Spin {
+            name,
+            default: Some(default),
+            min: Some(min),
+            max: Some(max),
+        },
+        OptionKind::String { default } => UciOptionConfig::String {
+            name,
+            default: Some(default.to_string()),
+        },
+    }
+}
+
+// Both kinds of move are written the same way, so convert between them through their notation.
+fn uci_move_to_move(uci_move: &UciMove) -> Option<Move> {
+    Move::from_uci_str(&uci_move.to_string())
+}
+
+fn move_to_uci_move(engine_move: &Move) -> UciMove {
+    let notation = engine_move.to_string();
+
+    let square = |name: &str| UciSquare {
+        file: name.as_bytes()[0] as char,
+        rank: name.as_bytes()[1] - b'0',
+    };
+
+    UciMove {
+        from: square(&notation[0..2]),
+        to: square(&notation[2..4]),
+        promotion: notation.get(4..).and_then(|piece| piece.parse().ok()),
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * A C interface to the engine. Link against the chess_engine_ffi library built by
+ * `cargo build --release -p chess_engine_ffi`.
+ *
+ * Functions returning int give 0 on success and -1 on failure. Strings are written NUL-terminated
+ * into a caller's buffer, as snprintf does, and the length of the full string is returned.
+ */
+
+#ifndef CHESS_ENGINE_H
+#define CHESS_ENGINE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ChessEngine ChessEngine;
+
+/* The limits of a search. A limit of 0 means no limit. */
+typedef struct ChessSearchLimits {
+    uint32_t depth;
+    uint64_t nodes;
+    uint64_t move_time_ms;
+} ChessSearchLimits;
+
+/* Creates an engine with the initial position set up. Free it with chess_engine_free. */
+ChessEngine *chess_engine_new(void);
+void chess_engine_free(ChessEngine *engine);
+
+/* Returns to the initial position and clears what the search learnt from the last game. */
+void chess_engine_new_game(ChessEngine *engine);
+
+int chess_engine_set_fen(ChessEngine *engine, const char *fen);
+
+/* Makes a move in coordinate notation, such as "e2e4" or "e7e8q". */
+int chess_engine_make_move(ChessEngine *engine, const char *move);
+
+/* Writes the legal moves, in coordinate notation separated by spaces. */
+size_t chess_engine_legal_moves(const ChessEngine *engine, char *buffer, size_t length);
+
+/* Searches the current position, blocking until done. Fails if there is no legal move. */
+int chess_engine_search(ChessEngine *engine, const ChessSearchLimits *limits);
+
+/* Writes the best move found by the last search, or an empty string if there was none. */
+size_t chess_engine_best_move(const ChessEngine *engine, char *buffer, size_t length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,253 @@
+//! A C interface to the engine, for embedding it in GUIs written in C, C++, C# and the like. The
+//! declarations are in `include/chess_engine.h`.
+//!
+//! An engine is created with [`chess_engine_new`] and must be freed with [`chess_engine_free`].
+//! Functions returning `int` give 0 on success and -1 on failure. Strings are returned by writing
+//! them, NUL-terminated, into a buffer supplied by the caller, as `snprintf` does: the length of
+//! the full string is returned, so a result at least as long as the buffer was truncated.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::ffi::{c_char, CStr};
+//! use chess_engine_ffi::*;
+//!
+//! unsafe {
+//!     let engine = chess_engine_new();
+//!     assert_eq!(chess_engine_make_move(engine, c"e2e4".as_ptr()), 0);
+//!     assert_eq!(chess_engine_make_move(engine, c"e2e4".as_ptr()), -1);
+//!
+//!     let limits = ChessSearchLimits {
+//!         depth: 4,
+//!         nodes: 0,
+//!         move_time_ms: 0,
+//!     };
+//!     assert_eq!(chess_engine_search(engine, &limits), 0);
+//!
+//!     let mut best_move = [0 as c_char; 8];
+//!     let length = chess_engine_best_move(engine, best_move.as_mut_ptr(), best_move.len());
+//!     assert_eq!(length, 4);
+//!     assert_eq!(CStr::from_ptr(best_move.as_ptr()).to_str().unwrap().len(), 4);
+//!
+//!     chess_engine_free(engine);
+//! }
+//! ```
+
+use std::ffi::{c_char, c_int, CStr};
+use std::ptr;
+use std::time::Duration;
+
+use chess_engine::{Engine, Move, Search, SearchLimits, TimeLimits};
+
+/// An engine and the position it is playing from. Opaque to C.
+pub struct ChessEngine {
+    engine: Engine,
+    search: Search,
+    best_move: Option<Move>,
+}
+
+/// The limits of a search. A limit of 0 means no limit, and a search with no limits at all runs
+/// to the maximum depth.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ChessSearchLimits {
+    pub depth: u32,
+    pub nodes: u64,
+    pub move_time_ms: u64,
+}
+
+/// Creates an engine with the initial position set up.
+#[no_mangle]
+pub extern "C" fn chess_engine_new() -> *mut ChessEngine {
+    let mut engine = Engine::default();
+    engine.set_initial_position();
+
+    Box::into_raw(Box::new(ChessEngine {
+        engine,
+        search: Search::new(),
+        best_move: None,
+    }))
+}
+
+/// Frees an engine. Does nothing if given null.
+///
+/// # Safety
+///
+/// `engine` must be null or have come from [`chess_engine_new`], and must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_free(engine: *mut ChessEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Returns to the initial position and forgets anything the search learnt from the last game.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_new_game(engine: *mut ChessEngine) {
+    let Some(engine) = engine.as_mut() else {
+        return;
+    };
+
+    engine.engine.set_initial_position();
+    engine.search.new_game();
+    engine.best_move = None;
+}
+
+/// Sets up the position given in FEN, leaving the position unchanged if it can't be read.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`], and `fen` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_set_fen(
+    engine: *mut ChessEngine,
+    fen: *const c_char,
+) -> c_int {
+    let (Some(engine), Some(fen)) = (engine.as_mut(), to_str(fen)) else {
+        return -1;
+    };
+
+    match engine.engine.set_fen(fen) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Makes a move given in coordinate notation, such as `e2e4` or `e7e8q`, failing if it can't be
+/// read or isn't legal.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`], and `notation` must be a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_make_move(
+    engine: *mut ChessEngine,
+    notation: *const c_char,
+) -> c_int {
+    let (Some(engine), Some(notation)) = (engine.as_mut(), to_str(notation)) else {
+        return -1;
+    };
+
+    match Move::from_uci_str(notation).map(|piece_move| engine.engine.try_make_move(piece_move)) {
+        Some(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+/// Writes the legal moves in the current position into `buffer`, in coordinate notation
+/// separated by spaces.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`], and `buffer` must be valid for writes of
+/// `length` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_legal_moves(
+    engine: *const ChessEngine,
+    buffer: *mut c_char,
+    length: usize,
+) -> usize {
+    let Some(engine) = engine.as_ref() else {
+        return 0;
+    };
+
+    let moves: Vec<String> = engine
+        .engine
+        .legal_moves()
+        .iter()
+        .map(|piece_move| piece_move.to_string())
+        .collect();
+
+    write_str(&moves.join(" "), buffer, length)
+}
+
+/// Searches the current position within the given limits, keeping the best move found to be
+/// read with [`chess_engine_best_move`]. Blocks until the search is done, and fails if there is
+/// no legal move.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`], and `limits` must point to a
+/// `ChessSearchLimits`.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_search(
+    engine: *mut ChessEngine,
+    limits: *const ChessSearchLimits,
+) -> c_int {
+    let (Some(engine), Some(limits)) = (engine.as_mut(), limits.as_ref()) else {
+        return -1;
+    };
+
+    let limits = SearchLimits {
+        depth: (limits.depth > 0).then_some(limits.depth),
+        nodes: (limits.nodes > 0).then_some(limits.nodes),
+        time: TimeLimits {
+            move_time: (limits.move_time_ms > 0)
+                .then(|| Duration::from_millis(limits.move_time_ms)),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    engine.best_move = engine
+        .search
+        .best_move_in_game(&engine.engine, &limits)
+        .best_move;
+
+    if engine.best_move.is_some() {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Writes the best move found by the last search into `buffer`, in coordinate notation, or an
+/// empty string if there was none.
+///
+/// # Safety
+///
+/// `engine` must have come from [`chess_engine_new`], and `buffer` must be valid for writes of
+/// `length` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chess_engine_best_move(
+    engine: *const ChessEngine,
+    buffer: *mut c_char,
+    length: usize,
+) -> usize {
+    let Some(engine) = engine.as_ref() else {
+        return 0;
+    };
+
+    let best_move = engine
+        .best_move
+        .map_or(String::new(), |piece_move| piece_move.to_string());
+
+    write_str(&best_move, buffer, length)
+}
+
+// Reads a string passed from C, which must be UTF-8.
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(string).to_str().ok()
+}
+
+// Copies as much of `string` as fits into `buffer`, always NUL-terminating it unless `length` is
+// 0, and returns the length of the whole string.
+unsafe fn write_str(string: &str, buffer: *mut c_char, length: usize) -> usize {
+    if !buffer.is_null() && length > 0 {
+        let copied = string.len().min(length - 1);
+
+        ptr::copy_nonoverlapping(string.as_ptr(), buffer.cast(), copied);
+        *buffer.add(copied) = 0;
+    }
+
+    string.len()
+}
@@ -0,0 +1,277 @@
+//! Serves the engine over HTTP: `chess-engine-server [port]`, listening on port 8080 of the local
+//! machine by default. Requests and responses are JSON, and scores are in centipawns from the
+//! point of view of the side to move.
+//!
+//! * `GET /sessions` lists the IDs of the boards being analysed.
+//! * `POST /sessions` with `{"fen": ..., "moves": [...]}`, both optional, sets up a board.
+//! * `GET /sessions/{id}` gives a board's FEN, side to move, legal moves and result.
+//! * `DELETE /sessions/{id}` removes a board.
+//! * `GET /sessions/{id}/moves` gives a board's legal moves, in coordinate notation and SAN.
+//! * `POST /sessions/{id}/moves` with `{"move": "e2e4"}` plays a move, given either way.
+//! * `GET /sessions/{id}/eval` gives a board's static evaluation.
+//! * `POST /sessions/{id}/search` with `{"depth": ..., "nodes": ..., "movetime": ...}`, all
+//!   optional, searches a board and gives the best move, score and principal variation.
+//!
+//! Each board is searched on a thread of its own, so searches of different boards run at the same
+//! time, while requests for a board being searched wait for the search to finish.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chess_engine::{Engine, Move, Score, SearchLimits, TimeLimits};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::session::{Session, Sessions};
+
+mod session;
+
+const DEFAULT_PORT: u16 = 8080;
+
+// The most boards kept at once. Sessions can't be added while this many exist.
+const MAX_SESSIONS: usize = 64;
+
+// The time searched for when a request gives no limits, and the most any search may take.
+const DEFAULT_MOVE_TIME_MS: u64 = 1000;
+const MAX_MOVE_TIME_MS: u64 = 60_000;
+
+#[tokio::main]
+async fn main() {
+    let port = match std::env::args().nth(1).map(|port| port.parse()) {
+        None => DEFAULT_PORT,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            eprintln!("usage: chess-engine-server [port]");
+            std::process::exit(1);
+        }
+    };
+
+    let app = Router::new()
+        .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/{id}", get(get_session).delete(delete_session))
+        .route("/sessions/{id}/moves", get(legal_moves).post(play_move))
+        .route("/sessions/{id}/eval", get(evaluate))
+        .route("/sessions/{id}/search", post(search))
+        .with_state(Arc::new(Sessions::default()));
+
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("couldn't listen on port {}: {}", port, error);
+            std::process::exit(1);
+        }
+    };
+
+    eprintln!("listening on port {}", port);
+
+    if let Err(error) = axum::serve(listener, app).await {
+        eprintln!("server stopped: {}", error);
+        std::process::exit(1);
+    }
+}
+
+/// A request which couldn't be answered, sent as `{"error": ...}` with its status.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self(StatusCode::BAD_REQUEST, message.into())
+    }
+
+    fn not_found(id: u64) -> Self {
+        Self(StatusCode::NOT_FOUND, format!("no session {}", id))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type SharedSessions = Arc<Sessions>;
+
+#[derive(Deserialize, Default)]
+struct NewSession {
+    fen: Option<String>,
+    #[serde(default)]
+    moves: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    #[serde(rename = "move")]
+    notation: String,
+}
+
+#[derive(Deserialize, Default)]
+struct SearchRequest {
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    movetime: Option<u64>,
+}
+
+impl SearchRequest {
+    // The limits to search within. Searches are always timed, so that one request can't tie up a
+    // board for ever.
+    fn limits(&self) -> SearchLimits {
+        let move_time = match (self.depth, self.nodes, self.movetime) {
+            (None, None, None) => DEFAULT_MOVE_TIME_MS,
+            (_, _, move_time) => move_time.unwrap_or(MAX_MOVE_TIME_MS),
+        };
+
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: TimeLimits {
+                move_time: Some(Duration::from_millis(move_time.min(MAX_MOVE_TIME_MS))),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+async fn list_sessions(State(sessions): State<SharedSessions>) -> Json<Value> {
+    Json(json!({ "sessions": sessions.ids() }))
+}
+
+async fn create_session(
+    State(sessions): State<SharedSessions>,
+    request: Option<Json<NewSession>>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let mut engine = Engine::default();
+
+    match &request.fen {
+        Some(fen) => engine
+            .set_fen(fen)
+            .map_err(|error| ApiError::bad_request(format!("invalid FEN: {}", error)))?,
+        None => engine.set_initial_position(),
+    }
+
+    let mut session = Session::new(engine);
+
+    for notation in &request.moves {
+        session.play(notation).map_err(ApiError::bad_request)?;
+    }
+
+    // The ID is only known once the session is added, which takes the session.
+    let mut state = session.state(0);
+    let id = sessions.insert(session, MAX_SESSIONS).ok_or_else(|| {
+        ApiError(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("there are already {} sessions", MAX_SESSIONS),
+        )
+    })?;
+
+    state["id"] = json!(id);
+
+    Ok((StatusCode::CREATED, Json(state)))
+}
+
+async fn get_session(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+) -> Result<Json<Value>, ApiError> {
+    let session = sessions.get(id).ok_or(ApiError::not_found(id))?;
+    let session = session.lock().await;
+
+    Ok(Json(session.state(id)))
+}
+
+async fn delete_session(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    match sessions.remove(id) {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(ApiError::not_found(id)),
+    }
+}
+
+async fn legal_moves(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+) -> Result<Json<Value>, ApiError> {
+    let session = sessions.get(id).ok_or(ApiError::not_found(id))?;
+    let session = session.lock().await;
+    let engine = &session.engine;
+
+    let moves: Vec<Value> = engine
+        .legal_moves()
+        .iter()
+        .map(|&piece_move| {
+            json!({ "uci": piece_move.to_string(), "san": engine.move_to_san(piece_move) })
+        })
+        .collect();
+
+    Ok(Json(json!({ "moves": moves })))
+}
+
+async fn play_move(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+    Json(request): Json<MoveRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let session = sessions.get(id).ok_or(ApiError::not_found(id))?;
+    let mut session = session.lock().await;
+
+    session
+        .play(&request.notation)
+        .map_err(ApiError::bad_request)?;
+
+    Ok(Json(session.state(id)))
+}
+
+async fn evaluate(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+) -> Result<Json<Value>, ApiError> {
+    let session = sessions.get(id).ok_or(ApiError::not_found(id))?;
+    let session = session.lock().await;
+
+    Ok(Json(json!({ "eval": session.engine.evaluate() })))
+}
+
+async fn search(
+    State(sessions): State<SharedSessions>,
+    Path(id): Path<u64>,
+    request: Option<Json<SearchRequest>>,
+) -> Result<Json<Value>, ApiError> {
+    let limits = request
+        .map(|Json(request)| request)
+        .unwrap_or_default()
+        .limits();
+    let session = sessions.get(id).ok_or(ApiError::not_found(id))?;
+    let mut session = session.lock_owned().await;
+
+    // The search blocks, so it runs away from the threads answering requests.
+    let response = tokio::task::spawn_blocking(move || {
+        let Session { engine, search } = &mut *session;
+        let result = search.best_move_in_game(engine, &limits);
+
+        let score = match Score::from_search_score(result.score) {
+            Score::Centipawns(centipawns) => json!({ "cp": centipawns }),
+            Score::Mate(moves) => json!({ "mate": moves }),
+        };
+
+        json!({
+            "bestmove": result.best_move.map(|best_move| best_move.to_string()),
+            "ponder": result.ponder_move.map(|ponder| ponder.to_string()),
+            "score": score,
+            "depth": result.depth,
+            "nodes": result.nodes,
+            "pv": result.pv.iter().map(Move::to_string).collect::<Vec<_>>(),
+        })
+    })
+    .await
+    .map_err(|error| ApiError(StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?;
+
+    Ok(Json(response))
+}
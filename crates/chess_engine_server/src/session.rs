@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chess_engine::{Engine, GameResult, Move, Search, Side};
+use serde_json::{json, Value};
+
+/// A board kept between requests, with a search of its own so that its transposition table
+/// carries over from one search to the next.
+pub struct Session {
+    pub engine: Engine,
+    pub search: Search,
+}
+
+impl Session {
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            search: Search::new(),
+        }
+    }
+
+    /// Plays a move given in coordinate notation, such as `e7e8q`, or in SAN, such as `Nf3`.
+    pub fn play(&mut self, notation: &str) -> Result<(), String> {
+        let piece_move = Move::from_uci_str(notation)
+            .or_else(|| self.engine.move_from_san(notation))
+            .ok_or_else(|| format!("invalid move '{}'", notation))?;
+
+        self.engine
+            .try_make_move(piece_move)
+            .map_err(|error| error.to_string())
+    }
+
+    /// The board as JSON: its FEN, side to move, legal moves and result.
+    pub fn state(&self, id: u64) -> Value {
+        let engine = &self.engine;
+
+        json!({
+            "id": id,
+            "fen": engine.fen(),
+            "side_to_move": if engine.side_to_move() == Side::White { "white" } else { "black" },
+            "legal_moves": engine.legal_moves().iter().map(Move::to_string).collect::<Vec<_>>(),
+            "result": format_result(engine.game_result()),
+        })
+    }
+}
+
+/// The boards being analysed, each locked on its own so that searches of different boards run at
+/// the same time.
+#[derive(Default)]
+pub struct Sessions {
+    next_id: AtomicU64,
+    sessions: Mutex<HashMap<u64, Arc<tokio::sync::Mutex<Session>>>>,
+}
+
+impl Sessions {
+    /// Adds a session and returns its ID, or `None` if there are already `limit` sessions.
+    pub fn insert(&self, session: Session, limit: usize) -> Option<u64> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if sessions.len() >= limit {
+            return None;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        sessions.insert(id, Arc::new(tokio::sync::Mutex::new(session)));
+
+        Some(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<tokio::sync::Mutex<Session>>> {
+        self.sessions.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Removes a session, returning whether there was one. A search already running on it is
+    /// left to finish.
+    pub fn remove(&self, id: u64) -> bool {
+        self.sessions.lock().unwrap().remove(&id).is_some()
+    }
+
+    pub fn ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.sessions.lock().unwrap().keys().copied().collect();
+        ids.sort_unstable();
+
+        ids
+    }
+}
+
+fn format_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::WhiteWins => "1-0",
+        GameResult::BlackWins => "0-1",
+        _ => "1/2-1/2",
+    }
+}
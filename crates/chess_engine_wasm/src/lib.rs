@@ -0,0 +1,120 @@
+//! JavaScript bindings for running the engine in a browser, built with
+//! `wasm-pack build crates/chess_engine_wasm --target web`.
+//!
+//! ```js
+//! import init, { ChessEngine } from "./pkg/chess_engine_wasm.js";
+//!
+//! await init();
+//!
+//! const engine = new ChessEngine();
+//! engine.makeMove("e2e4");
+//! console.log(engine.legalMoves(), engine.search(1000));
+//! ```
+//!
+//! Searching blocks the thread it runs on, so a page should run the engine in a Web Worker to
+//! stay responsive.
+
+use std::time::Duration;
+
+use chess_engine::{Engine, Error, Move, Search, SearchLimits, Square, TimeLimits};
+use wasm_bindgen::prelude::*;
+
+/// A game against the engine: the current position, and the search which plays from it.
+#[wasm_bindgen]
+pub struct ChessEngine {
+    engine: Engine,
+    search: Search,
+}
+
+#[wasm_bindgen]
+impl ChessEngine {
+    /// Creates an engine with the initial position set up.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let mut engine = Engine::default();
+        engine.set_initial_position();
+
+        Self {
+            engine,
+            search: Search::new(),
+        }
+    }
+
+    /// Returns to the initial position and forgets anything the search learnt from the last game.
+    #[wasm_bindgen(js_name = newGame)]
+    pub fn new_game(&mut self) {
+        self.engine.set_initial_position();
+        self.search.new_game();
+    }
+
+    /// Sets up the position given in FEN, throwing if it can't be read.
+    #[wasm_bindgen(js_name = setFen)]
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), JsError> {
+        self.engine.set_fen(fen).map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// The current position in FEN.
+    pub fn fen(&self) -> String {
+        self.engine.fen()
+    }
+
+    /// The legal moves in the current position, in coordinate notation such as `e2e4`.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.engine
+            .legal_moves()
+            .iter()
+            .map(|piece_move| piece_move.to_string())
+            .collect()
+    }
+
+    /// The legal moves of the piece on the given square, such as `e2`, in coordinate notation.
+    /// Throws if the square can't be read.
+    #[wasm_bindgen(js_name = legalMovesFrom)]
+    pub fn legal_moves_from(&self, square: &str) -> Result<Vec<String>, JsError> {
+        let square = square.parse::<Square>().map_err(Error::from)?;
+
+        Ok(self
+            .engine
+            .legal_moves_from(square)
+            .iter()
+            .map(|piece_move| piece_move.to_string())
+            .collect())
+    }
+
+    /// Makes a move given in coordinate notation, throwing if it can't be read or isn't legal.
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, notation: &str) -> Result<(), JsError> {
+        let piece_move =
+            Move::from_uci_str(notation).ok_or_else(|| Error::InvalidMove(notation.to_string()))?;
+
+        self.engine.try_make_move(piece_move).map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// Searches the current position for the given number of milliseconds, returning the best
+    /// move in coordinate notation, or `undefined` if the game is over. The move isn't made.
+    pub fn search(&mut self, time_ms: u32) -> Option<String> {
+        let limits = SearchLimits {
+            time: TimeLimits {
+                move_time: Some(Duration::from_millis(time_ms.into())),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        self.search
+            .best_move_in_game(&self.engine, &limits)
+            .best_move
+            .map(|piece_move| piece_move.to_string())
+    }
+}
+
+impl Default for ChessEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
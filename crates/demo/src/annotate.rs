@@ -0,0 +1,113 @@
+use chess_engine::{
+    Engine, Game, GameResult, Score, Search, SearchLimits, Side, MATE_SCORE, NAG_BLUNDER,
+    NAG_DUBIOUS, NAG_MISTAKE,
+};
+
+// How much worse a move's score must be than the best move's, in centipawns, for it to be
+// marked as dubious (`?!`), a mistake (`?`) or a blunder (`??`).
+const INACCURACY_LOSS: i32 = 50;
+const MISTAKE_LOSS: i32 = 100;
+const BLUNDER_LOSS: i32 = 250;
+
+// Scores are capped at this many centipawns when comparing moves, so that a mate counts as a
+// large advantage rather than dwarfing every other difference.
+const SCORE_CAP: i32 = 1000;
+
+/// Annotates a game with the engine's opinion of each move of its main line: the score after it,
+/// from White's point of view, as a comment unless the move ends the game, and for a move which
+/// loses enough against the best move, a `?!`, `?` or `??` with the line the engine preferred as
+/// a variation. Each position is searched within the given limits, and the game is left at its
+/// final position.
+pub fn annotate_game(game: &mut Game, search: &mut Search, limits: &SearchLimits) {
+    search.new_game();
+
+    let plies = game.main_line().len();
+
+    // The score of each position of the main line for the side to move, and the line the engine
+    // would play from it.
+    let mut analysis = vec![];
+
+    for ply in 0..=plies {
+        game.go_to(ply);
+
+        let position = game.position();
+
+        analysis.push(match position.game_result() {
+            GameResult::Ongoing => {
+                let result = search.best_move_in_game(position, limits);
+                (result.score, result.pv)
+            }
+            result if result.is_draw() => (0, vec![]),
+            // The side to move has been mated.
+            _ => (-MATE_SCORE, vec![]),
+        });
+    }
+
+    for ply in 0..plies {
+        game.go_to(ply);
+
+        let side = game.position().side_to_move();
+        let played = game.main_line()[ply].piece_move;
+        let (best_score, best_line) = (analysis[ply].0, &analysis[ply].1);
+        let score = -analysis[ply + 1].0;
+
+        let loss = best_score.clamp(-SCORE_CAP, SCORE_CAP) - score.clamp(-SCORE_CAP, SCORE_CAP);
+        let nag = if loss >= BLUNDER_LOSS {
+            Some((NAG_BLUNDER, "Blunder"))
+        } else if loss >= MISTAKE_LOSS {
+            Some((NAG_MISTAKE, "Mistake"))
+        } else if loss >= INACCURACY_LOSS {
+            Some((NAG_DUBIOUS, "Inaccuracy"))
+        } else {
+            None
+        };
+
+        let mut comment = vec![];
+        let suggestion = best_line
+            .first()
+            .filter(|&&best| nag.is_some() && best != played);
+
+        if let (Some((_, label)), Some(&best)) = (nag, suggestion) {
+            let san = game.position().move_to_san(best);
+            comment.push(format!("{}. {} was best.", label, san));
+
+            // Playing the engine's line makes it the main line, keeping the game's moves as a
+            // variation, so the game's moves are then promoted back.
+            for &piece_move in best_line {
+                game.make_move(piece_move);
+            }
+
+            game.go_to(ply);
+            game.promote_variation(0);
+        }
+
+        game.go_to(ply + 1);
+
+        // Once the game is over, the result says all there is to say about the position.
+        if game.position().game_result() == GameResult::Ongoing {
+            comment.insert(0, format_score(analysis[ply + 1].0, side.flip()));
+        }
+
+        if !comment.is_empty() {
+            game.set_comment(&comment.join(" "));
+        }
+
+        if let Some((nag, _)) = nag {
+            game.add_nag(nag);
+        }
+    }
+
+    game.set_tag("Annotator", Engine::default().name());
+}
+
+// Writes the score of a position for the side to move from White's point of view, as pawns with
+// a sign, such as `+0.35`, or as a mate, such as `#3` for White mating in three or `#-2` for
+// Black mating in two.
+fn format_score(score: i32, side_to_move: Side) -> String {
+    let sign = if side_to_move == Side::White { 1 } else { -1 };
+
+    match Score::from_search_score(score) {
+        Score::Centipawns(centipawns) => format!("{:+.2}", (sign * centipawns) as f64 / 100.0),
+        Score::Mate(moves) => format!("#{}", sign * moves),
+    }
+}
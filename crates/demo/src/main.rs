@@ -1,152 +1,580 @@
-use std::io::BufRead;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
 
-use rand::Rng;
-use vampirc_uci::{parse, MessageList, UciMessage, UciMove, UciPiece, UciSquare};
+use chess_engine::uci::UciHandler;
+use chess_engine::{
+    check_eval_symmetry, parse_pgn, run_bench, run_epd_suite, BookBuilder, BookWeighting,
+    DataGenerator, DatagenConfig, Engine, Game, Score, Search, SearchLimits, Side, TimeControl,
+    TimeLimits, DEFAULT_BENCH_DEPTH, MAX_SKILL_LEVEL,
+};
 
-use chess_engine::{Engine, Move, PieceType, Side};
+use crate::annotate::annotate_game;
+use crate::selfplay::{play_match, EnginePlayer, Player, UciPlayer};
+use crate::solve::solve_puzzles;
+use crate::sprt::{Sprt, SprtResult};
+
+mod annotate;
+mod play;
+mod selfplay;
+mod server;
+mod solve;
+mod sprt;
+
+// The time given to each position of a test suite, unless another is given.
+const DEFAULT_SUITE_MOVE_TIME_MS: u64 = 1000;
+
+// The time given to each position of a game being annotated, unless another is given.
+const DEFAULT_ANNOTATE_MOVE_TIME_MS: u64 = 500;
+
+// The time control for self-play games, unless another is given.
+const DEFAULT_TIME_CONTROL: &str = "10+0.1";
+
+// The most games an SPRT runs for before giving up, unless another limit is given.
+const DEFAULT_SPRT_MAX_GAMES: u32 = 20000;
+
+// How many plies from the benchmark positions the evaluation's symmetry is checked to, unless
+// another depth is given.
+const DEFAULT_SYMMETRY_DEPTH: u32 = 3;
+
+// The most nodes of the final iteration written out when tracing a search, unless another limit
+// is given.
+const DEFAULT_TRACE_NODES: usize = 2000;
+
+// The engine's skill level when playing interactively, unless another is given.
+const DEFAULT_PLAY_SKILL_LEVEL: u32 = 10;
 
 fn main() {
-    let stdin = std::io::stdin();
+    let args: Vec<String> = std::env::args().collect();
 
-    let mut uci_mode = false;
+    match args.get(1).map(String::as_str) {
+        // Run an EPD test suite: `demo epd <file> [milliseconds per position]`.
+        Some("epd") => {
+            let Some(path) = args.get(2) else {
+                eprintln!("usage: {} epd <file> [milliseconds per position]", args[0]);
+                std::process::exit(1);
+            };
 
-    let mut engine = Engine::default();
-    let mut side = Side::White;
-
-    'main_loop: loop {
-        for line in stdin.lock().lines() {
-            let messages: MessageList = parse(&line.unwrap());
-
-            for message in messages {
-                match message {
-                    UciMessage::Uci => {
-                        // The engine is now running in UCI mode.
-                        uci_mode = true;
-
-                        // Send identification message, and report as ready.
-                        println!("{}", UciMessage::id_name(engine.name()));
-                        println!("{}", UciMessage::id_author(engine.author()));
-                        println!("{}", UciMessage::UciOk);
-                    }
-                    UciMessage::IsReady => {
-                        if !uci_mode {
-                            continue;
-                        }
+            let move_time = args
+                .get(3)
+                .and_then(|ms| ms.parse().ok())
+                .unwrap_or(DEFAULT_SUITE_MOVE_TIME_MS);
 
-                        // Immediately send a readyok message back, no reason not to at the moment.
-                        println!("{}", UciMessage::ReadyOk);
-                    }
-                    UciMessage::Position {
-                        startpos,
-                        fen,
-                        moves,
-                    } => {
-                        if !uci_mode {
-                            continue;
-                        }
-                        // Set up the given position.
-                        if startpos {
-                            engine.set_initial_position();
-                        }
-
-                        side = Side::White;
-
-                        for uci_move in moves {
-                            engine.make_move(uci_move_to_move(&uci_move));
-
-                            if side == Side::White {
-                                side = Side::Black;
-                            } else {
-                                side = Side::White;
-                            }
-                        }
-
-                        engine.print_board();
-                    }
-                    UciMessage::Go {
-                        time_control,
-                        search_control,
-                    } => {
-                        if !uci_mode {
-                            continue;
-                        }
-
-                        // Search for and return the next move.
-                        let moves = engine.generate_moves(side);
-                        if !moves.is_empty() {
-                            let chosen_move = &moves[rand::thread_rng().gen_range(0..moves.len())];
-                            let move_string = UciMessage::BestMove {
-                                best_move: move_to_uci_move(chosen_move),
-                                ponder: None,
-                            };
-                            println!("{}", move_string);
-                        }
+            run_suite(path, Duration::from_millis(move_time));
+        }
+        // Solve the puzzles in an EPD file, reporting how long each took to solve:
+        // `demo solve <file> [milliseconds per puzzle]`.
+        Some("solve") => {
+            let Some(path) = args.get(2) else {
+                eprintln!("usage: {} solve <file> [milliseconds per puzzle]", args[0]);
+                std::process::exit(1);
+            };
+
+            let move_time = args
+                .get(3)
+                .and_then(|ms| ms.parse().ok())
+                .unwrap_or(DEFAULT_SUITE_MOVE_TIME_MS);
+
+            run_puzzles(path, Duration::from_millis(move_time));
+        }
+        // Build a Polyglot opening book from the games in a PGN file, weighting moves by how
+        // often they were played or how well they scored:
+        // `demo book <pgn> <book> [max plies] [min games] [frequency|score]`.
+        Some("book") => {
+            let (Some(pgn_path), Some(book_path)) = (args.get(2), args.get(3)) else {
+                eprintln!(
+                    "usage: {} book <pgn> <book> [max plies] [min games] [frequency|score]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let mut builder = BookBuilder::new();
+
+            if let Some(max_plies) = args.get(4).and_then(|plies| plies.parse().ok()) {
+                builder.max_plies = max_plies;
+            }
+
+            if let Some(min_games) = args.get(5).and_then(|games| games.parse().ok()) {
+                builder.min_games = min_games;
+            }
+
+            if args.get(6).map(String::as_str) == Some("score") {
+                builder.weighting = BookWeighting::Score;
+            }
+
+            build_book(pgn_path, book_path, &mut builder);
+        }
+        // Generate training data for an evaluation network from self-play, appending packed
+        // positions to a file: `demo datagen <output> <games> [nodes per move] [seed]`.
+        Some("datagen") => {
+            let (Some(path), Some(games)) = (
+                args.get(2),
+                args.get(3).and_then(|games| games.parse().ok()),
+            ) else {
+                eprintln!(
+                    "usage: {} datagen <output> <games> [nodes per move] [seed]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let mut config = DatagenConfig::default();
+
+            if let Some(nodes) = args.get(4).and_then(|nodes| nodes.parse().ok()) {
+                config.nodes = nodes;
+            }
+
+            if let Some(seed) = args.get(5).and_then(|seed| seed.parse().ok()) {
+                config.seed = seed;
+            }
+
+            generate_data(path, games, config);
+        }
+        // Answer JSON search requests, one per line, on stdin or on a local TCP port:
+        // `demo serve [port]`.
+        Some("serve") => {
+            let result = match args.get(2) {
+                Some(port) => match port.parse() {
+                    Ok(port) => server::serve_tcp(port),
+                    Err(_) => {
+                        eprintln!("invalid port '{}'", port);
+                        std::process::exit(1);
                     }
-                    UciMessage::Stop => {
-                        if !uci_mode {
-                            continue;
-                        }
+                },
+                None => server::serve(std::io::stdin().lock(), std::io::stdout()),
+            };
+
+            if let Err(error) = result {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        // Annotate the games in a PGN file with the engine's scores, marking dubious moves,
+        // mistakes and blunders, and write them out in PGN:
+        // `demo annotate <file> [milliseconds per position]`.
+        Some("annotate") => {
+            let Some(path) = args.get(2) else {
+                eprintln!(
+                    "usage: {} annotate <file> [milliseconds per position]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let move_time = args
+                .get(3)
+                .and_then(|ms| ms.parse().ok())
+                .unwrap_or(DEFAULT_ANNOTATE_MOVE_TIME_MS);
+
+            annotate_file(path, Duration::from_millis(move_time));
+        }
+        // Write out the tree explored by a search to the given depth, as a Graphviz DOT file, or
+        // as JSON if the file name ends in `.json`:
+        // `demo trace <output> <depth> [max nodes] [fen]`.
+        Some("trace") => {
+            let (Some(path), Some(depth)) = (
+                args.get(2),
+                args.get(3).and_then(|depth| depth.parse().ok()),
+            ) else {
+                eprintln!(
+                    "usage: {} trace <output> <depth> [max nodes] [fen]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let max_nodes = args
+                .get(4)
+                .and_then(|nodes| nodes.parse().ok())
+                .unwrap_or(DEFAULT_TRACE_NODES);
+            let fen = (args.len() > 5).then(|| args[5..].join(" "));
+
+            trace_search(path, depth, max_nodes, fen.as_deref());
+        }
+        // Search a fixed set of positions and report the node count: `demo bench [depth]`.
+        Some("bench") => {
+            let depth = args
+                .get(2)
+                .and_then(|depth| depth.parse().ok())
+                .unwrap_or(DEFAULT_BENCH_DEPTH);
+
+            println!("{}", run_bench(depth));
+        }
+        // Check that the evaluation scores positions the same as their mirror images, exiting with
+        // an error if any don't: `demo symmetry [depth]`.
+        Some("symmetry") => {
+            let depth = args
+                .get(2)
+                .and_then(|depth| depth.parse().ok())
+                .unwrap_or(DEFAULT_SYMMETRY_DEPTH);
+
+            let asymmetries = check_eval_symmetry(depth);
+
+            for asymmetry in &asymmetries {
+                println!(
+                    "{}: {} but {} mirrored",
+                    asymmetry.fen, asymmetry.score, asymmetry.mirrored_score
+                );
+            }
+
+            println!("{} asymmetric positions", asymmetries.len());
+
+            if !asymmetries.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        // Play against the engine on the terminal: `demo --play [skill level] [white|black]`, with
+        // skill levels from 0 to 20.
+        Some("--play") => {
+            let skill_level = args
+                .get(2)
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(DEFAULT_PLAY_SKILL_LEVEL)
+                .min(MAX_SKILL_LEVEL);
 
-                        // Stop thinking, but keep the current best move.
+            let human = match args.get(3).map(String::as_str) {
+                Some("black") => Side::Black,
+                _ => Side::White,
+            };
 
-                        break 'main_loop;
+            play::play(skill_level, human);
+        }
+        // Play games against itself, or against another UCI engine, writing them out in PGN:
+        // `demo selfplay <games> [seconds+increment] [opponent]`.
+        Some("selfplay") => {
+            let Some(games) = args.get(2).and_then(|games| games.parse().ok()) else {
+                eprintln!(
+                    "usage: {} selfplay <games> [seconds+increment] [opponent]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let notation = args.get(3).map_or(DEFAULT_TIME_CONTROL, String::as_str);
+            let Some(time_control) = TimeControl::parse(notation) else {
+                eprintln!("invalid time control '{}'", notation);
+                std::process::exit(1);
+            };
+
+            let engine = Engine::default();
+            let mut first = EnginePlayer::new(engine.name());
+
+            let score = match args.get(4) {
+                Some(path) => match UciPlayer::start(path) {
+                    Ok(mut opponent) => play_match(&mut first, &mut opponent, games, time_control),
+                    Err(error) => {
+                        eprintln!("failed to start {}: {}", path, error);
+                        std::process::exit(1);
                     }
-                    UciMessage::Quit => break 'main_loop,
-                    _ => {}
+                },
+                None => {
+                    let mut second = EnginePlayer::new(engine.name());
+                    play_match(&mut first, &mut second, games, time_control)
                 }
+            };
+
+            eprintln!(
+                "{} games: +{} ={} -{}",
+                score.games(),
+                score.wins,
+                score.draws,
+                score.losses
+            );
+        }
+        // Test whether one engine is stronger than another, each given as the path of a UCI
+        // engine or `self`: `demo sprt <new> <base> <elo0> <elo1> [seconds+increment] [games]`.
+        Some("sprt") => {
+            let usage = || {
+                eprintln!(
+                    "usage: {} sprt <new> <base> <elo0> <elo1> [seconds+increment] [games]",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+
+            let (Some(new), Some(base)) = (args.get(2), args.get(3)) else {
+                usage()
+            };
+
+            let elo = |i: usize| args.get(i).and_then(|elo| elo.parse().ok());
+            let (Some(elo0), Some(elo1)) = (elo(4), elo(5)) else {
+                usage()
+            };
+
+            let notation = args.get(6).map_or(DEFAULT_TIME_CONTROL, String::as_str);
+            let Some(time_control) = TimeControl::parse(notation) else {
+                usage()
+            };
+
+            let max_games = args
+                .get(7)
+                .and_then(|games| games.parse().ok())
+                .unwrap_or(DEFAULT_SPRT_MAX_GAMES);
+
+            let mut new = start_player(new);
+            let mut base = start_player(base);
+
+            let result =
+                Sprt { elo0, elo1 }.run(new.as_mut(), base.as_mut(), max_games, time_control);
+
+            match result {
+                SprtResult::H0 => println!("H0 accepted"),
+                SprtResult::H1 => println!("H1 accepted"),
+                SprtResult::Inconclusive => println!("no result after {} games", max_games),
             }
         }
+        _ => {
+            let mut uci = UciHandler::new(std::io::stdout());
+            uci.run(std::io::stdin().lock());
+        }
     }
 }
 
-fn uci_piece_to_piece(piece: UciPiece) -> PieceType {
-    match piece {
-        UciPiece::Pawn => PieceType::Pawn,
-        UciPiece::Knight => PieceType::Knight,
-        UciPiece::Bishop => PieceType::Bishop,
-        UciPiece::Rook => PieceType::Rook,
-        UciPiece::Queen => PieceType::Queen,
-        UciPiece::King => PieceType::King,
+// Starts a player from the command line: this engine if given `self`, otherwise the UCI engine at
+// the given path.
+fn start_player(spec: &str) -> Box<dyn Player> {
+    if spec == "self" {
+        return Box::new(EnginePlayer::new(Engine::default().name()));
+    }
+
+    match UciPlayer::start(spec) {
+        Ok(player) => Box::new(player),
+        Err(error) => {
+            eprintln!("failed to start {}: {}", spec, error);
+            std::process::exit(1);
+        }
     }
 }
 
-fn piece_to_uci_piece(piece: PieceType) -> UciPiece {
-    match piece {
-        PieceType::Pawn => UciPiece::Pawn,
-        PieceType::Knight => UciPiece::Knight,
-        PieceType::Bishop => UciPiece::Bishop,
-        PieceType::Rook => UciPiece::Rook,
-        PieceType::Queen => UciPiece::Queen,
-        PieceType::King => UciPiece::King,
-        PieceType::Count => UciPiece::King,
+fn run_suite(path: &str, move_time: Duration) {
+    let suite = match std::fs::read_to_string(path) {
+        Ok(suite) => suite,
+        Err(error) => {
+            eprintln!("failed to read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+
+    let limits = SearchLimits {
+        time: TimeLimits {
+            move_time: Some(move_time),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = run_epd_suite(&suite, &mut Search::new(), &limits);
+
+    for failure in &result.failures {
+        match failure.found {
+            Some(found) => println!("{}: failed, played {}", failure.id, found),
+            None => println!("{}: failed, no move found", failure.id),
+        }
     }
+
+    println!(
+        "solved {}/{} positions ({} skipped)",
+        result.solved, result.total, result.skipped
+    );
 }
 
-fn uci_move_to_move(uci_move: &UciMove) -> Move {
-    let from_idx = (8 * (uci_move.from.rank - 1)) + ((uci_move.from.file as u8) - b'a');
-    let to_idx = (8 * (uci_move.to.rank - 1)) + ((uci_move.to.file as u8) - b'a');
+fn run_puzzles(path: &str, move_time: Duration) {
+    let suite = match std::fs::read_to_string(path) {
+        Ok(suite) => suite,
+        Err(error) => {
+            eprintln!("failed to read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+
+    let limits = SearchLimits {
+        time: TimeLimits {
+            move_time: Some(move_time),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let results = solve_puzzles(&suite, &mut Search::new(), &limits);
+    let mut solved_times = vec![];
+
+    for result in &results {
+        let found = result
+            .found
+            .map_or("no move".to_string(), |found| found.to_string());
+
+        match result.time_to_solution {
+            Some(time) if result.solved => {
+                println!(
+                    "{}: solved in {:.1}ms with {}",
+                    result.id,
+                    time.as_secs_f64() * 1000.0,
+                    found
+                );
+                solved_times.push(time);
+            }
+            _ if result.solved => println!("{}: solved with {}", result.id, found),
+            _ => {
+                let score = match result.score {
+                    Score::Centipawns(centipawns) => format!("score {}", centipawns),
+                    Score::Mate(moves) => format!("mate {}", moves),
+                };
 
-    Move {
-        from: from_idx as u32,
-        to: to_idx as u32,
-        promote: uci_move.promotion.map(uci_piece_to_piece),
+                println!("{}: unsolved, played {} ({})", result.id, found, score);
+            }
+        }
     }
+
+    let solved = results.iter().filter(|result| result.solved).count();
+    let mean_time = solved_times.iter().sum::<Duration>() / solved_times.len().max(1) as u32;
+
+    println!(
+        "solved {}/{} puzzles, in {:.1}ms on average",
+        solved,
+        results.len(),
+        mean_time.as_secs_f64() * 1000.0
+    );
 }
 
-fn move_to_uci_move(engine_move: &Move) -> UciMove {
-    let from = UciSquare {
-        rank: ((engine_move.from / 8) + 1) as u8,
-        file: ((engine_move.from % 8) as u8 + b'a') as char,
+fn generate_data(path: &str, games: u32, config: DatagenConfig) {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path);
+
+    let mut output = match file {
+        Ok(file) => BufWriter::new(file),
+        Err(error) => {
+            eprintln!("failed to open {}: {}", path, error);
+            std::process::exit(1);
+        }
     };
 
-    let to = UciSquare {
-        rank: ((engine_move.to / 8) + 1) as u8,
-        file: ((engine_move.to % 8) as u8 + b'a') as char,
+    let mut generator = DataGenerator::new(config);
+    let mut total = 0;
+
+    for i in 0..games {
+        let positions = generator.play_game();
+
+        for sample in &positions {
+            if let Err(error) = output.write_all(&sample.to_bytes()) {
+                eprintln!("failed to write {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+
+        total += positions.len();
+        eprintln!(
+            "game {}/{}: {} positions, {} in total",
+            i + 1,
+            games,
+            positions.len(),
+            total
+        );
+    }
+
+    if let Err(error) = output.flush() {
+        eprintln!("failed to write {}: {}", path, error);
+        std::process::exit(1);
+    }
+}
+
+fn build_book(pgn_path: &str, book_path: &str, builder: &mut BookBuilder) {
+    let games = read_pgn_file(pgn_path);
+
+    for game in &games {
+        builder.add_game(game);
+    }
+
+    if let Err(error) = builder.save(book_path) {
+        eprintln!("failed to write {}: {}", book_path, error);
+        std::process::exit(1);
+    }
+
+    println!("built {} from {} games", book_path, games.len());
+}
+
+fn trace_search(path: &str, depth: u32, max_nodes: usize, fen: Option<&str>) {
+    let mut engine = Engine::default();
+
+    match fen {
+        Some(fen) => {
+            if let Err(error) = engine.set_fen(fen) {
+                eprintln!("invalid FEN: {}", error);
+                std::process::exit(1);
+            }
+        }
+        None => engine.set_initial_position(),
+    }
+
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..Default::default()
+    };
+
+    let mut search = Search::new();
+    search.start_trace(max_nodes);
+
+    let result = search.best_move(&engine, &limits);
+    let trace = search.take_trace().unwrap();
+
+    let output = if path.ends_with(".json") {
+        trace.to_json()
+    } else {
+        trace.to_dot()
+    };
+
+    if let Err(error) = std::fs::write(path, output) {
+        eprintln!("failed to write {}: {}", path, error);
+        std::process::exit(1);
+    }
+
+    println!(
+        "best move {} at depth {}: wrote {} nodes to {}, leaving out {}",
+        result
+            .best_move
+            .map_or("(none)".to_string(), |best_move| best_move.to_string()),
+        result.depth,
+        trace.nodes().len(),
+        path,
+        trace.omitted()
+    );
+}
+
+// Reads the games in a PGN file, exiting if it can't be read.
+fn read_pgn_file(path: &str) -> Vec<Game> {
+    let pgn = match std::fs::read_to_string(path) {
+        Ok(pgn) => pgn,
+        Err(error) => {
+            eprintln!("failed to read {}: {}", path, error);
+            std::process::exit(1);
+        }
     };
 
-    UciMove {
-        from,
-        to,
-        promotion: engine_move.promote.map(piece_to_uci_piece),
+    match parse_pgn(&pgn) {
+        Ok(games) => games,
+        Err(error) => {
+            eprintln!("failed to read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn annotate_file(path: &str, move_time: Duration) {
+    let games = read_pgn_file(path);
+
+    let limits = SearchLimits {
+        time: TimeLimits {
+            move_time: Some(move_time),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut search = Search::new();
+
+    for mut game in games {
+        annotate_game(&mut game, &mut search, &limits);
+        println!("{}", game.to_pgn());
     }
 }
@@ -0,0 +1,103 @@
+use std::io::{BufRead, Write};
+
+use std::time::Duration;
+
+use chess_engine::{
+    BoardRenderer, Engine, GameResult, Move, Search, SearchLimits, Side, TimeLimits,
+};
+
+// The time the engine takes over each move.
+const MOVE_TIME: Duration = Duration::from_millis(1000);
+
+// Plays a game against the engine on the terminal. Moves are entered in SAN, such as `Nf3`, or in
+// coordinate notation, such as `g1f3`, and the engine replies at the given skill level.
+pub fn play(skill_level: u32, human: Side) {
+    let mut engine = Engine::default();
+    engine.set_initial_position();
+
+    let mut search = Search::new();
+    search.config.skill_level = skill_level;
+
+    let limits = SearchLimits {
+        time: TimeLimits {
+            move_time: Some(MOVE_TIME),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // The board is shown from the human's side, marking the move just played.
+    let mut renderer = BoardRenderer {
+        unicode: true,
+        flipped: human == Side::Black,
+        last_move: None,
+        highlight_check: true,
+    };
+
+    let mut lines = std::io::stdin().lock().lines();
+
+    println!("Enter moves in SAN or coordinate notation, or `quit` to stop.");
+
+    while engine.game_result() == GameResult::Ongoing {
+        if engine.side_to_move() != human {
+            let result = search.best_move_in_game(&engine, &limits);
+
+            let Some(piece_move) = result.best_move else {
+                break;
+            };
+
+            println!("{}", engine.move_to_san(piece_move));
+            engine.make_move(piece_move);
+            renderer.last_move = Some(piece_move);
+            continue;
+        }
+
+        println!("\n{}\n", renderer.render(&engine));
+        print!("{}> ", engine.fullmove_number());
+        let _ = std::io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            return;
+        };
+
+        let notation = line.trim();
+
+        if notation == "quit" {
+            return;
+        }
+
+        let Some(piece_move) = parse_move(&engine, notation) else {
+            println!("couldn't read the move '{}'", notation);
+            continue;
+        };
+
+        match engine.try_make_move(piece_move) {
+            Ok(()) => renderer.last_move = Some(piece_move),
+            Err(error) => println!("{}", error),
+        }
+    }
+
+    println!("\n{}\n", renderer.render(&engine));
+    println!("{}", describe_result(engine.game_result()));
+}
+
+// Reads a move in SAN if it is one in the position, and in coordinate notation otherwise, so that
+// illegal coordinate moves still get a specific error.
+fn parse_move(engine: &Engine, notation: &str) -> Option<Move> {
+    engine
+        .move_from_san(notation)
+        .or_else(|| Move::from_uci_str(notation))
+}
+
+fn describe_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "The game was abandoned.",
+        GameResult::WhiteWins => "White wins by checkmate.",
+        GameResult::BlackWins => "Black wins by checkmate.",
+        GameResult::DrawStalemate => "Draw by stalemate.",
+        GameResult::DrawRepetition => "Draw by threefold repetition.",
+        GameResult::DrawFiftyMove => "Draw by the fifty-move rule.",
+        GameResult::DrawMaterial => "Draw by insufficient material.",
+        GameResult::DrawAdjudicated => "Draw by agreement.",
+    }
+}
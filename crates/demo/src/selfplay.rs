@@ -0,0 +1,328 @@
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use chess_engine::{
+    Clock, Engine, Game, GameResult, Move, Search, SearchLimits, Side, TimeControl, TimeLimits,
+};
+
+// Short opening lines, in coordinate notation, which games start from so that the same two
+// players don't play the same game every time. Each is played twice, once with each player as
+// White.
+const OPENINGS: [&str; 8] = [
+    "e2e4 e7e5 g1f3 b8c6",
+    "e2e4 c7c5 g1f3 d7d6",
+    "e2e4 e7e6 d2d4 d7d5",
+    "e2e4 c7c6 d2d4 d7d5",
+    "d2d4 d7d5 c2c4 e7e6",
+    "d2d4 g8f6 c2c4 g7g6",
+    "c2c4 e7e5 b1c3 g8f6",
+    "g1f3 d7d5 g2g3 g8f6",
+];
+
+// Games still going after this many plies are adjudicated as draws.
+const MAX_GAME_PLIES: usize = 400;
+
+/// Something which chooses moves in a game: the engine itself, or another engine run over UCI.
+pub trait Player {
+    fn name(&self) -> String;
+
+    // Forgets anything learnt from the last game.
+    fn new_game(&mut self);
+
+    // Chooses a move for the side to move in the game's current position, or returns `None` if
+    // the player couldn't.
+    fn choose_move(&mut self, game: &Game, time: &TimeLimits) -> Option<Move>;
+}
+
+/// This engine, searching with its own `Search`.
+pub struct EnginePlayer {
+    pub name: String,
+    pub search: Search,
+}
+
+impl EnginePlayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            search: Search::new(),
+        }
+    }
+}
+
+impl Player for EnginePlayer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) {
+        self.search.new_game();
+    }
+
+    fn choose_move(&mut self, game: &Game, time: &TimeLimits) -> Option<Move> {
+        let limits = SearchLimits {
+            time: time.clone(),
+            ..Default::default()
+        };
+
+        self.search
+            .best_move_in_game(game.position(), &limits)
+            .best_move
+    }
+}
+
+/// Another engine, run as a subprocess and spoken to over UCI.
+pub struct UciPlayer {
+    name: String,
+    process: Child,
+    input: ChildStdin,
+    output: Lines<BufReader<ChildStdout>>,
+}
+
+impl UciPlayer {
+    /// Starts the engine at the given path and waits for it to be ready.
+    pub fn start(path: &str) -> std::io::Result<Self> {
+        let mut process = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let input = process.stdin.take().unwrap();
+        let output = BufReader::new(process.stdout.take().unwrap()).lines();
+
+        let mut player = Self {
+            name: path.to_string(),
+            process,
+            input,
+            output,
+        };
+
+        player.send("uci");
+
+        for line in player.wait_for("uciok") {
+            if let Some(name) = line.strip_prefix("id name ") {
+                player.name = name.trim().to_string();
+            }
+        }
+
+        Ok(player)
+    }
+
+    fn send(&mut self, command: &str) {
+        let _ = writeln!(self.input, "{}", command);
+        let _ = self.input.flush();
+    }
+
+    // Reads lines until one starts with `token`, returning them all. Stops early if the engine
+    // closes its output.
+    fn wait_for(&mut self, token: &str) -> Vec<String> {
+        let mut lines = vec![];
+
+        for line in self.output.by_ref() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            let done = line.starts_with(token);
+            lines.push(line);
+
+            if done {
+                break;
+            }
+        }
+
+        lines
+    }
+}
+
+impl Player for UciPlayer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) {
+        self.send("ucinewgame");
+        self.send("isready");
+        self.wait_for("readyok");
+    }
+
+    fn choose_move(&mut self, game: &Game, time: &TimeLimits) -> Option<Move> {
+        let mut initial = Engine::default();
+        initial.set_initial_position();
+
+        let mut position = if game.start().fen() == initial.fen() {
+            "position startpos".to_string()
+        } else {
+            format!("position fen {}", game.start().fen())
+        };
+
+        if !game.moves().is_empty() {
+            position += " moves";
+
+            for piece_move in game.moves() {
+                position += &format!(" {}", piece_move);
+            }
+        }
+
+        let millis = |time: Option<Duration>| time.unwrap_or_default().as_millis();
+
+        self.send(&position);
+        self.send(&format!(
+            "go wtime {} btime {} winc {} binc {}",
+            millis(time.white_time),
+            millis(time.black_time),
+            millis(time.white_increment),
+            millis(time.black_increment)
+        ));
+
+        let lines = self.wait_for("bestmove");
+
+        lines
+            .last()?
+            .strip_prefix("bestmove ")?
+            .split_whitespace()
+            .next()
+            .and_then(Move::from_uci_str)
+    }
+}
+
+impl Drop for UciPlayer {
+    fn drop(&mut self) {
+        self.send("quit");
+        let _ = self.process.wait();
+    }
+}
+
+/// A tally of game results, from the point of view of one player.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    /// Adds the result of a game in which the player was on the given side.
+    pub fn add(&mut self, result: GameResult, side: Side) {
+        match (result, side) {
+            (GameResult::WhiteWins, Side::White) | (GameResult::BlackWins, Side::Black) => {
+                self.wins += 1
+            }
+            (GameResult::WhiteWins, _) | (GameResult::BlackWins, _) => self.losses += 1,
+            (result, _) if result.is_draw() => self.draws += 1,
+            _ => {}
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Plays one game from the given opening, with each side's clock running as in a real game. The
+/// game is adjudicated if a player runs out of time, fails to give a legal move, or it runs on
+/// too long.
+pub fn play_game(
+    white: &mut dyn Player,
+    black: &mut dyn Player,
+    opening: &str,
+    time_control: TimeControl,
+) -> Game {
+    let mut game = Game::new();
+    game.set_tag("White", &white.name());
+    game.set_tag("Black", &black.name());
+    game.set_tag("TimeControl", &time_control.to_string());
+
+    for piece_move in opening.split_whitespace().filter_map(Move::from_uci_str) {
+        if game.try_make_move(piece_move).is_err() {
+            break;
+        }
+    }
+
+    white.new_game();
+    black.new_game();
+
+    let mut clock = Clock::new(time_control);
+
+    while game.result() == GameResult::Ongoing {
+        if game.moves().len() >= MAX_GAME_PLIES {
+            game.adjudicate(GameResult::DrawAdjudicated, "adjudication");
+            break;
+        }
+
+        let side = game.position().side_to_move();
+        let loss = match side {
+            Side::White => GameResult::BlackWins,
+            _ => GameResult::WhiteWins,
+        };
+
+        let time = clock.time_limits();
+
+        let player: &mut dyn Player = match side {
+            Side::White => &mut *white,
+            _ => &mut *black,
+        };
+
+        let start = Instant::now();
+        let chosen = player.choose_move(&game, &time);
+        let elapsed = start.elapsed();
+
+        if !clock.tick(side, elapsed) {
+            game.adjudicate(loss, "time forfeit");
+            break;
+        }
+
+        if chosen.is_none_or(|piece_move| game.try_make_move(piece_move).is_err()) {
+            game.adjudicate(loss, "rules infraction");
+        }
+    }
+
+    game
+}
+
+/// Plays the game with the given number in a match between two players. Colours alternate from
+/// one game to the next, and each opening is used for two games in a row, once with each player
+/// as White. Returns the game and the side the first player had.
+pub fn play_match_game(
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    index: u32,
+    time_control: TimeControl,
+) -> (Game, Side) {
+    let opening = OPENINGS[(index as usize / 2) % OPENINGS.len()];
+
+    if index.is_multiple_of(2) {
+        (play_game(first, second, opening, time_control), Side::White)
+    } else {
+        (play_game(second, first, opening, time_control), Side::Black)
+    }
+}
+
+/// Plays a match of the given number of games between two players. Each game is written to
+/// stdout in PGN as it finishes, and the running score to stderr. Returns the score of the first
+/// player.
+pub fn play_match(
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    games: u32,
+    time_control: TimeControl,
+) -> Score {
+    let mut score = Score::default();
+
+    for i in 0..games {
+        let (game, first_side) = play_match_game(first, second, i, time_control);
+        score.add(game.result(), first_side);
+
+        println!("{}", game.to_pgn());
+        eprintln!(
+            "game {}/{}: +{} ={} -{}",
+            i + 1,
+            games,
+            score.wins,
+            score.draws,
+            score.losses
+        );
+    }
+
+    score
+}
@@ -0,0 +1,147 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use chess_engine::{Engine, Move, Score, Search, SearchLimits, TimeLimits};
+use serde_json::{json, Value};
+
+// The time searched for when a request gives no limits.
+const DEFAULT_MOVE_TIME_MS: u64 = 1000;
+
+/// Answers one request, a JSON object such as `{"fen": "...", "depth": 8}`, with a JSON object.
+///
+/// A request gives the position as `fen`, or the initial position if there is none, and any
+/// `moves` to play from it in coordinate notation. It is searched within `depth`, `nodes` and
+/// `movetime` in milliseconds, or for a second if none are given, and a depth of 0 only
+/// evaluates it. Any `id` is copied to the response.
+///
+/// The response gives the static evaluation as `eval`, and unless the depth was 0, the search's
+/// `bestmove`, `ponder`, `score` as `{"cp": ...}` or `{"mate": ...}`, `depth`, `nodes` and `pv`.
+/// Scores are from the point of view of the side to move. A request which can't be answered gets
+/// an `error` instead.
+pub fn handle_request(request: &str, search: &mut Search) -> Value {
+    let request: Value = match serde_json::from_str(request) {
+        Ok(request) => request,
+        Err(error) => return json!({ "error": format!("invalid JSON: {}", error) }),
+    };
+
+    let mut response = match answer(&request, search) {
+        Ok(response) => response,
+        Err(error) => json!({ "error": error }),
+    };
+
+    if !request["id"].is_null() {
+        response["id"] = request["id"].clone();
+    }
+
+    response
+}
+
+fn answer(request: &Value, search: &mut Search) -> Result<Value, String> {
+    let mut engine = Engine::default();
+
+    match request["fen"].as_str() {
+        Some(fen) => engine
+            .set_fen(fen)
+            .map_err(|error| format!("invalid FEN: {}", error))?,
+        None => engine.set_initial_position(),
+    }
+
+    for notation in request["moves"].as_array().into_iter().flatten() {
+        let notation = notation.as_str().unwrap_or_default();
+        let piece_move =
+            Move::from_uci_str(notation).ok_or_else(|| format!("invalid move '{}'", notation))?;
+
+        engine
+            .try_make_move(piece_move)
+            .map_err(|error| error.to_string())?;
+    }
+
+    let number = |name: &str| request[name].as_u64();
+    let mut response = json!({ "eval": engine.evaluate() });
+
+    if number("depth") == Some(0) {
+        return Ok(response);
+    }
+
+    let move_time = match (number("depth"), number("nodes"), number("movetime")) {
+        (None, None, None) => Some(DEFAULT_MOVE_TIME_MS),
+        (_, _, move_time) => move_time,
+    };
+
+    let limits = SearchLimits {
+        depth: number("depth").map(|depth| depth as u32),
+        nodes: number("nodes"),
+        time: TimeLimits {
+            move_time: move_time.map(Duration::from_millis),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = search.best_move_in_game(&engine, &limits);
+
+    response["bestmove"] = json!(result.best_move.map(|best_move| best_move.to_string()));
+    response["ponder"] = json!(result.ponder_move.map(|ponder| ponder.to_string()));
+    response["score"] = match Score::from_search_score(result.score) {
+        Score::Centipawns(centipawns) => json!({ "cp": centipawns }),
+        Score::Mate(moves) => json!({ "mate": moves }),
+    };
+    response["depth"] = json!(result.depth);
+    response["nodes"] = json!(result.nodes);
+    response["pv"] = json!(result.pv.iter().map(Move::to_string).collect::<Vec<_>>());
+
+    Ok(response)
+}
+
+/// Answers requests read one per line, writing each response on a line of its own, until the
+/// input ends. Blank lines are ignored.
+pub fn serve(input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+    let mut search = Search::new();
+
+    for line in input.lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        writeln!(output, "{}", handle_request(&line, &mut search))?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Listens for connections on the given local port, serving each on its own thread, with its own
+/// search, as `serve` does.
+pub fn serve_tcp(port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("failed to accept a connection: {}", error);
+                continue;
+            }
+        };
+
+        std::thread::spawn(move || {
+            let input = match stream.try_clone() {
+                Ok(input) => BufReader::new(input),
+                Err(error) => {
+                    eprintln!("failed to read from a connection: {}", error);
+                    return;
+                }
+            };
+
+            if let Err(error) = serve(input, stream) {
+                eprintln!("connection closed: {}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
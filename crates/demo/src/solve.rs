@@ -0,0 +1,123 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use chess_engine::{Engine, EpdOperation, Move, Score, Search, SearchInfo, SearchLimits};
+
+/// What happened when a puzzle was searched.
+#[derive(Clone, Debug)]
+pub struct PuzzleResult {
+    // The `id` of the puzzle, or its line number if it has none.
+    pub id: String,
+    pub solved: bool,
+    pub found: Option<Move>,
+    pub score: Score,
+    // When the search settled on the solution: the time of the first iteration from which every
+    // later one chose a solving move.
+    pub time_to_solution: Option<Duration>,
+}
+
+/// Searches each puzzle of a suite of EPD records, such as `... bm Qxf7+; id "mate 1";`. The
+/// solution is given by the `bm` moves, or if there are none, the first move of the `pv`, and
+/// any `am` moves must be avoided. A puzzle with `dm n` is searched for a mate in at most `n`
+/// moves, which must be found to solve it. Puzzles with nothing to check are skipped.
+pub fn solve_puzzles(suite: &str, search: &mut Search, limits: &SearchLimits) -> Vec<PuzzleResult> {
+    let (sender, receiver) = mpsc::channel();
+    search.set_info_callback(move |info: &SearchInfo| {
+        let _ = sender.send((info.time, info.pv.first().copied()));
+    });
+
+    let mut results = vec![];
+    let mut engine = Engine::default();
+
+    for (line_number, line) in suite.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let operations = match engine.set_position_from_epd(line) {
+            Ok(operations) => operations,
+            Err(error) => {
+                eprintln!("skipping line {}: {}", line_number + 1, error);
+                continue;
+            }
+        };
+
+        let operands = |opcode: &str| -> Vec<&str> {
+            operations
+                .iter()
+                .filter(|operation| operation.opcode == opcode)
+                .flat_map(|operation| operation.operands.iter().map(String::as_str))
+                .collect()
+        };
+
+        let moves = |sans: Vec<&str>| -> Vec<Move> {
+            sans.into_iter()
+                .filter_map(|san| engine.move_from_san(san))
+                .collect()
+        };
+
+        let mut best_moves = moves(operands("bm"));
+
+        if best_moves.is_empty() {
+            best_moves = moves(operands("pv").into_iter().take(1).collect());
+        }
+
+        let avoid_moves = moves(operands("am"));
+        let mate: Option<u32> = operands("dm").first().and_then(|moves| moves.parse().ok());
+
+        if best_moves.is_empty() && avoid_moves.is_empty() && mate.is_none() {
+            eprintln!("skipping line {}: nothing to solve", line_number + 1);
+            continue;
+        }
+
+        let is_solution = |piece_move: Move| {
+            (best_moves.is_empty() || best_moves.contains(&piece_move))
+                && !avoid_moves.contains(&piece_move)
+        };
+
+        let puzzle_limits = SearchLimits {
+            mate: mate.or(limits.mate),
+            ..limits.clone()
+        };
+
+        search.new_game();
+        let result = search.best_move(&engine, &puzzle_limits);
+        let score = Score::from_search_score(result.score);
+
+        let mates_in_time = match (mate, score) {
+            (Some(limit), Score::Mate(moves)) => moves > 0 && moves as u32 <= limit,
+            (Some(_), _) => false,
+            (None, _) => true,
+        };
+
+        let solved = mates_in_time && result.best_move.is_some_and(is_solution);
+
+        // The search settled on the solution at the first iteration of the last unbroken run
+        // choosing a solving move.
+        let mut settled = None;
+
+        for (time, best_move) in receiver.try_iter() {
+            match best_move.filter(|&best_move| is_solution(best_move)) {
+                Some(_) => settled = settled.or(Some(time)),
+                None => settled = None,
+            }
+        }
+
+        results.push(PuzzleResult {
+            id: puzzle_id(&operations).unwrap_or_else(|| format!("line {}", line_number + 1)),
+            solved,
+            found: result.best_move,
+            score,
+            time_to_solution: settled.filter(|_| solved),
+        });
+    }
+
+    results
+}
+
+fn puzzle_id(operations: &[EpdOperation]) -> Option<String> {
+    operations
+        .iter()
+        .find(|operation| operation.opcode == "id")
+        .and_then(|operation| operation.operands.first().cloned())
+}
@@ -0,0 +1,128 @@
+use chess_engine::TimeControl;
+
+use crate::selfplay::{play_match_game, Player, Score};
+
+// The chances of accepting H1 when H0 is true, and of accepting H0 when H1 is true.
+const ALPHA: f64 = 0.05;
+const BETA: f64 = 0.05;
+
+/// A sequential probability ratio test of whether one player is stronger than another: either by
+/// at most `elo0` (H0), or by at least `elo1` (H1). Games are played until the log-likelihood
+/// ratio passes one of the bounds, so a clear improvement or regression is found after far fewer
+/// games than a fixed length match would need.
+#[derive(Copy, Clone, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+}
+
+/// Which hypothesis the test accepted.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SprtResult {
+    H0,
+    H1,
+    // The game limit was reached first.
+    Inconclusive,
+}
+
+impl Sprt {
+    /// The log-likelihood ratio of H1 against H0, given the score so far. This uses the normal
+    /// approximation to the distribution of game results, which is accurate once more than a
+    /// handful of games have been played.
+    pub fn llr(&self, score: &Score) -> f64 {
+        let (mean, variance) = score_distribution(score);
+
+        if variance <= 0.0 {
+            return 0.0;
+        }
+
+        let s0 = expected_score(self.elo0);
+        let s1 = expected_score(self.elo1);
+
+        (s1 - s0) * (2.0 * mean - s0 - s1) * score.games() as f64 / (2.0 * variance)
+    }
+
+    /// Plays games between the two players until one hypothesis is accepted or `max_games` have
+    /// been played, printing the score, ratio and Elo estimate after each game.
+    pub fn run(
+        &self,
+        first: &mut dyn Player,
+        second: &mut dyn Player,
+        max_games: u32,
+        time_control: TimeControl,
+    ) -> SprtResult {
+        let lower = (BETA / (1.0 - ALPHA)).ln();
+        let upper = ((1.0 - BETA) / ALPHA).ln();
+
+        let mut score = Score::default();
+
+        for i in 0..max_games {
+            let (game, first_side) = play_match_game(first, second, i, time_control);
+            score.add(game.result(), first_side);
+
+            let llr = self.llr(&score);
+            let (elo, margin) = elo_estimate(&score);
+
+            println!(
+                "games {}: +{} ={} -{} llr {:.2} ({:.2}, {:.2}) elo {:.1} +/- {:.1}",
+                score.games(),
+                score.wins,
+                score.draws,
+                score.losses,
+                llr,
+                lower,
+                upper,
+                elo,
+                margin
+            );
+
+            if llr >= upper {
+                return SprtResult::H1;
+            }
+
+            if llr <= lower {
+                return SprtResult::H0;
+            }
+        }
+
+        SprtResult::Inconclusive
+    }
+}
+
+// The mean and variance of the points scored per game.
+fn score_distribution(score: &Score) -> (f64, f64) {
+    let games = score.games().max(1) as f64;
+    let mean = (score.wins as f64 + score.draws as f64 / 2.0) / games;
+    let variance = (score.wins as f64 * (1.0 - mean).powi(2)
+        + score.draws as f64 * (0.5 - mean).powi(2)
+        + score.losses as f64 * mean.powi(2))
+        / games;
+
+    (mean, variance)
+}
+
+// The expected score of a player the given number of Elo points stronger than their opponent.
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// The inverse of `expected_score`.
+fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+// The Elo difference implied by a score, with the margin of its 95% confidence interval.
+fn elo_estimate(score: &Score) -> (f64, f64) {
+    let (mean, variance) = score_distribution(score);
+
+    if mean <= 0.0 || mean >= 1.0 {
+        return (elo_from_score(mean), f64::INFINITY);
+    }
+
+    let deviation = 1.96 * (variance / score.games() as f64).sqrt();
+
+    let low = elo_from_score((mean - deviation).max(f64::MIN_POSITIVE));
+    let high = elo_from_score((mean + deviation).min(1.0 - f64::EPSILON));
+
+    (elo_from_score(mean), (high - low) / 2.0)
+}
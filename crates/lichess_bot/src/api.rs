@@ -0,0 +1,130 @@
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+use serde_json::Value;
+
+const LICHESS_URL: &str = "https://lichess.org";
+
+// Lichess sends a blank line every few seconds to keep its streams open, so one which stays quiet
+// for this long has dropped.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a request to Lichess failed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ApiError {
+    // Lichess refused the request with this HTTP status, such as 401 for a bad token.
+    Status(u16),
+    // Lichess couldn't be reached, or its response couldn't be read.
+    Transport(String),
+}
+
+impl ApiError {
+    /// Whether retrying the request could help, which it can't if Lichess rejected it as invalid.
+    pub fn is_temporary(&self) -> bool {
+        !matches!(self, ApiError::Status(status) if *status < 500)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Status(status) => write!(f, "HTTP status {}", status),
+            ApiError::Transport(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ureq::Error> for ApiError {
+    fn from(error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::Status(status, _) => ApiError::Status(status),
+            ureq::Error::Transport(transport) => ApiError::Transport(transport.to_string()),
+        }
+    }
+}
+
+/// A client for the parts of the Lichess Bot API the bot uses, authenticated with a bot account's
+/// API token.
+#[derive(Clone)]
+pub struct Lichess {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl Lichess {
+    pub fn new(token: String) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_read(STREAM_TIMEOUT)
+                .build(),
+            token,
+        }
+    }
+
+    /// The ID of the account the token belongs to, which is its username in lower case.
+    pub fn account_id(&self) -> Result<String, ApiError> {
+        let account: Value = serde_json::from_reader(self.get("/api/account")?.into_reader())
+            .map_err(|error| ApiError::Transport(error.to_string()))?;
+
+        Ok(account["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Streams the account's incoming challenges and the starts and ends of its games.
+    pub fn stream_events(&self) -> Result<impl Iterator<Item = Value>, ApiError> {
+        Ok(ndjson(self.get("/api/stream/event")?))
+    }
+
+    /// Streams a game, starting with its full state and then the state after each move.
+    pub fn stream_game(&self, game_id: &str) -> Result<impl Iterator<Item = Value>, ApiError> {
+        Ok(ndjson(
+            self.get(&format!("/api/bot/game/stream/{}", game_id))?,
+        ))
+    }
+
+    pub fn accept_challenge(&self, challenge_id: &str) -> Result<(), ApiError> {
+        self.post(&format!("/api/challenge/{}/accept", challenge_id), &[])
+    }
+
+    /// Declines a challenge, giving one of the reasons Lichess knows, such as `variant` or `later`.
+    pub fn decline_challenge(&self, challenge_id: &str, reason: &str) -> Result<(), ApiError> {
+        self.post(
+            &format!("/api/challenge/{}/decline", challenge_id),
+            &[("reason", reason)],
+        )
+    }
+
+    /// Plays a move, given in coordinate notation.
+    pub fn make_move(&self, game_id: &str, notation: &str) -> Result<(), ApiError> {
+        self.post(&format!("/api/bot/game/{}/move/{}", game_id, notation), &[])
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, ApiError> {
+        self.agent
+            .get(&format!("{}{}", LICHESS_URL, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(ApiError::from)
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<(), ApiError> {
+        self.agent
+            .post(&format!("{}{}", LICHESS_URL, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_form(form)?;
+
+        Ok(())
+    }
+}
+
+// Reads a stream of newline-delimited JSON until it ends or fails, skipping the blank lines sent
+// to keep it open.
+fn ndjson(response: ureq::Response) -> impl Iterator<Item = Value> {
+    BufReader::new(response.into_reader())
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+}
@@ -0,0 +1,145 @@
+use std::thread;
+use std::time::Duration;
+
+use chess_engine::{Engine, Move, Search, SearchLimits, Side, TimeLimits};
+use serde_json::Value;
+
+use crate::api::Lichess;
+use crate::RECONNECT_DELAY;
+
+// Time lost on every move to network delays, which is kept back from the clock.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(300);
+
+// How long to think in games without a clock.
+const UNTIMED_MOVE_TIME: Duration = Duration::from_secs(5);
+
+/// Plays a game until it ends, answering each of the opponent's moves with the engine's. The game
+/// stream is reopened if it drops before the game is over.
+pub fn play(lichess: &Lichess, account_id: &str, game_id: &str) {
+    let mut search = Search::new();
+    let mut side = None;
+    let mut initial_fen = String::new();
+
+    // The number of moves in the position last answered, so that a state sent again after
+    // reconnecting isn't answered twice.
+    let mut answered = None;
+
+    loop {
+        let events = match lichess.stream_game(game_id) {
+            Ok(events) => events,
+            Err(error) if !error.is_temporary() => {
+                eprintln!("game {}: can't stream it: {}", game_id, error);
+                return;
+            }
+            Err(error) => {
+                eprintln!("game {}: {}", game_id, error);
+                thread::sleep(RECONNECT_DELAY);
+                continue;
+            }
+        };
+
+        for event in events {
+            let state = match event["type"].as_str() {
+                Some("gameFull") => {
+                    side = if event["white"]["id"] == account_id {
+                        Some(Side::White)
+                    } else if event["black"]["id"] == account_id {
+                        Some(Side::Black)
+                    } else {
+                        None
+                    };
+                    initial_fen = event["initialFen"]
+                        .as_str()
+                        .unwrap_or("startpos")
+                        .to_string();
+
+                    &event["state"]
+                }
+                Some("gameState") => &event,
+                _ => continue,
+            };
+
+            let status = state["status"].as_str().unwrap_or_default();
+
+            if !matches!(status, "created" | "started") {
+                eprintln!("game {}: {}", game_id, status);
+                return;
+            }
+
+            let Some(side) = side else {
+                eprintln!("game {}: not playing in it", game_id);
+                return;
+            };
+
+            let moves = state["moves"].as_str().unwrap_or_default();
+            let Some(position) = replay(&initial_fen, moves) else {
+                eprintln!("game {}: couldn't follow the moves '{}'", game_id, moves);
+                return;
+            };
+
+            let played = moves.split_whitespace().count();
+
+            if position.side_to_move() != side || answered == Some(played) {
+                continue;
+            }
+
+            let limits = SearchLimits {
+                time: time_limits(state),
+                ..Default::default()
+            };
+
+            let Some(best_move) = search.best_move_in_game(&position, &limits).best_move else {
+                continue;
+            };
+
+            match lichess.make_move(game_id, &best_move.to_string()) {
+                Ok(()) => answered = Some(played),
+                Err(error) => eprintln!("game {}: couldn't play {}: {}", game_id, best_move, error),
+            }
+        }
+
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+// The position reached by playing moves in coordinate notation from a FEN, or from the initial
+// position given `startpos`.
+fn replay(initial_fen: &str, moves: &str) -> Option<Engine> {
+    let mut position = Engine::default();
+
+    if initial_fen == "startpos" {
+        position.set_initial_position();
+    } else {
+        position.set_fen(initial_fen).ok()?;
+    }
+
+    for notation in moves.split_whitespace() {
+        position.try_make_move(Move::from_uci_str(notation)?).ok()?;
+    }
+
+    Some(position)
+}
+
+// The clocks in a game state, which Lichess gives in milliseconds.
+fn time_limits(state: &Value) -> TimeLimits {
+    let millis = |name: &str| state[name].as_u64().map(Duration::from_millis);
+
+    let limits = TimeLimits {
+        white_time: millis("wtime"),
+        black_time: millis("btime"),
+        white_increment: millis("winc"),
+        black_increment: millis("binc"),
+        move_overhead: MOVE_OVERHEAD,
+        ..Default::default()
+    };
+
+    // Correspondence and unlimited games have no clock.
+    if limits.is_empty() {
+        TimeLimits {
+            move_time: Some(UNTIMED_MOVE_TIME),
+            ..Default::default()
+        }
+    } else {
+        limits
+    }
+}
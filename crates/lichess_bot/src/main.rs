@@ -0,0 +1,127 @@
+//! Plays on Lichess as a bot account: `LICHESS_BOT_TOKEN=<token> lichess-bot`. The token must
+//! belong to an account upgraded to a bot, with the `bot:play` scope.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::api::Lichess;
+
+mod api;
+mod game;
+
+// The most games played at once. Challenges are declined while this many are going.
+const MAX_GAMES: usize = 2;
+
+// How long to wait before reopening a stream which dropped, or retrying a failed request.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+fn main() {
+    let Ok(token) = std::env::var("LICHESS_BOT_TOKEN") else {
+        eprintln!("set LICHESS_BOT_TOKEN to the bot account's API token");
+        std::process::exit(1);
+    };
+
+    let lichess = Lichess::new(token);
+
+    let account_id = loop {
+        match lichess.account_id() {
+            Ok(id) => break id,
+            Err(error) if !error.is_temporary() => {
+                eprintln!("the token was rejected: {}", error);
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("couldn't connect: {}", error);
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    };
+
+    eprintln!("connected as {}", account_id);
+
+    // The IDs of the games being played, each on a thread of its own.
+    let games = Arc::new(Mutex::new(HashSet::new()));
+
+    loop {
+        match lichess.stream_events() {
+            Ok(events) => {
+                for event in events {
+                    handle_event(&lichess, &account_id, &games, &event);
+                }
+
+                eprintln!("event stream closed, reconnecting");
+            }
+            Err(error) => eprintln!("couldn't stream events: {}", error),
+        }
+
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+fn handle_event(
+    lichess: &Lichess,
+    account_id: &str,
+    games: &Arc<Mutex<HashSet<String>>>,
+    event: &Value,
+) {
+    match event["type"].as_str() {
+        Some("challenge") => {
+            let challenge = &event["challenge"];
+            let id = challenge["id"].as_str().unwrap_or_default();
+
+            // Challenges the bot sent itself are reported too.
+            if challenge["challenger"]["id"] == account_id {
+                return;
+            }
+
+            let decline_reason = if !matches!(
+                challenge["variant"]["key"].as_str(),
+                Some("standard" | "fromPosition")
+            ) {
+                Some("variant")
+            } else if games.lock().unwrap().len() >= MAX_GAMES {
+                Some("later")
+            } else {
+                None
+            };
+
+            let response = match decline_reason {
+                Some(reason) => lichess.decline_challenge(id, reason),
+                None => lichess.accept_challenge(id),
+            };
+
+            match response {
+                Ok(()) if decline_reason.is_none() => eprintln!("accepted challenge {}", id),
+                Ok(()) => eprintln!("declined challenge {}", id),
+                Err(error) => eprintln!("couldn't answer challenge {}: {}", id, error),
+            }
+        }
+        Some("gameStart") => {
+            let Some(game_id) = event["game"]["gameId"].as_str() else {
+                return;
+            };
+
+            // Games still going are announced again whenever the event stream reconnects.
+            if !games.lock().unwrap().insert(game_id.to_string()) {
+                return;
+            }
+
+            eprintln!("game {}: started", game_id);
+
+            let lichess = lichess.clone();
+            let account_id = account_id.to_string();
+            let game_id = game_id.to_string();
+            let games = Arc::clone(games);
+
+            thread::spawn(move || {
+                game::play(&lichess, &account_id, &game_id);
+                games.lock().unwrap().remove(&game_id);
+            });
+        }
+        _ => {}
+    }
+}
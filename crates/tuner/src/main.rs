@@ -0,0 +1,308 @@
+//! Tunes the evaluation term weights with Texel's method: the evaluation of each position is
+//! mapped to an expected result with a sigmoid, and the weights are adjusted by gradient descent
+//! to minimise the squared error against the results of the games the positions came from.
+//!
+//! Usage: `tuner <positions> [output]`
+//!
+//! Each line of the positions file holds a FEN, with or without its move clocks, followed by the
+//! game result as `1-0`, `0-1` or `1/2-1/2`, or as White's score such as `[0.5]`. Quotes and EPD
+//! opcodes around the result are ignored, so `... c9 "1-0";` works too. The positions should be
+//! quiet, since the static evaluation knows nothing of pending captures. The tuned weights are
+//! written as a `TERM_WEIGHTS` constant to paste into the evaluation.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::process::ExitCode;
+
+use chess_engine::{Engine, Side, MAX_PHASE, SCALE_NORMAL, TERM_WEIGHTS};
+
+// The number of full passes over the positions made by gradient descent.
+const EPOCHS: usize = 2000;
+
+// Adam optimiser parameters. The learning rate is in percentage points of weight.
+const LEARNING_RATE: f64 = 0.5;
+const BETA_1: f64 = 0.9;
+const BETA_2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+// The number of evaluation terms weighted by `TERM_WEIGHTS`.
+const TERM_COUNT: usize = TERM_WEIGHTS.len();
+
+// A position reduced to what the evaluation is made of: the difference between the sides in each
+// term, before weighting, the game phase and the endgame scale.
+struct Sample {
+    terms: [(f64, f64); TERM_COUNT],
+    phase: f64,
+    scale: f64,
+    result: f64,
+}
+
+type Weights = [(f64, f64); TERM_COUNT];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+
+    let Some(positions_path) = args.get(1) else {
+        eprintln!("usage: {} <positions> [output]", args[0]);
+        return ExitCode::FAILURE;
+    };
+
+    let samples = match load_samples(positions_path) {
+        Ok(samples) if !samples.is_empty() => samples,
+        Ok(_) => {
+            eprintln!("no positions found in {}", positions_path);
+            return ExitCode::FAILURE;
+        }
+        Err(error) => {
+            eprintln!("failed to read {}: {}", positions_path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!("loaded {} positions", samples.len());
+
+    let mut weights: Weights =
+        TERM_WEIGHTS.map(|(middlegame, endgame)| (middlegame as f64, endgame as f64));
+
+    let k = find_scaling_constant(&samples, &weights);
+    eprintln!(
+        "K = {:.4}, initial error {:.6}",
+        k,
+        mean_error(&samples, &weights, k)
+    );
+
+    tune(&samples, &mut weights, k);
+
+    let output = format_weights(&weights);
+
+    match args.get(2) {
+        Some(output_path) => {
+            if let Err(error) =
+                File::create(output_path).and_then(|mut file| file.write_all(output.as_bytes()))
+            {
+                eprintln!("failed to write {}: {}", output_path, error);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", output),
+    }
+
+    ExitCode::SUCCESS
+}
+
+// Reads and evaluates every labelled position in the file, skipping lines which can't be parsed.
+fn load_samples(path: &str) -> std::io::Result<Vec<Sample>> {
+    let mut samples = vec![];
+    let mut engine = Engine::default();
+
+    for (line_number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some((fen, result)) = parse_line(&line) else {
+            eprintln!("line {}: no result found, skipping", line_number + 1);
+            continue;
+        };
+
+        if let Err(error) = engine.set_fen(&fen) {
+            eprintln!("line {}: {}, skipping", line_number + 1, error);
+            continue;
+        }
+
+        let trace = engine.evaluate_verbose();
+
+        samples.push(Sample {
+            terms: trace.terms().map(|(_, term)| {
+                let white = term[Side::White.val()];
+                let black = term[Side::Black.val()];
+
+                ((white.0 - black.0) as f64, (white.1 - black.1) as f64)
+            }),
+            phase: trace.phase as f64,
+            scale: trace.scale as f64 / SCALE_NORMAL as f64,
+            result,
+        });
+    }
+
+    Ok(samples)
+}
+
+// Splits a line into its FEN and the result from White's point of view.
+fn parse_line(line: &str) -> Option<(String, f64)> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    if tokens.len() < 5 {
+        return None;
+    }
+
+    // The placement, side to move, castling rights and en passant square are always present, and
+    // may be followed by the two move clocks.
+    let clocks = tokens[4..]
+        .iter()
+        .take(2)
+        .take_while(|token| token.parse::<u32>().is_ok())
+        .count();
+    let fen_length = 4 + clocks;
+
+    let result = tokens[fen_length..].iter().find_map(|token| {
+        match token.trim_matches(|c| matches!(c, '"' | '[' | ']' | ';' | ',')) {
+            "1-0" => Some(1.0),
+            "0-1" => Some(0.0),
+            "1/2-1/2" => Some(0.5),
+            score => score
+                .parse::<f64>()
+                .ok()
+                .filter(|s| (0.0..=1.0).contains(s)),
+        }
+    })?;
+
+    Some((tokens[..fen_length].join(" "), result))
+}
+
+// The evaluation of a sample with the given weights, from White's perspective, as the engine
+// computes it but without rounding.
+fn evaluate(sample: &Sample, weights: &Weights) -> f64 {
+    let max_phase = MAX_PHASE as f64;
+
+    let (middlegame, endgame) =
+        sample
+            .terms
+            .iter()
+            .zip(weights)
+            .fold((0.0, 0.0), |total, (term, weight)| {
+                (
+                    total.0 + term.0 * weight.0 / 100.0,
+                    total.1 + term.1 * weight.1 / 100.0,
+                )
+            });
+
+    (middlegame * sample.phase + endgame * sample.scale * (max_phase - sample.phase)) / max_phase
+}
+
+// Maps an evaluation in centipawns to an expected score between 0 and 1.
+fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+fn mean_error(samples: &[Sample], weights: &Weights, k: f64) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|sample| (sample.result - sigmoid(evaluate(sample, weights), k)).powi(2))
+        .sum();
+
+    total / samples.len() as f64
+}
+
+// Finds the sigmoid scaling constant which best fits the current weights, by golden-section
+// search. Tuning the weights with this held fixed keeps them in centipawn scale.
+fn find_scaling_constant(samples: &[Sample], weights: &Weights) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, 5.0);
+
+    for _ in 0..50 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+
+        if mean_error(samples, weights, a) < mean_error(samples, weights, b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+// Minimises the error over the weights with the Adam optimiser.
+fn tune(samples: &[Sample], weights: &mut Weights, k: f64) {
+    let max_phase = MAX_PHASE as f64;
+
+    let mut first_moments = [(0.0, 0.0); TERM_COUNT];
+    let mut second_moments = [(0.0, 0.0); TERM_COUNT];
+
+    for epoch in 1..=EPOCHS {
+        let mut gradient = [(0.0, 0.0); TERM_COUNT];
+
+        for sample in samples {
+            let expected = sigmoid(evaluate(sample, weights), k);
+
+            // The derivative of the squared error with respect to the evaluation.
+            let error_slope = -2.0
+                * (sample.result - expected)
+                * expected
+                * (1.0 - expected)
+                * k
+                * std::f64::consts::LN_10
+                / 400.0;
+
+            for (term, slope) in sample.terms.iter().zip(&mut gradient) {
+                slope.0 += error_slope * term.0 * sample.phase / max_phase / 100.0;
+                slope.1 += error_slope * term.1 * sample.scale * (max_phase - sample.phase)
+                    / max_phase
+                    / 100.0;
+            }
+        }
+
+        let count = samples.len() as f64;
+
+        for i in 0..TERM_COUNT {
+            let slope = (gradient[i].0 / count, gradient[i].1 / count);
+
+            for (weight, slope, first, second) in [
+                (
+                    &mut weights[i].0,
+                    slope.0,
+                    &mut first_moments[i].0,
+                    &mut second_moments[i].0,
+                ),
+                (
+                    &mut weights[i].1,
+                    slope.1,
+                    &mut first_moments[i].1,
+                    &mut second_moments[i].1,
+                ),
+            ] {
+                *first = BETA_1 * *first + (1.0 - BETA_1) * slope;
+                *second = BETA_2 * *second + (1.0 - BETA_2) * slope * slope;
+
+                let first_corrected = *first / (1.0 - BETA_1.powi(epoch as i32));
+                let second_corrected = *second / (1.0 - BETA_2.powi(epoch as i32));
+
+                *weight -= LEARNING_RATE * first_corrected / (second_corrected.sqrt() + EPSILON);
+            }
+        }
+
+        if epoch % 100 == 0 {
+            eprintln!(
+                "epoch {}: error {:.6}",
+                epoch,
+                mean_error(samples, weights, k)
+            );
+        }
+    }
+}
+
+// Formats the weights as a constant to paste over `TERM_WEIGHTS`.
+fn format_weights(weights: &Weights) -> String {
+    let names = Engine::default()
+        .evaluate_verbose()
+        .terms()
+        .map(|(name, _)| name);
+
+    let mut output = format!("pub const TERM_WEIGHTS: [(i32, i32); {}] = [\n", TERM_COUNT);
+
+    for (name, weight) in names.iter().zip(weights) {
+        output += &format!(
+            "    ({}, {}), // {}\n",
+            weight.0.round() as i32,
+            weight.1.round() as i32,
+            name
+        );
+    }
+
+    output += "];\n";
+    output
+}